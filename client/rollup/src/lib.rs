@@ -0,0 +1,457 @@
+//! # Paraxiom Rollup Client
+//!
+//! A native counterpart of the `SubstrateRollupClient` running in the Phat Contracts. It reads the
+//! queues and the kv-store of a name of the offchain rollup anchor over RPC, builds the rollup
+//! transactions with `pink_kv_session`, signs and submits them. It allows a plain service to act
+//! as the submitter of a name, e.g. for testing without a Phat worker.
+//!
+//! ```ignore
+//! let mut client = RollupClient::new("http://127.0.0.1:9933", name, b"_queue/")?;
+//! if let Some(message) = client.session().pop()? {
+//!     client.action(Action::Reply(process(message).try_into()?));
+//! }
+//! if let Some(tx) = client.commit()? {
+//!     tx.submit(&submitter, nonce)?;
+//! }
+//! ```
+//!
+//! The kv-store is read at the best block when the client is created. The transaction carries
+//! the read values as conditions, so it's rejected if the state changed in between.
+
+use std::sync::Arc;
+
+use codec::{Decode, Encode};
+use paraxiom_runtime::{AccountId, Runtime, RuntimeCall, SignedExtra, UncheckedExtrinsic};
+use phat_offchain_rollup::types::{Action, Cond, RollupTx};
+use pink_kv_session::{
+    rollup::{rollup, VersionLayout},
+    traits::{BumpVersion, KvSnapshot, QueueIndexCodec},
+    ReadTracker, Session,
+};
+use sp_core::{sr25519, Pair, H256};
+use sp_runtime::generic::{Era, SignedPayload};
+
+mod rpc;
+
+pub use rpc::Rpc;
+
+/// The errors of the rollup client
+#[derive(Debug)]
+pub enum Error {
+    /// The RPC request failed
+    Rpc(String),
+    /// The block was not found
+    BlockNotFound,
+    /// The kv session failed
+    Session(pink_kv_session::Error),
+    /// A key, value or action exceeds the size limits of the anchor
+    TooLarge,
+}
+
+impl From<pink_kv_session::Error> for Error {
+    fn from(err: pink_kv_session::Error) -> Self {
+        Self::Session(err)
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// The kv-store of a name at a block, read over RPC
+pub struct RpcSnapshot {
+    rpc: Arc<Rpc>,
+    name: H256,
+    at: H256,
+}
+
+impl KvSnapshot for RpcSnapshot {
+    fn snapshot_id(&self) -> pink_kv_session::Result<Vec<u8>> {
+        Ok(self.at.encode())
+    }
+
+    fn get(&self, key: &[u8]) -> pink_kv_session::Result<Option<Vec<u8>>> {
+        self.rpc.get_state(self.name, key, self.at).map_err(|err| {
+            log::error!("Failed to read the rollup state: {err}");
+            pink_kv_session::Error::FetchDataFailed
+        })
+    }
+}
+
+impl BumpVersion for RpcSnapshot {
+    fn bump_version(&self, version: Option<Vec<u8>>) -> pink_kv_session::Result<Vec<u8>> {
+        bump_u32_version(version)
+    }
+}
+
+/// The versions of the kv-store entries are SCALE-encoded `u32`, starting from 1
+fn bump_u32_version(version: Option<Vec<u8>>) -> pink_kv_session::Result<Vec<u8>> {
+    match version {
+        Some(v) => {
+            let ov =
+                u32::decode(&mut &v[..]).or(Err(pink_kv_session::Error::FailedToDecode))?;
+            Ok((ov + 1).encode())
+        }
+        None => Ok(1_u32.encode()),
+    }
+}
+
+/// The queue indices are SCALE-encoded `u32`, as the anchor pallet stores them
+pub struct ScaleCodec;
+
+impl QueueIndexCodec for ScaleCodec {
+    fn encode(number: u32) -> Vec<u8> {
+        number.encode()
+    }
+
+    fn decode(raw: impl AsRef<[u8]>) -> pink_kv_session::Result<u32> {
+        Decode::decode(&mut raw.as_ref()).or(Err(pink_kv_session::Error::FailedToDecode))
+    }
+}
+
+/// The kv session of a name, over the kv-store snapshot `S`
+pub type RollupSession<S = RpcSnapshot> = Session<S, ReadTracker, ScaleCodec>;
+
+/// Builds the rollup transaction of a session, with the extra conditions and actions
+///
+/// Returns `None` if there is nothing to submit.
+pub fn build_rollup_tx<S: KvSnapshot + BumpVersion>(
+    session: RollupSession<S>,
+    extra_conds: Vec<Cond>,
+    extra_actions: Vec<Action>,
+) -> Result<Option<RollupTx>> {
+    let (tx, kvdb) = session.commit();
+    let tx = rollup(
+        &kvdb,
+        tx,
+        VersionLayout::Standalone {
+            key_postfix: "_ver".into(),
+        },
+    )?;
+    if !tx.has_updates() && extra_actions.is_empty() {
+        // We don't have to submit it if there are no updates
+        return Ok(None);
+    }
+
+    let mut conds = tx
+        .conditions
+        .into_iter()
+        .map(|(k, v)| {
+            Ok(Cond::Eq(
+                k.try_into().or(Err(Error::TooLarge))?,
+                v.map(|v| v.try_into().or(Err(Error::TooLarge))).transpose()?,
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    conds.extend(extra_conds);
+    let updates = tx
+        .updates
+        .into_iter()
+        .map(|(k, v)| {
+            Ok((
+                k.try_into().or(Err(Error::TooLarge))?,
+                v.map(|v| v.try_into().or(Err(Error::TooLarge))).transpose()?,
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let mut actions = extra_actions
+        .into_iter()
+        .map(|action| action.encode().try_into().or(Err(Error::TooLarge)))
+        .collect::<Result<Vec<_>>>()?;
+    if let Some(head) = tx.queue_head {
+        actions.push(
+            Action::SetQueueHead(head)
+                .encode()
+                .try_into()
+                .or(Err(Error::TooLarge))?,
+        );
+    }
+
+    Ok(Some(RollupTx {
+        conds,
+        actions,
+        updates,
+    }))
+}
+
+/// A client building a rollup transaction of a name
+pub struct RollupClient {
+    rpc: Arc<Rpc>,
+    name: H256,
+    session: RollupSession,
+    conds: Vec<Cond>,
+    actions: Vec<Action>,
+}
+
+impl RollupClient {
+    /// Connects to the node at `rpc_url` and opens a session on the name at the best block
+    ///
+    /// `queue_prefix` is the prefix of the queue popped by the session (`Config::QueuePrefix` of
+    /// the anchor for the queue 0).
+    pub fn new(rpc_url: &str, name: H256, queue_prefix: &[u8]) -> Result<Self> {
+        Self::with_rpc(Arc::new(Rpc::new(rpc_url)?), name, queue_prefix)
+    }
+
+    /// Opens a session on the name at the best block, over an existing connection
+    pub fn with_rpc(rpc: Arc<Rpc>, name: H256, queue_prefix: &[u8]) -> Result<Self> {
+        let at = rpc.best_hash()?;
+        let snapshot = RpcSnapshot {
+            rpc: rpc.clone(),
+            name,
+            at,
+        };
+        let session = Session::new(snapshot, ReadTracker::new(), queue_prefix)?;
+        Ok(Self {
+            rpc,
+            name,
+            session,
+            conds: Vec::new(),
+            actions: Vec::new(),
+        })
+    }
+
+    /// Returns the kv session to read and write the kv-store and to pop the queue
+    pub fn session(&mut self) -> &mut RollupSession {
+        &mut self.session
+    }
+
+    /// Adds a condition to the transaction, on top of the ones tracked by the session
+    pub fn cond(&mut self, cond: Cond) {
+        self.conds.push(cond);
+    }
+
+    /// Adds an action to the transaction
+    pub fn action(&mut self, action: Action) {
+        self.actions.push(action);
+    }
+
+    /// Builds the rollup transaction
+    ///
+    /// Returns `None` if there is nothing to submit.
+    pub fn commit(self) -> Result<Option<SubmittableRollupTx>> {
+        let tx = build_rollup_tx(self.session, self.conds, self.actions)?;
+        Ok(tx.map(|tx| SubmittableRollupTx {
+            rpc: self.rpc,
+            name: self.name,
+            tx,
+        }))
+    }
+}
+
+/// A rollup transaction ready to be submitted
+pub struct SubmittableRollupTx {
+    rpc: Arc<Rpc>,
+    name: H256,
+    tx: RollupTx,
+}
+
+impl SubmittableRollupTx {
+    /// Returns the rollup transaction
+    pub fn tx(&self) -> &RollupTx {
+        &self.tx
+    }
+
+    /// Signs the transaction with the submitter key of the name and submits it
+    ///
+    /// `nonce` is an arbitrary number echoed in the `RollupExecuted` event. Returns the hash of the
+    /// submitted extrinsic.
+    pub fn submit(&self, signer: &sr25519::Pair, nonce: u128) -> Result<H256> {
+        let call = RuntimeCall::PhatRollupAnchor(phat_offchain_rollup::anchor::Call::rollup {
+            name: self.name,
+            tx: self.tx.clone(),
+            nonce,
+        });
+        let extrinsic = self.sign(signer, call)?;
+        self.rpc.submit_extrinsic(extrinsic.encode())
+    }
+
+    fn sign(&self, signer: &sr25519::Pair, call: RuntimeCall) -> Result<UncheckedExtrinsic> {
+        let account = AccountId::from(signer.public());
+        let account_nonce = self.rpc.account_nonce(&account)?;
+        let version = self.rpc.runtime_version()?;
+        let genesis_hash = self.rpc.genesis_hash()?;
+
+        let extra: SignedExtra = (
+            frame_system::CheckNonZeroSender::<Runtime>::new(),
+            frame_system::CheckSpecVersion::<Runtime>::new(),
+            frame_system::CheckTxVersion::<Runtime>::new(),
+            frame_system::CheckGenesis::<Runtime>::new(),
+            frame_system::CheckEra::<Runtime>::from(Era::Immortal),
+            frame_system::CheckNonce::<Runtime>::from(account_nonce),
+            frame_system::CheckWeight::<Runtime>::new(),
+            pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(0),
+        );
+        let additional = (
+            (),
+            version.spec_version,
+            version.transaction_version,
+            genesis_hash,
+            genesis_hash,
+            (),
+            (),
+            (),
+        );
+        let payload = SignedPayload::from_raw(call.clone(), extra.clone(), additional);
+        let signature = payload.using_encoded(|payload| signer.sign(payload));
+        Ok(UncheckedExtrinsic::new_signed(
+            call,
+            account.into(),
+            signature.into(),
+            extra,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use frame_support::assert_ok;
+    use paraxiom_runtime::{PhatRollupAnchor, RuntimeOrigin, System, UNIT};
+    use phat_offchain_rollup::anchor::Error as AnchorError;
+    use pink_kv_session::traits::{KvSession, QueueSession};
+
+    const NAME: H256 = H256([1u8; 32]);
+    const SUBMITTER: AccountId = AccountId::new([1u8; 32]);
+
+    /// The kv-store of the name, read from the runtime storage of the test externalities
+    struct RuntimeSnapshot;
+
+    impl KvSnapshot for RuntimeSnapshot {
+        fn snapshot_id(&self) -> pink_kv_session::Result<Vec<u8>> {
+            Ok(Vec::new())
+        }
+
+        fn get(&self, key: &[u8]) -> pink_kv_session::Result<Option<Vec<u8>>> {
+            Ok(PhatRollupAnchor::get_state(&NAME, key))
+        }
+    }
+
+    impl BumpVersion for RuntimeSnapshot {
+        fn bump_version(&self, version: Option<Vec<u8>>) -> pink_kv_session::Result<Vec<u8>> {
+            bump_u32_version(version)
+        }
+    }
+
+    fn new_test_ext() -> sp_io::TestExternalities {
+        let mut t = frame_system::GenesisConfig::default()
+            .build_storage::<Runtime>()
+            .unwrap();
+        pallet_balances::GenesisConfig::<Runtime> {
+            balances: vec![(SUBMITTER, 1_000 * UNIT)],
+        }
+        .assimilate_storage(&mut t)
+        .unwrap();
+        let mut ext = sp_io::TestExternalities::new(t);
+        ext.execute_with(|| {
+            System::set_block_number(1);
+            assert_ok!(PhatRollupAnchor::claim_name(
+                RuntimeOrigin::signed(SUBMITTER),
+                NAME,
+                None
+            ));
+        });
+        ext
+    }
+
+    fn open_session() -> RollupSession<RuntimeSnapshot> {
+        Session::new(
+            RuntimeSnapshot,
+            ReadTracker::new(),
+            &PhatRollupAnchor::queue_prefix(0),
+        )
+        .unwrap()
+    }
+
+    fn submit(tx: RollupTx, nonce: u128) -> sp_runtime::DispatchResult {
+        PhatRollupAnchor::rollup(RuntimeOrigin::signed(SUBMITTER), NAME, tx, nonce)
+    }
+
+    #[test]
+    fn nothing_to_submit() {
+        new_test_ext().execute_with(|| {
+            assert_eq!(build_rollup_tx(open_session(), vec![], vec![]).unwrap(), None);
+        });
+    }
+
+    #[test]
+    fn round_trip_works() {
+        new_test_ext().execute_with(|| {
+            assert_ok!(PhatRollupAnchor::push_message(
+                &NAME,
+                0,
+                b"ping".to_vec().try_into().unwrap()
+            ));
+            assert_ok!(PhatRollupAnchor::push_message(
+                &NAME,
+                0,
+                b"pong".to_vec().try_into().unwrap()
+            ));
+
+            // Pop a message, write the kv-store and reply
+            let mut session = open_session();
+            assert_eq!(session.pop().unwrap(), Some(b"ping".to_vec()));
+            session.put(b"counter", 1u32.encode());
+            let reply = Action::EmitEvent(b"done".to_vec().try_into().unwrap());
+            let tx = build_rollup_tx(session, vec![], vec![reply])
+                .unwrap()
+                .expect("has updates");
+            assert_ok!(submit(tx, 1));
+            assert_eq!(PhatRollupAnchor::queue_head(&NAME, 0), 1);
+            assert_eq!(
+                PhatRollupAnchor::get_state(&NAME, b"counter"),
+                Some(1u32.encode())
+            );
+
+            // The next session continues from the committed state
+            let mut session = open_session();
+            assert_eq!(session.pop().unwrap(), Some(b"pong".to_vec()));
+            assert_eq!(session.get(b"counter").unwrap(), Some(1u32.encode()));
+            session.put(b"counter", 2u32.encode());
+            let tx = build_rollup_tx(session, vec![], vec![])
+                .unwrap()
+                .expect("has updates");
+            assert_ok!(submit(tx, 2));
+            assert_eq!(PhatRollupAnchor::queue_head(&NAME, 0), 2);
+            assert_eq!(PhatRollupAnchor::queue_len(&NAME, 0), 0);
+            assert_eq!(
+                PhatRollupAnchor::get_state(&NAME, b"counter"),
+                Some(2u32.encode())
+            );
+        });
+    }
+
+    #[test]
+    fn stale_tx_is_rejected() {
+        new_test_ext().execute_with(|| {
+            assert_ok!(PhatRollupAnchor::push_message(
+                &NAME,
+                0,
+                b"ping".to_vec().try_into().unwrap()
+            ));
+
+            // Two sessions read the same state
+            let mut first = open_session();
+            let mut second = open_session();
+            for session in [&mut first, &mut second] {
+                assert_eq!(session.pop().unwrap(), Some(b"ping".to_vec()));
+                session.put(b"counter", 1u32.encode());
+            }
+            let first = build_rollup_tx(first, vec![], vec![]).unwrap().unwrap();
+            let second = build_rollup_tx(second, vec![], vec![]).unwrap().unwrap();
+
+            // Only the first one applies, the message isn't processed twice
+            assert_ok!(submit(first, 1));
+            assert_eq!(
+                submit(second, 2),
+                Err(AnchorError::<Runtime>::CondNotMet.into())
+            );
+            assert_eq!(PhatRollupAnchor::queue_head(&NAME, 0), 1);
+        });
+    }
+}
@@ -0,0 +1,79 @@
+//! A blocking JSON-RPC connection to a Paraxiom node
+
+use jsonrpsee::{
+    core::{client::ClientT, params::ArrayParams},
+    http_client::{HttpClient, HttpClientBuilder},
+    rpc_params,
+};
+use paraxiom_runtime::{AccountId, Hash, Index};
+use serde::de::DeserializeOwned;
+use sp_core::{Bytes, H256};
+use sp_version::RuntimeVersion;
+
+use crate::{Error, Result};
+
+/// A blocking JSON-RPC connection to a Paraxiom node
+///
+/// The requests are driven by a private tokio runtime, so the connection must not be used from
+/// within an async context.
+pub struct Rpc {
+    runtime: tokio::runtime::Runtime,
+    client: HttpClient,
+}
+
+impl Rpc {
+    /// Connects to the HTTP RPC endpoint of a node
+    pub fn new(url: &str) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|err| Error::Rpc(err.to_string()))?;
+        let client = {
+            let _guard = runtime.enter();
+            HttpClientBuilder::default()
+                .build(url)
+                .map_err(|err| Error::Rpc(err.to_string()))?
+        };
+        Ok(Self { runtime, client })
+    }
+
+    fn call<R: DeserializeOwned>(&self, method: &str, params: ArrayParams) -> Result<R> {
+        self.runtime
+            .block_on(self.client.request(method, params))
+            .map_err(|err| Error::Rpc(err.to_string()))
+    }
+
+    /// Returns the hash of the best block
+    pub fn best_hash(&self) -> Result<Hash> {
+        self.call::<Option<Hash>>("chain_getBlockHash", rpc_params![])?
+            .ok_or(Error::BlockNotFound)
+    }
+
+    /// Returns the hash of the genesis block
+    pub fn genesis_hash(&self) -> Result<Hash> {
+        self.call::<Option<Hash>>("chain_getBlockHash", rpc_params![0u32])?
+            .ok_or(Error::BlockNotFound)
+    }
+
+    /// Returns the version of the runtime at the best block
+    pub fn runtime_version(&self) -> Result<RuntimeVersion> {
+        self.call("state_getRuntimeVersion", rpc_params![])
+    }
+
+    /// Returns the next nonce of the account, including the transactions in the pool
+    pub fn account_nonce(&self, account: &AccountId) -> Result<Index> {
+        self.call("system_accountNextIndex", rpc_params![account.to_string()])
+    }
+
+    /// Returns the value of a key in the kv-store of the name at the block `at`
+    pub fn get_state(&self, name: H256, key: &[u8], at: Hash) -> Result<Option<Vec<u8>>> {
+        let value: Option<Bytes> =
+            self.call("anchor_getState", rpc_params![name, Bytes(key.to_vec()), at])?;
+        Ok(value.map(|value| value.0))
+    }
+
+    /// Submits a SCALE-encoded extrinsic to the transaction pool, and returns its hash
+    pub fn submit_extrinsic(&self, extrinsic: Vec<u8>) -> Result<Hash> {
+        self.call("author_submitExtrinsic", rpc_params![Bytes(extrinsic)])
+    }
+}
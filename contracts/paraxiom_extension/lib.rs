@@ -0,0 +1,127 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! ink! bindings of the Paraxiom chain extension
+//!
+//! Contracts use [`ParaxiomEnvironment`] as their environment to call the oracle:
+//!
+//! ```ignore
+//! #[ink::contract(env = paraxiom_extension::ParaxiomEnvironment)]
+//! mod consumer {
+//!     // ...
+//!     #[ink(message)]
+//!     pub fn price(&self) -> Option<u128> {
+//!         self.env()
+//!             .extension()
+//!             .latest_value(b"BTC/USD".to_vec())
+//!             .and_then(|latest| latest.value)
+//!     }
+//! }
+//! ```
+
+use ink::env::{DefaultEnvironment, Environment};
+use ink::prelude::vec::Vec;
+
+/// The id of a request
+pub type RequestId = [u8; 32];
+
+/// The id of a message queue of the rollup anchor
+pub type QueueId = u8;
+
+/// The latest response received for a topic
+#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct LatestValue {
+    pub request_id: RequestId,
+    pub phat_contract_id: [u8; 32],
+    /// The value of the response, if it's a (possibly aggregated) number with 12 decimals
+    pub value: Option<u128>,
+    pub timestamp_ms: u64,
+    /// The block the response was received at
+    pub block_number: u32,
+}
+
+/// A response to a request
+#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct ResultInfo {
+    pub phat_contract_id: [u8; 32],
+    /// The raw response data
+    pub data: Vec<u8>,
+    /// The value of the response, if it's a (possibly aggregated) number with 12 decimals
+    pub value: Option<u128>,
+}
+
+/// The Paraxiom chain extension
+#[ink::chain_extension]
+pub trait ParaxiomExtension {
+    type ErrorCode = ParaxiomError;
+
+    /// Returns the latest value of the topic (feed key)
+    #[ink(extension = 0x5058_0001, handle_status = false)]
+    fn latest_value(topic: Vec<u8>) -> Option<LatestValue>;
+
+    /// Returns the responses received for the request
+    #[ink(extension = 0x5058_0002, handle_status = false)]
+    fn get_result(request_id: RequestId) -> Vec<ResultInfo>;
+
+    /// Requests the feed `topic` registered by the calling contract, and returns the request id
+    #[ink(extension = 0x5058_0003)]
+    fn request(topic: Vec<u8>, queue: QueueId, nonce: u128) -> RequestId;
+
+    /// Registers the feed `key` owned by the calling contract, reserving the feed bond from the
+    /// contract's balance
+    #[ink(extension = 0x5058_0004)]
+    fn register_feed(
+        key: Vec<u8>,
+        url: Vec<u8>,
+        path: Vec<u8>,
+        secret_slot: Option<u32>,
+        body: Option<Vec<u8>>,
+    );
+}
+
+/// The errors returned by the Paraxiom chain extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum ParaxiomError {
+    /// The topic doesn't fit in a registry feed key
+    InvalidTopic,
+    /// The oracle rejected the request
+    RequestFailed,
+    /// A field of the feed exceeds the registry bounds
+    InvalidFeed,
+    /// The registry rejected the feed
+    RegisterFailed,
+    /// Unknown status code
+    Unknown(u32),
+}
+
+impl ink::env::chain_extension::FromStatusCode for ParaxiomError {
+    fn from_status_code(status_code: u32) -> Result<(), Self> {
+        match status_code {
+            0 => Ok(()),
+            1 => Err(Self::InvalidTopic),
+            2 => Err(Self::RequestFailed),
+            3 => Err(Self::InvalidFeed),
+            4 => Err(Self::RegisterFailed),
+            code => Err(Self::Unknown(code)),
+        }
+    }
+}
+
+/// The environment of the contracts on Paraxiom
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum ParaxiomEnvironment {}
+
+impl Environment for ParaxiomEnvironment {
+    const MAX_EVENT_TOPICS: usize = <DefaultEnvironment as Environment>::MAX_EVENT_TOPICS;
+
+    type AccountId = <DefaultEnvironment as Environment>::AccountId;
+    type Balance = <DefaultEnvironment as Environment>::Balance;
+    type Hash = <DefaultEnvironment as Environment>::Hash;
+    type Timestamp = <DefaultEnvironment as Environment>::Timestamp;
+    type BlockNumber = <DefaultEnvironment as Environment>::BlockNumber;
+
+    type ChainExtension = ParaxiomExtension;
+}
@@ -3,8 +3,8 @@ use paraxiom_runtime::{AccountId, AuraId, Signature, EXISTENTIAL_DEPOSIT};
 use sc_chain_spec::{ChainSpecExtension, ChainSpecGroup};
 use sc_service::ChainType;
 use serde::{Deserialize, Serialize};
-use sp_core::{sr25519, Pair, Public};
-use sp_runtime::traits::{IdentifyAccount, Verify};
+use sp_core::{sr25519, Pair, Public, H256};
+use sp_runtime::traits::{BlakeTwo256, Hash, IdentifyAccount, Verify};
 
 /// Specialized `ChainSpec` for the normal parachain runtime.
 pub type ChainSpec = sc_service::GenericChainSpec<paraxiom_runtime::GenesisConfig, Extensions>;
@@ -101,6 +101,7 @@ pub fn development_config() -> ChainSpec {
                     get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
                 ],
                 1000.into(),
+                Some(oracle_fallback_name()),
             )
         },
         Vec::new(),
@@ -156,6 +157,7 @@ pub fn local_testnet_config() -> ChainSpec {
                     get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
                 ],
                 1000.into(),
+                None,
             )
         },
         // Bootnodes
@@ -176,10 +178,17 @@ pub fn local_testnet_config() -> ChainSpec {
     )
 }
 
+/// The anchor name served by the off-chain worker of the development chain when no Phat contract
+/// is available
+pub fn oracle_fallback_name() -> H256 {
+    BlakeTwo256::hash(b"paraxiom/oracle-fallback")
+}
+
 fn testnet_genesis(
     invulnerables: Vec<(AccountId, AuraId)>,
     endowed_accounts: Vec<AccountId>,
     id: ParaId,
+    fallback_name: Option<H256>,
 ) -> paraxiom_runtime::GenesisConfig {
     paraxiom_runtime::GenesisConfig {
         system: paraxiom_runtime::SystemConfig {
@@ -202,7 +211,8 @@ fn testnet_genesis(
         },
         session: paraxiom_runtime::SessionConfig {
             keys: invulnerables
-                .into_iter()
+                .iter()
+                .cloned()
                 .map(|(acc, aura)| {
                     (
                         acc.clone(),                 // account id
@@ -222,5 +232,24 @@ fn testnet_genesis(
         },
         assets: Default::default(),
         democracy: Default::default(),
+        // The collators form the technical committee of the development chains
+        technical_committee: paraxiom_runtime::TechnicalCommitteeConfig {
+            members: invulnerables.iter().cloned().map(|(acc, _)| acc).collect(),
+            phantom: Default::default(),
+        },
+        // The collators run the off-chain workers feeding `para_oracle`
+        oracle_provider: paraxiom_runtime::OracleProviderConfig {
+            members: invulnerables
+                .iter()
+                .cloned()
+                .map(|(acc, _)| acc)
+                .collect::<Vec<_>>()
+                .try_into()
+                .expect("too many oracle members"),
+            phantom: Default::default(),
+        },
+        phat_oracle: paraxiom_runtime::PhatOracleConfig {
+            fallback_name,
+        },
     }
 }
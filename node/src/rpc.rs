@@ -7,15 +7,19 @@
 
 use std::sync::Arc;
 
-use paraxiom_runtime::{opaque::Block, AccountId, Balance, Index as Nonce};
+use paraxiom_runtime::{opaque::Block, AccountId, Balance, BlockNumber, Index as Nonce};
 
-use sc_client_api::AuxStore;
+use sc_client_api::{AuxStore, Backend, BlockchainEvents, StorageProvider};
 pub use sc_rpc::{DenyUnsafe, SubscriptionTaskExecutor};
 use sc_transaction_pool_api::TransactionPool;
 use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder;
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
 
+pub mod anchor;
+pub mod oracle;
+pub mod oracle_pubsub;
+
 /// A type representing all RPC extensions.
 pub type RpcExtension = jsonrpsee::RpcModule<()>;
 
@@ -27,10 +31,12 @@ pub struct FullDeps<C, P> {
     pub pool: Arc<P>,
     /// Whether to deny unsafe calls
     pub deny_unsafe: DenyUnsafe,
+    /// Executor spawning the subscription tasks
+    pub subscription_executor: SubscriptionTaskExecutor,
 }
 
 /// Instantiate all RPC extensions.
-pub fn create_full<C, P>(
+pub fn create_full<C, P, BE>(
     deps: FullDeps<C, P>,
 ) -> Result<RpcExtension, Box<dyn std::error::Error + Send + Sync>>
 where
@@ -38,14 +44,22 @@ where
         + HeaderBackend<Block>
         + AuxStore
         + HeaderMetadata<Block, Error = BlockChainError>
+        + BlockchainEvents<Block>
+        + StorageProvider<Block, BE>
         + Send
         + Sync
         + 'static,
     C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
     C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
     C::Api: BlockBuilder<Block>,
+    C::Api: phat_offchain_rollup_runtime_api::RollupAnchorApi<Block, AccountId>,
+    C::Api: pallet_oracle_runtime_api::OracleApi<Block, AccountId, BlockNumber>,
     P: TransactionPool + Sync + Send + 'static,
+    BE: Backend<Block> + 'static,
 {
+    use anchor::{Anchor, AnchorApiServer};
+    use oracle::{Oracle, OracleApiServer};
+    use oracle_pubsub::{OraclePubSub, OraclePubSubApiServer};
     use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApiServer};
     use substrate_frame_rpc_system::{System, SystemApiServer};
 
@@ -54,9 +68,13 @@ where
         client,
         pool,
         deny_unsafe,
+        subscription_executor,
     } = deps;
 
     module.merge(System::new(client.clone(), pool, deny_unsafe).into_rpc())?;
-    module.merge(TransactionPayment::new(client).into_rpc())?;
+    module.merge(TransactionPayment::new(client.clone()).into_rpc())?;
+    module.merge(Anchor::new(client.clone()).into_rpc())?;
+    module.merge(Oracle::new(client.clone()).into_rpc())?;
+    module.merge(OraclePubSub::new(client, subscription_executor).into_rpc())?;
     Ok(module)
 }
@@ -0,0 +1,150 @@
+//! `anchor_*` RPC methods, reading the queues and the kv-store of the offchain rollup anchor
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::Codec;
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    proc_macros::rpc,
+    types::error::{CallError, ErrorObject},
+};
+use phat_offchain_rollup_runtime_api::RollupAnchorApi as RollupAnchorRuntimeApi;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{Bytes, H256};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+/// Maximum number of queue items returned by a single `anchor_queueItems` call
+const MAX_QUEUE_ITEMS: u32 = 256;
+
+/// Rollup anchor RPC methods
+#[rpc(client, server)]
+pub trait AnchorApi<BlockHash, AccountId> {
+    /// Returns the position of the head element of a queue of the name
+    #[method(name = "anchor_queueHead")]
+    fn queue_head(&self, name: H256, queue: u8, at: Option<BlockHash>) -> RpcResult<u32>;
+
+    /// Returns the position of the tail element of a queue of the name
+    #[method(name = "anchor_queueTail")]
+    fn queue_tail(&self, name: H256, queue: u8, at: Option<BlockHash>) -> RpcResult<u32>;
+
+    /// Returns up to `limit` messages of a queue of the name, starting from the position `from`
+    #[method(name = "anchor_queueItems")]
+    fn queue_items(
+        &self,
+        name: H256,
+        queue: u8,
+        from: u32,
+        limit: u32,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Vec<(u32, Bytes)>>;
+
+    /// Returns the value of a key in the kv-store of the name
+    #[method(name = "anchor_getState")]
+    fn get_state(&self, name: H256, key: Bytes, at: Option<BlockHash>)
+        -> RpcResult<Option<Bytes>>;
+
+    /// Returns all the claimed names
+    #[method(name = "anchor_names")]
+    fn names(&self, at: Option<BlockHash>) -> RpcResult<Vec<H256>>;
+
+    /// Returns the submitter of the name
+    #[method(name = "anchor_submitter")]
+    fn submitter(&self, name: H256, at: Option<BlockHash>) -> RpcResult<Option<AccountId>>;
+}
+
+/// Provides the `anchor_*` RPC methods
+pub struct Anchor<C, Block> {
+    client: Arc<C>,
+    _marker: PhantomData<Block>,
+}
+
+impl<C, Block> Anchor<C, Block> {
+    /// Creates a new instance of the anchor RPC
+    pub fn new(client: Arc<C>) -> Self {
+        Self {
+            client,
+            _marker: Default::default(),
+        }
+    }
+}
+
+/// Converts a runtime API error into an RPC error
+fn runtime_error(err: impl std::fmt::Debug) -> jsonrpsee::core::Error {
+    CallError::Custom(ErrorObject::owned(
+        1,
+        "Unable to query the rollup anchor",
+        Some(format!("{err:?}")),
+    ))
+    .into()
+}
+
+#[async_trait]
+impl<C, Block, AccountId> AnchorApiServer<<Block as BlockT>::Hash, AccountId> for Anchor<C, Block>
+where
+    Block: BlockT,
+    AccountId: Codec + serde::Serialize + Send + Sync + 'static,
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+    C::Api: RollupAnchorRuntimeApi<Block, AccountId>,
+{
+    fn queue_head(&self, name: H256, queue: u8, at: Option<Block::Hash>) -> RpcResult<u32> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        self.client
+            .runtime_api()
+            .queue_head(&at, name, queue)
+            .map_err(runtime_error)
+    }
+
+    fn queue_tail(&self, name: H256, queue: u8, at: Option<Block::Hash>) -> RpcResult<u32> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        self.client
+            .runtime_api()
+            .queue_tail(&at, name, queue)
+            .map_err(runtime_error)
+    }
+
+    fn queue_items(
+        &self,
+        name: H256,
+        queue: u8,
+        from: u32,
+        limit: u32,
+        at: Option<Block::Hash>,
+    ) -> RpcResult<Vec<(u32, Bytes)>> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        let items = self
+            .client
+            .runtime_api()
+            .queue_items(&at, name, queue, from, limit.min(MAX_QUEUE_ITEMS))
+            .map_err(runtime_error)?;
+        Ok(items.into_iter().map(|(i, data)| (i, data.into())).collect())
+    }
+
+    fn get_state(
+        &self,
+        name: H256,
+        key: Bytes,
+        at: Option<Block::Hash>,
+    ) -> RpcResult<Option<Bytes>> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        let value = self
+            .client
+            .runtime_api()
+            .get_state(&at, name, key.to_vec())
+            .map_err(runtime_error)?;
+        Ok(value.map(Into::into))
+    }
+
+    fn names(&self, at: Option<Block::Hash>) -> RpcResult<Vec<H256>> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        self.client.runtime_api().names(&at).map_err(runtime_error)
+    }
+
+    fn submitter(&self, name: H256, at: Option<Block::Hash>) -> RpcResult<Option<AccountId>> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        self.client
+            .runtime_api()
+            .submitter(&at, name)
+            .map_err(runtime_error)
+    }
+}
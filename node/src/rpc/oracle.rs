@@ -0,0 +1,454 @@
+//! `oracle_*` RPC methods, querying the oracle feeds, requests and results
+//!
+//! The runtime API types are converted into JSON-friendly ones: the byte strings (keys, urls and
+//! paths) are returned as UTF-8 strings and the `u128` numbers as decimal strings.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::Codec;
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    proc_macros::rpc,
+    types::error::{CallError, ErrorObject},
+};
+use pallet_oracle_runtime_api::{
+    CombinedValue as RuntimeCombinedValue, FeedInfo, LatestValue as RuntimeLatestValue,
+    OracleApi as OracleRuntimeApi, RequestInfo, ResultInfo, RoundInfo,
+    ValueStatus as RuntimeValueStatus,
+};
+use serde::{Deserialize, Serialize};
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{Bytes, H256};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+/// A registry feed
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Feed<AccountId, BlockNumber> {
+    pub owner: AccountId,
+    pub key: String,
+    pub url: String,
+    pub path: String,
+    pub secret_slot: Option<u32>,
+    pub body: Option<String>,
+    pub status: String,
+    pub started_at: BlockNumber,
+}
+
+impl<AccountId, BlockNumber> From<FeedInfo<AccountId, BlockNumber>>
+    for Feed<AccountId, BlockNumber>
+{
+    fn from(feed: FeedInfo<AccountId, BlockNumber>) -> Self {
+        Self {
+            owner: feed.owner,
+            key: text(feed.key),
+            url: text(feed.url),
+            path: text(feed.path),
+            secret_slot: feed.secret_slot,
+            body: feed.body.map(text),
+            status: format!("{:?}", feed.status),
+            started_at: feed.started_at,
+        }
+    }
+}
+
+/// An oracle request
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Request<AccountId> {
+    pub request_id: H256,
+    pub caller: AccountId,
+    pub registry_feed_key: String,
+    pub nonce: String,
+}
+
+impl<AccountId> From<RequestInfo<AccountId>> for Request<AccountId> {
+    fn from(request: RequestInfo<AccountId>) -> Self {
+        Self {
+            request_id: request.request_id,
+            caller: request.caller,
+            registry_feed_key: text(request.registry_feed_key),
+            nonce: request.nonce.to_string(),
+        }
+    }
+}
+
+/// The response of a Phat contract to a request
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestResult {
+    pub phat_contract_id: H256,
+    pub data: Bytes,
+    pub value: Option<String>,
+}
+
+impl From<ResultInfo> for RequestResult {
+    fn from(result: ResultInfo) -> Self {
+        Self {
+            phat_contract_id: result.phat_contract_id,
+            data: result.data.into(),
+            value: result.value.map(|v| v.to_string()),
+        }
+    }
+}
+
+/// The latest response received for a topic
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatestValue<BlockNumber> {
+    pub request_id: H256,
+    pub phat_contract_id: H256,
+    pub value: Option<String>,
+    pub timestamp_ms: u64,
+    pub block_number: BlockNumber,
+}
+
+impl<BlockNumber> From<RuntimeLatestValue<BlockNumber>> for LatestValue<BlockNumber> {
+    fn from(latest: RuntimeLatestValue<BlockNumber>) -> Self {
+        Self {
+            request_id: latest.request_id,
+            phat_contract_id: latest.phat_contract_id,
+            value: latest.value.map(|v| v.to_string()),
+            timestamp_ms: latest.timestamp_ms,
+            block_number: latest.block_number,
+        }
+    }
+}
+
+/// Status of a value combined by `para_oracle`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ValueStatus {
+    Valid,
+    Stale,
+    Frozen,
+}
+
+impl From<RuntimeValueStatus> for ValueStatus {
+    fn from(status: RuntimeValueStatus) -> Self {
+        match status {
+            RuntimeValueStatus::Valid => Self::Valid,
+            RuntimeValueStatus::Stale => Self::Stale,
+            RuntimeValueStatus::Frozen => Self::Frozen,
+        }
+    }
+}
+
+/// A value combined from the feeders by `para_oracle`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CombinedValue {
+    pub value: i64,
+    pub timestamp: String,
+    pub status: ValueStatus,
+}
+
+impl From<RuntimeCombinedValue> for CombinedValue {
+    fn from(combined: RuntimeCombinedValue) -> Self {
+        Self {
+            value: combined.value,
+            timestamp: combined.timestamp.to_string(),
+            status: combined.status.into(),
+        }
+    }
+}
+
+/// The latest reporting round of a `para_oracle` key
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Round<BlockNumber> {
+    pub round_id: u32,
+    pub started_at: BlockNumber,
+    pub deadline: BlockNumber,
+    pub submissions: u32,
+    pub answer: Option<i64>,
+    pub updated_at: Option<String>,
+    pub answered_in_round: u32,
+    /// True if the round is not answered yet, the answer coming from a previous round
+    pub stale: bool,
+}
+
+impl<BlockNumber> From<RoundInfo<BlockNumber>> for Round<BlockNumber> {
+    fn from(round: RoundInfo<BlockNumber>) -> Self {
+        Self {
+            round_id: round.round_id,
+            started_at: round.started_at,
+            deadline: round.deadline,
+            submissions: round.submissions,
+            answer: round.answer,
+            updated_at: round.updated_at.map(|t| t.to_string()),
+            answered_in_round: round.answered_in_round,
+            stale: round.answered_in_round < round.round_id,
+        }
+    }
+}
+
+/// Oracle RPC methods
+#[rpc(client, server)]
+pub trait OracleApi<BlockHash, AccountId, BlockNumber> {
+    /// Returns the registry feed `key` of `owner`
+    #[method(name = "oracle_getFeed")]
+    fn get_feed(
+        &self,
+        owner: AccountId,
+        key: String,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Option<Feed<AccountId, BlockNumber>>>;
+
+    /// Returns the registry feeds, optionally filtered by owner and by topic (feed key)
+    #[method(name = "oracle_listFeeds")]
+    fn list_feeds(
+        &self,
+        owner: Option<AccountId>,
+        topic: Option<String>,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Vec<Feed<AccountId, BlockNumber>>>;
+
+    /// Returns the request `request_id`
+    #[method(name = "oracle_getRequest")]
+    fn get_request(
+        &self,
+        request_id: H256,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Option<Request<AccountId>>>;
+
+    /// Returns the responses of the Phat contracts to the request `request_id`
+    #[method(name = "oracle_getResult")]
+    fn get_result(&self, request_id: H256, at: Option<BlockHash>)
+        -> RpcResult<Vec<RequestResult>>;
+
+    /// Returns the latest response received for the topic (feed key)
+    #[method(name = "oracle_latestValue")]
+    fn latest_value(
+        &self,
+        topic: String,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Option<LatestValue<BlockNumber>>>;
+
+    /// Returns the value combined by `para_oracle` for the key
+    #[method(name = "oracle_combinedValue")]
+    fn combined_value(&self, key: String, at: Option<BlockHash>)
+        -> RpcResult<Option<CombinedValue>>;
+
+    /// Returns the time-weighted average of the value combined by `para_oracle` for the key over
+    /// the last `window` milliseconds
+    #[method(name = "oracle_twap")]
+    fn twap(
+        &self,
+        key: String,
+        window: u64,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Option<CombinedValue>>;
+
+    /// Returns the latest reporting round of the `para_oracle` key
+    #[method(name = "oracle_latestRoundData")]
+    fn latest_round_data(
+        &self,
+        key: String,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Option<Round<BlockNumber>>>;
+}
+
+/// Provides the `oracle_*` RPC methods
+pub struct Oracle<C, Block> {
+    client: Arc<C>,
+    _marker: PhantomData<Block>,
+}
+
+impl<C, Block> Oracle<C, Block> {
+    /// Creates a new instance of the oracle RPC
+    pub fn new(client: Arc<C>) -> Self {
+        Self {
+            client,
+            _marker: Default::default(),
+        }
+    }
+}
+
+impl<C, Block> Oracle<C, Block>
+where
+    Block: BlockT,
+    C: HeaderBackend<Block>,
+{
+    fn at(&self, at: Option<Block::Hash>) -> BlockId<Block> {
+        BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash))
+    }
+}
+
+impl<C, Block> Oracle<C, Block>
+where
+    Block: BlockT,
+    C: ProvideRuntimeApi<Block>,
+{
+    /// Fails if the runtime at the block predates the `para_oracle` values of the runtime API,
+    /// whose version 1 only returned stubs
+    fn ensure_combined_values<AccountId, BlockNumber>(&self, at: &BlockId<Block>) -> RpcResult<()>
+    where
+        AccountId: Codec,
+        BlockNumber: Codec,
+        C::Api: OracleRuntimeApi<Block, AccountId, BlockNumber>,
+    {
+        let version = self
+            .client
+            .runtime_api()
+            .api_version::<dyn OracleRuntimeApi<Block, AccountId, BlockNumber>>(at)
+            .map_err(runtime_error)?;
+        match version {
+            Some(version) if version >= COMBINED_VALUES_API_VERSION => Ok(()),
+            _ => Err(unsupported_error()),
+        }
+    }
+}
+
+/// First version of the `OracleApi` runtime API serving the values combined by `para_oracle`
+const COMBINED_VALUES_API_VERSION: u32 = 2;
+
+/// Decodes a byte string returned by the runtime as UTF-8
+fn text(bytes: Vec<u8>) -> String {
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Converts a runtime API error into an RPC error
+fn runtime_error(err: impl std::fmt::Debug) -> jsonrpsee::core::Error {
+    CallError::Custom(ErrorObject::owned(
+        1,
+        "Unable to query the oracle",
+        Some(format!("{err:?}")),
+    ))
+    .into()
+}
+
+/// Returns the RPC error of the `para_oracle` methods on the runtimes not serving them
+fn unsupported_error() -> jsonrpsee::core::Error {
+    CallError::Custom(ErrorObject::owned(
+        2,
+        "The runtime doesn't serve the para_oracle values",
+        None::<()>,
+    ))
+    .into()
+}
+
+#[async_trait]
+impl<C, Block, AccountId, BlockNumber>
+    OracleApiServer<<Block as BlockT>::Hash, AccountId, BlockNumber> for Oracle<C, Block>
+where
+    Block: BlockT,
+    AccountId: Codec + Serialize + for<'de> Deserialize<'de> + Send + Sync + 'static,
+    BlockNumber: Codec + Serialize + for<'de> Deserialize<'de> + Send + Sync + 'static,
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+    C::Api: OracleRuntimeApi<Block, AccountId, BlockNumber>,
+{
+    fn get_feed(
+        &self,
+        owner: AccountId,
+        key: String,
+        at: Option<Block::Hash>,
+    ) -> RpcResult<Option<Feed<AccountId, BlockNumber>>> {
+        let feed = self
+            .client
+            .runtime_api()
+            .get_feed(&self.at(at), owner, key.into_bytes())
+            .map_err(runtime_error)?;
+        Ok(feed.map(Into::into))
+    }
+
+    fn list_feeds(
+        &self,
+        owner: Option<AccountId>,
+        topic: Option<String>,
+        at: Option<Block::Hash>,
+    ) -> RpcResult<Vec<Feed<AccountId, BlockNumber>>> {
+        let feeds = self
+            .client
+            .runtime_api()
+            .list_feeds(&self.at(at), owner, topic.map(String::into_bytes))
+            .map_err(runtime_error)?;
+        Ok(feeds.into_iter().map(Into::into).collect())
+    }
+
+    fn get_request(
+        &self,
+        request_id: H256,
+        at: Option<Block::Hash>,
+    ) -> RpcResult<Option<Request<AccountId>>> {
+        let request = self
+            .client
+            .runtime_api()
+            .get_request(&self.at(at), request_id)
+            .map_err(runtime_error)?;
+        Ok(request.map(Into::into))
+    }
+
+    fn get_result(
+        &self,
+        request_id: H256,
+        at: Option<Block::Hash>,
+    ) -> RpcResult<Vec<RequestResult>> {
+        let results = self
+            .client
+            .runtime_api()
+            .get_result(&self.at(at), request_id)
+            .map_err(runtime_error)?;
+        Ok(results.into_iter().map(Into::into).collect())
+    }
+
+    fn latest_value(
+        &self,
+        topic: String,
+        at: Option<Block::Hash>,
+    ) -> RpcResult<Option<LatestValue<BlockNumber>>> {
+        let latest = self
+            .client
+            .runtime_api()
+            .latest_value(&self.at(at), topic.into_bytes())
+            .map_err(runtime_error)?;
+        Ok(latest.map(Into::into))
+    }
+
+    fn combined_value(
+        &self,
+        key: String,
+        at: Option<Block::Hash>,
+    ) -> RpcResult<Option<CombinedValue>> {
+        let at = self.at(at);
+        self.ensure_combined_values::<AccountId, BlockNumber>(&at)?;
+        let combined = self
+            .client
+            .runtime_api()
+            .combined_value(&at, key.into_bytes())
+            .map_err(runtime_error)?;
+        Ok(combined.map(Into::into))
+    }
+
+    fn twap(
+        &self,
+        key: String,
+        window: u64,
+        at: Option<Block::Hash>,
+    ) -> RpcResult<Option<CombinedValue>> {
+        let at = self.at(at);
+        self.ensure_combined_values::<AccountId, BlockNumber>(&at)?;
+        let twap = self
+            .client
+            .runtime_api()
+            .twap(&at, key.into_bytes(), window.into())
+            .map_err(runtime_error)?;
+        Ok(twap.map(Into::into))
+    }
+
+    fn latest_round_data(
+        &self,
+        key: String,
+        at: Option<Block::Hash>,
+    ) -> RpcResult<Option<Round<BlockNumber>>> {
+        let at = self.at(at);
+        self.ensure_combined_values::<AccountId, BlockNumber>(&at)?;
+        let round = self
+            .client
+            .runtime_api()
+            .latest_round_data(&at, key.into_bytes())
+            .map_err(runtime_error)?;
+        Ok(round.map(Into::into))
+    }
+}
@@ -0,0 +1,344 @@
+//! `oracle_subscribe*` RPC subscriptions, streaming the oracle updates of the imported blocks
+//!
+//! The subscriptions are driven by the block import notifications. The events of each new best
+//! block are decoded, and the matching updates are read back through the `OracleApi` runtime API
+//! at that block.
+//!
+//! The request subscriptions end with the block the request is responded in.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::Decode;
+use futures::{future, FutureExt, StreamExt};
+use jsonrpsee::{proc_macros::rpc, types::SubscriptionResult, SubscriptionSink};
+use pallet_oracle_runtime_api::OracleApi as OracleRuntimeApi;
+use paraxiom_runtime::{opaque::Block, AccountId, BlockNumber, Hash, RuntimeEvent};
+use sc_client_api::{Backend, BlockchainEvents, StorageProvider};
+use sc_rpc::SubscriptionTaskExecutor;
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_core::{hashing::twox_128, storage::StorageKey, Bytes, H256};
+use sp_runtime::{generic::BlockId, traits::Header as HeaderT};
+
+use super::oracle::{CombinedValue, LatestValue};
+
+type EventRecords = Vec<frame_system::EventRecord<RuntimeEvent, Hash>>;
+
+/// A new value of a topic
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValueUpdate {
+    pub block_hash: Hash,
+    #[serde(flatten)]
+    pub value: LatestValue<BlockNumber>,
+}
+
+/// A new value combined by `para_oracle` for a key, or a change of its status
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CombinedValueUpdate {
+    pub block_number: BlockNumber,
+    pub block_hash: Hash,
+    #[serde(flatten)]
+    pub value: CombinedValue,
+}
+
+/// A change of the state of a request
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum RequestEvent {
+    /// The request was pushed to the Phat contracts
+    #[serde(rename_all = "camelCase")]
+    Requested { caller: AccountId },
+    /// A Phat contract responded to the request
+    #[serde(rename_all = "camelCase")]
+    Responded {
+        phat_contract_id: H256,
+        data: Bytes,
+        value: Option<String>,
+        timestamp_ms: u64,
+    },
+}
+
+/// An update of a request
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestUpdate {
+    pub block_number: BlockNumber,
+    pub block_hash: Hash,
+    pub request_id: H256,
+    #[serde(flatten)]
+    pub event: RequestEvent,
+}
+
+/// Oracle RPC subscriptions
+#[rpc(server)]
+pub trait OraclePubSubApi {
+    /// Streams the new values of the topic (feed key)
+    #[subscription(
+        name = "oracle_subscribeValue" => "oracle_value",
+        unsubscribe = "oracle_unsubscribeValue",
+        item = ValueUpdate,
+    )]
+    fn subscribe_value(&self, topic: String);
+
+    /// Streams the values combined by `para_oracle` for the key
+    #[subscription(
+        name = "oracle_subscribeCombinedValue" => "oracle_combinedValue",
+        unsubscribe = "oracle_unsubscribeCombinedValue",
+        item = CombinedValueUpdate,
+    )]
+    fn subscribe_combined_value(&self, key: String);
+
+    /// Streams the updates of the request `request_id`, until it's responded
+    #[subscription(
+        name = "oracle_subscribeRequest" => "oracle_request",
+        unsubscribe = "oracle_unsubscribeRequest",
+        item = RequestUpdate,
+    )]
+    fn subscribe_request(&self, request_id: H256);
+}
+
+/// Provides the `oracle_subscribe*` RPC subscriptions
+pub struct OraclePubSub<C, BE> {
+    client: Arc<C>,
+    executor: SubscriptionTaskExecutor,
+    _marker: PhantomData<BE>,
+}
+
+impl<C, BE> OraclePubSub<C, BE> {
+    /// Creates a new instance of the oracle subscriptions
+    pub fn new(client: Arc<C>, executor: SubscriptionTaskExecutor) -> Self {
+        Self {
+            client,
+            executor,
+            _marker: Default::default(),
+        }
+    }
+}
+
+/// Reads the events of the block `hash`
+fn runtime_events<C, BE>(client: &C, hash: Hash) -> Vec<RuntimeEvent>
+where
+    BE: Backend<Block>,
+    C: StorageProvider<Block, BE>,
+{
+    let key = StorageKey([twox_128(b"System"), twox_128(b"Events")].concat());
+    let Ok(Some(raw)) = client.storage(hash, &key) else {
+        return Vec::new();
+    };
+    EventRecords::decode(&mut &raw.0[..])
+        .unwrap_or_default()
+        .into_iter()
+        .map(|record| record.event)
+        .collect()
+}
+
+/// Reads the `pallet_oracle` events of the block `hash`
+fn oracle_events<C, BE>(
+    client: &C,
+    hash: Hash,
+) -> Vec<pallet_oracle::Event<paraxiom_runtime::Runtime>>
+where
+    BE: Backend<Block>,
+    C: StorageProvider<Block, BE>,
+{
+    runtime_events(client, hash)
+        .into_iter()
+        .filter_map(|event| match event {
+            RuntimeEvent::PhatOracle(event) => Some(event),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Reads the `para_oracle` events of the block `hash`
+fn para_oracle_events<C, BE>(
+    client: &C,
+    hash: Hash,
+) -> Vec<para_oracle::Event<paraxiom_runtime::Runtime>>
+where
+    BE: Backend<Block>,
+    C: StorageProvider<Block, BE>,
+{
+    runtime_events(client, hash)
+        .into_iter()
+        .filter_map(|event| match event {
+            RuntimeEvent::ParaOracle(event) => Some(event),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns true if the `para_oracle` event may change the combined value of the key, or its
+/// status
+fn touches_key(event: &para_oracle::Event<paraxiom_runtime::Runtime>, key: &[u8]) -> bool {
+    match event {
+        para_oracle::Event::NewFeedData { values, .. } => {
+            values.iter().any(|(fed, _)| &fed[..] == key)
+        }
+        para_oracle::Event::CircuitBreakerSet { key: k, .. }
+        | para_oracle::Event::CircuitBreakerTripped { key: k, .. }
+        | para_oracle::Event::FrozenSet { key: k, .. } => &k[..] == key,
+        _ => false,
+    }
+}
+
+impl<C, BE> OraclePubSubApiServer for OraclePubSub<C, BE>
+where
+    BE: Backend<Block> + 'static,
+    C: ProvideRuntimeApi<Block>
+        + BlockchainEvents<Block>
+        + StorageProvider<Block, BE>
+        + Send
+        + Sync
+        + 'static,
+    C::Api: OracleRuntimeApi<Block, AccountId, BlockNumber>,
+{
+    fn subscribe_value(&self, sink: SubscriptionSink, topic: String) -> SubscriptionResult {
+        let client = self.client.clone();
+        let stream = self
+            .client
+            .import_notification_stream()
+            .filter(|notification| future::ready(notification.is_new_best))
+            .filter_map(move |notification| {
+                let hash = notification.hash;
+                // Only query the topic when the block has some responses
+                let responded = oracle_events(&*client, hash).iter().any(|event| {
+                    matches!(event, pallet_oracle::Event::ResponseRecordReceived { .. })
+                });
+                let update = responded
+                    .then(|| {
+                        client
+                            .runtime_api()
+                            .latest_value(&BlockId::hash(hash), topic.clone().into_bytes())
+                            .ok()
+                            .flatten()
+                    })
+                    .flatten()
+                    .filter(|latest| latest.block_number == *notification.header.number())
+                    .map(|latest| ValueUpdate {
+                        block_hash: hash,
+                        value: latest.into(),
+                    });
+                future::ready(update)
+            });
+
+        let fut = async move {
+            sink.pipe_from_stream(stream).await;
+        };
+        self.executor
+            .spawn("oracle-rpc-subscription", Some("rpc"), fut.boxed());
+        Ok(())
+    }
+
+    fn subscribe_combined_value(&self, sink: SubscriptionSink, key: String) -> SubscriptionResult {
+        let client = self.client.clone();
+        let stream = self
+            .client
+            .import_notification_stream()
+            .filter(|notification| future::ready(notification.is_new_best))
+            .filter_map(move |notification| {
+                let hash = notification.hash;
+                // Only query the key when the block touches it
+                let touched = para_oracle_events(&*client, hash)
+                    .iter()
+                    .any(|event| touches_key(event, key.as_bytes()));
+                let update = touched
+                    .then(|| {
+                        client
+                            .runtime_api()
+                            .combined_value(&BlockId::hash(hash), key.clone().into_bytes())
+                            .ok()
+                            .flatten()
+                    })
+                    .flatten()
+                    .map(|combined| CombinedValueUpdate {
+                        block_number: *notification.header.number(),
+                        block_hash: hash,
+                        value: combined.into(),
+                    });
+                future::ready(update)
+            });
+
+        let fut = async move {
+            sink.pipe_from_stream(stream).await;
+        };
+        self.executor
+            .spawn("oracle-rpc-subscription", Some("rpc"), fut.boxed());
+        Ok(())
+    }
+
+    fn subscribe_request(&self, sink: SubscriptionSink, request_id: H256) -> SubscriptionResult {
+        let client = self.client.clone();
+        let stream = self
+            .client
+            .import_notification_stream()
+            .filter(|notification| future::ready(notification.is_new_best))
+            .map(move |notification| {
+                let hash = notification.hash;
+                let block_number = *notification.header.number();
+                oracle_events(&*client, hash)
+                    .into_iter()
+                    .filter_map(|event| {
+                        let event = match event {
+                            pallet_oracle::Event::OracleRequest {
+                                caller,
+                                request_id: id,
+                                ..
+                            } if id == request_id => RequestEvent::Requested { caller },
+                            pallet_oracle::Event::ResponseRecordReceived {
+                                phat_contract_id,
+                                request_id: id,
+                                response_data,
+                                timestamp_ms,
+                                ..
+                            } if id == request_id => {
+                                let value = client
+                                    .runtime_api()
+                                    .get_result(&BlockId::hash(hash), request_id)
+                                    .ok()
+                                    .and_then(|results| {
+                                        results
+                                            .into_iter()
+                                            .find(|r| r.phat_contract_id == phat_contract_id)
+                                    })
+                                    .and_then(|r| r.value);
+                                RequestEvent::Responded {
+                                    phat_contract_id,
+                                    data: response_data.into_inner().into(),
+                                    value: value.map(|v| v.to_string()),
+                                    timestamp_ms,
+                                }
+                            }
+                            _ => return None,
+                        };
+                        Some(RequestUpdate {
+                            block_number,
+                            block_hash: hash,
+                            request_id,
+                            event,
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            // Stream the updates of the block the request is responded in, then end
+            .scan(false, |responded, updates| {
+                if *responded {
+                    return future::ready(None);
+                }
+                *responded = updates
+                    .iter()
+                    .any(|update| matches!(update.event, RequestEvent::Responded { .. }));
+                future::ready(Some(futures::stream::iter(updates)))
+            })
+            .flatten();
+
+        let fut = async move {
+            sink.pipe_from_stream(stream).await;
+        };
+        self.executor
+            .spawn("oracle-rpc-subscription", Some("rpc"), fut.boxed());
+        Ok(())
+    }
+}
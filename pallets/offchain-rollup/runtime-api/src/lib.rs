@@ -0,0 +1,29 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+//! Runtime API to read the queues and the kv-store of the offchain rollup anchor
+//!
+//! Spares the off-chain tooling from computing the raw storage keys of the anchor pallet.
+
+use codec::Codec;
+use sp_core::H256;
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+    pub trait RollupAnchorApi<AccountId>
+    where
+        AccountId: Codec,
+    {
+        /// Returns the position of the head element of a queue of the name
+        fn queue_head(name: H256, queue: u8) -> u32;
+        /// Returns the position of the tail element of a queue of the name
+        fn queue_tail(name: H256, queue: u8) -> u32;
+        /// Returns up to `limit` messages of a queue of the name, starting from the position
+        /// `from`, along with their positions
+        fn queue_items(name: H256, queue: u8, from: u32, limit: u32) -> Vec<(u32, Vec<u8>)>;
+        /// Returns the value of a key in the kv-store of the name
+        fn get_state(name: H256, key: Vec<u8>) -> Option<Vec<u8>>;
+        /// Returns all the claimed names
+        fn names() -> Vec<H256>;
+        /// Returns the submitter of the name
+        fn submitter(name: H256) -> Option<AccountId>;
+    }
+}
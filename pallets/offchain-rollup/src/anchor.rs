@@ -21,7 +21,7 @@
 //!
 //! The anchor pallet allows arbitrary Phat Contract to connect to it. Before using, the Phat
 //! Contract must register itself in the pallet to claim a name (in `H256`) by calling extrinsic
-//! `claim_name(name)` by the _submitter account_.
+//! `claim_name(name, attestation_key)` by the _submitter account_.
 //!
 //! The _submitter account_ should be an account solely controlled by the Phat Contract. After
 //! the name is claimed, the submitter account will be saved on the blockchain for access control.
@@ -32,14 +32,52 @@
 //! since it's unique. The name will be used to identify the connected contract and the associated
 //! resources (kv-store and the queue).
 //!
+//! A deposit (`Config::NameDeposit`) is reserved from the submitter account when claiming a name.
+//! The submitter account can be rotated by the owner or the governance by `rotate_submitter`
+//! (e.g. when the key is leaked or lost), and the name can be freed by `release_name`, which
+//! clears its kv-store and refunds the deposit.
+//!
+//! ## Storage deposit
+//!
+//! The kv-store of each name is accounted by number of entries and bytes ([`StorageUsage`]). A
+//! deposit proportional to the usage (`Config::DepositPerItem` and `Config::DepositPerByte`) is
+//! reserved from the submitter account at the end of each rollup transaction, and released as the
+//! entries are removed. The rollup transactions exceeding the quota of a name
+//! (`Config::MaxStorageItems` or `Config::MaxStorageBytes`) are rejected.
+//!
+//! The message queues are excluded from the usage: anyone can push messages to a name (e.g. by
+//! an oracle request), which must not be charged to its submitter. They are bounded by the queue
+//! capacities instead, and the rollup transactions can't update the keys under
+//! `Config::QueuePrefix`.
+//!
+//! Optionally, the contract can register an _attestation key_ (sr25519) along with the name. The
+//! receivers can then verify the messages signed by the contract with [`Pallet::attestation_key`],
+//! which allows anyone to relay the messages, not only the submitter account.
+//!
 //! ## Outbound message queue
 //!
-//! The anchor pallet provides a message queue to help pass messages to the Phat Contracts:
+//! The anchor pallet provides message queues to help pass messages to the Phat Contracts:
+//!
+//! - `push_message(name, queue, message)`: Push a message (arbitrary bytes) to the contract and
+//!    return the id of the message. The id starts from 0.
+//! - `queue_head(name, queue)`: Return the id of the first unprocessed message
+//! - `queue_tail(name, queue)`: Return the id of the last unprocessed message
+//!
+//! Each name has `Config::QueueCapacities.len()` independent queues, each with its own capacity.
+//! The queues are meant to be drained by priority, the lowest queue id first, so that urgent
+//! messages don't wait behind the routine ones. The queue 0 is stored under `Config::QueuePrefix`
+//! and the other ones under `queue_prefix(queue)`.
+//!
+//! The stock rollup clients only know a single queue, and pop the messages with
+//! `Action::SetQueueHead`. The action is applied to the queue whose head is checked by the
+//! conditions of the transaction, i.e. the queue the client was created for. Other clients can
+//! use `Action::SetQueueHeadOf` to name the queue explicitly.
 //!
-//! - `push_message(name, message)`: Push a message (arbitrary bytes) to the contract and return
-//!    the id of the message. The id starts from 0.
-//! - `queue_head(name)`: Return the id of the first unprocessed message
-//! - `queue_tail(name)`: Return the id of the last unprocessed message
+//! The capacities may change at runtime (e.g. by governance). Shrinking a queue below its current
+//! length doesn't drop any message: the queue keeps its messages, rejects new ones until it's
+//! drained below the new capacity, and a single head move pops at most `capacity` messages, as
+//! many as `queue_items` returns. A queue with capacity 0 is paused: it can't be read nor popped
+//! until its capacity is raised again.
 //!
 //! ## Receive a message
 //!
@@ -54,19 +92,35 @@
 //! }
 //! ```
 //!
+//! Multiple receivers can be combined as a tuple. Each reply is routed to the first receiver
+//! accepting it, e.g. with the [`ByName`] or [`ByPrefix`] adapters:
+//!
+//! ```ignore
+//! type OnResponse = (ByName<OracleName, PhatOracle>, ByPrefix<ConstU8<1>, Other>, Fallback);
+//! ```
+//!
+//! Besides the replies, the contracts can dispatch filtered runtime calls as their submitter
+//! (`Action::CallRuntime`), push messages to the queues of other names (`Action::PushToQueue`
+//! for queue 0, `Action::PushToQueueOf` for any queue) and emit raw events
+//! (`Action::EmitEvent`). A failed runtime call fails the whole rollup transaction.
+//!
 #![allow(clippy::tabs_in_doc_comments)]
 
 pub use self::pallet::*;
 
 #[frame_support::pallet]
 pub mod pallet {
-    use crate::types::*;
+    use crate::{types::*, weights::WeightInfo};
     use core::fmt::Debug;
     use frame_support::{
-        dispatch::DispatchResult, pallet_prelude::*, traits::StorageVersion, transactional,
+        dispatch::{DispatchResult, DispatchResultWithPostInfo, GetDispatchInfo},
+        pallet_prelude::*,
+        traits::{BalanceStatus, Contains, Currency, ReservableCurrency, StorageVersion},
+        transactional,
     };
+    use sp_runtime::traits::{Dispatchable, Zero};
     use frame_system::pallet_prelude::*;
-    use sp_core::H256;
+    use sp_core::{sr25519, H256};
     use sp_std::vec::Vec;
 
     #[pallet::config]
@@ -75,21 +129,122 @@ pub mod pallet {
 
         type OnResponse: OnResponse<Self::AccountId>;
         type QueuePrefix: Get<&'static [u8]>;
-        type QueueCapacity: Get<u32>;
+        /// The capacity of each message queue of a name, indexed by `QueueId`
+        type QueueCapacities: Get<Vec<u32>>;
+
+        /// The currency to reserve the name deposits
+        type Currency: ReservableCurrency<Self::AccountId>;
+        /// The deposit reserved from the submitter account when claiming a name
+        #[pallet::constant]
+        type NameDeposit: Get<BalanceOf<Self>>;
+        /// The origin allowed to manage any name besides its owner
+        type GovernanceOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+        /// The origin of the emergency operations, e.g. the technical committee
+        type ForceOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+        /// The deposit reserved for each kv-store entry of a name
+        #[pallet::constant]
+        type DepositPerItem: Get<BalanceOf<Self>>;
+        /// The deposit reserved for each byte of the kv-store of a name
+        #[pallet::constant]
+        type DepositPerByte: Get<BalanceOf<Self>>;
+        /// The maximum number of kv-store entries of a name
+        #[pallet::constant]
+        type MaxStorageItems: Get<u32>;
+        /// The maximum size in bytes of the kv-store of a name
+        #[pallet::constant]
+        type MaxStorageBytes: Get<u32>;
+        /// Weight information for the extrinsics in this pallet
+        type WeightInfo: WeightInfo;
+        /// The runtime calls the contracts can dispatch with `Action::CallRuntime`
+        type RuntimeCall: Parameter
+            + Dispatchable<RuntimeOrigin = Self::RuntimeOrigin>
+            + GetDispatchInfo;
+        /// Filters the runtime calls the contracts are allowed to dispatch
+        type CallFilter: Contains<<Self as Config>::RuntimeCall>;
     }
 
+    pub type BalanceOf<T> =
+        <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
     /// Anchor response handler trait
+    ///
+    /// Implemented for tuples, where each reply is routed to the first handler accepting it. The
+    /// replies accepted by no handler are dropped.
     pub trait OnResponse<AccountId> {
+        /// Returns if the handler consumes the reply
+        fn accepts(_name: &H256, _data: &[u8]) -> bool {
+            true
+        }
         fn on_response(name: H256, submitter: AccountId, data: Vec<u8>) -> DispatchResult;
+        /// Returns the maximum weight of handling a reply, charged by each `Action::Reply`
+        fn weight() -> Weight {
+            Weight::zero()
+        }
     }
-    // Default implementation
-    impl<AccountId> OnResponse<AccountId> for () {
-        fn on_response(_name: H256, _submitter: AccountId, _data: Vec<u8>) -> DispatchResult {
+
+    #[impl_trait_for_tuples::impl_for_tuples(8)]
+    impl<AccountId> OnResponse<AccountId> for Tuple {
+        fn accepts(name: &H256, data: &[u8]) -> bool {
+            for_tuples!( #( if Tuple::accepts(name, data) { return true; } )* );
+            false
+        }
+
+        fn weight() -> Weight {
+            let mut weight = Weight::zero();
+            for_tuples!( #( weight = weight.max(Tuple::weight()); )* );
+            weight
+        }
+
+        fn on_response(name: H256, submitter: AccountId, data: Vec<u8>) -> DispatchResult {
+            for_tuples!( #(
+                if Tuple::accepts(&name, &data) {
+                    return Tuple::on_response(name, submitter, data);
+                }
+            )* );
             Ok(())
         }
     }
 
-    const STORAGE_VERSION: StorageVersion = StorageVersion::new(0);
+    /// Routes the replies of a single name to `Handler`
+    pub struct ByName<Name, Handler>(PhantomData<(Name, Handler)>);
+    impl<AccountId, Name: Get<H256>, Handler: OnResponse<AccountId>> OnResponse<AccountId>
+        for ByName<Name, Handler>
+    {
+        fn accepts(name: &H256, data: &[u8]) -> bool {
+            *name == Name::get() && Handler::accepts(name, data)
+        }
+
+        fn on_response(name: H256, submitter: AccountId, data: Vec<u8>) -> DispatchResult {
+            Handler::on_response(name, submitter, data)
+        }
+
+        fn weight() -> Weight {
+            Handler::weight()
+        }
+    }
+
+    /// Routes the replies starting with the `Prefix` byte to `Handler`, with the prefix stripped
+    pub struct ByPrefix<Prefix, Handler>(PhantomData<(Prefix, Handler)>);
+    impl<AccountId, Prefix: Get<u8>, Handler: OnResponse<AccountId>> OnResponse<AccountId>
+        for ByPrefix<Prefix, Handler>
+    {
+        fn accepts(name: &H256, data: &[u8]) -> bool {
+            match data.split_first() {
+                Some((prefix, rest)) => *prefix == Prefix::get() && Handler::accepts(name, rest),
+                None => false,
+            }
+        }
+
+        fn on_response(name: H256, submitter: AccountId, data: Vec<u8>) -> DispatchResult {
+            Handler::on_response(name, submitter, data.get(1..).unwrap_or_default().to_vec())
+        }
+
+        fn weight() -> Weight {
+            Handler::weight()
+        }
+    }
+
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
 
     #[pallet::pallet]
     #[pallet::generate_store(pub(super) trait Store)]
@@ -101,6 +256,28 @@ pub mod pallet {
     #[pallet::getter(fn submitter_by_names)]
     pub type SubmitterByNames<T: Config> = StorageMap<_, Blake2_128Concat, H256, T::AccountId>;
 
+    /// The deposits reserved from the submitters of the names
+    #[pallet::storage]
+    #[pallet::getter(fn name_deposits)]
+    pub type NameDeposits<T: Config> = StorageMap<_, Blake2_128Concat, H256, BalanceOf<T>>;
+
+    /// The kv-store usage of the names
+    #[pallet::storage]
+    #[pallet::getter(fn storage_usage)]
+    pub type StorageUsageByNames<T: Config> =
+        StorageMap<_, Blake2_128Concat, H256, StorageUsage, ValueQuery>;
+
+    /// The deposits reserved from the submitters for the kv-store usage of the names
+    #[pallet::storage]
+    #[pallet::getter(fn storage_deposits)]
+    pub type StorageDeposits<T: Config> =
+        StorageMap<_, Blake2_128Concat, H256, BalanceOf<T>, ValueQuery>;
+
+    /// The sr25519 public keys used by the contracts to sign their messages
+    #[pallet::storage]
+    #[pallet::getter(fn attestation_key)]
+    pub type AttestationKeys<T: Config> = StorageMap<_, Blake2_128Concat, H256, sr25519::Public>;
+
     #[pallet::storage]
     #[pallet::getter(fn states)]
     pub type States<T> =
@@ -111,6 +288,31 @@ pub mod pallet {
     pub enum Event<T: Config> {
         /// A name is claimed by a submitter
         NameClaimed { submitter: T::AccountId, name: H256 },
+        /// The submitter of a name is changed
+        SubmitterRotated {
+            name: H256,
+            old_submitter: T::AccountId,
+            new_submitter: T::AccountId,
+        },
+        /// A name is released and its states are cleared
+        NameReleased { submitter: T::AccountId, name: H256 },
+        /// A name is released by `Config::ForceOrigin` and its deposits are slashed
+        NameForceReleased {
+            submitter: T::AccountId,
+            name: H256,
+            slashed: BalanceOf<T>,
+        },
+        /// A contract emitted an event with `Action::EmitEvent`
+        ContractEmitted { name: H256, data: ActionBytes },
+        /// A contract pushed a message to the queue of another name
+        MessagePushed {
+            from: H256,
+            to: H256,
+            queue: QueueId,
+            index: u32,
+        },
+        /// A contract dispatched a runtime call as its submitter
+        CallDispatched { name: H256 },
         /// A rollup transaction is executed
         RollupExecuted {
             submitter: T::AccountId,
@@ -136,23 +338,45 @@ pub mod pallet {
         QueueIsFull,
         /// Trying to set an invalid queue head
         InvalidQueueHead,
+        /// The queue doesn't exist
+        InvalidQueue,
+        /// The kv-store of the name exceeds its quota
+        StorageQuotaExceeded,
+        /// Cannot decode the runtime call
+        FailedToDecodeCall,
+        /// The runtime call is not allowed by `Config::CallFilter`
+        CallFiltered,
+        /// The key is reserved to the message queues
+        ReservedKey,
     }
 
     #[pallet::call]
     impl<T: Config> Pallet<T> {
         /// Claims a name and assign the caller as the owner of the name
         ///
-        /// Once the name is claimed, we don't allow to change the owner or deregister any more.
+        /// The optional `attestation_key` is the key the contract signs its messages with.
+        ///
+        /// `Config::NameDeposit` is reserved from the caller until the name is released.
         #[pallet::call_index(0)]
-        #[pallet::weight(0)]
+        #[pallet::weight(T::WeightInfo::claim_name())]
         #[transactional]
-        pub fn claim_name(origin: OriginFor<T>, name: H256) -> DispatchResult {
+        pub fn claim_name(
+            origin: OriginFor<T>,
+            name: H256,
+            attestation_key: Option<sr25519::Public>,
+        ) -> DispatchResult {
             let who = ensure_signed(origin)?;
             ensure!(
                 SubmitterByNames::<T>::get(name).is_none(),
                 Error::<T>::NameAlreadyClaimed
             );
+            let deposit = T::NameDeposit::get();
+            T::Currency::reserve(&who, deposit)?;
+            NameDeposits::<T>::insert(name, deposit);
             SubmitterByNames::<T>::insert(name, &who);
+            if let Some(key) = attestation_key {
+                AttestationKeys::<T>::insert(name, key);
+            }
             Self::deposit_event(Event::NameClaimed {
                 submitter: who,
                 name,
@@ -162,7 +386,7 @@ pub mod pallet {
 
         /// Triggers a rollup with an optional nonce
         #[pallet::call_index(1)]
-        #[pallet::weight(0)]
+        #[pallet::weight(Pallet::<T>::rollup_weight(tx))]
         #[transactional]
         pub fn rollup(
             origin: OriginFor<T>,
@@ -173,35 +397,25 @@ pub mod pallet {
             // Check submitter
             let who = ensure_signed(origin)?;
             Self::ensure_name_owner(&name, &who)?;
+            // The queue popped by the legacy `SetQueueHead` action
+            let popped_queue = Self::queue_of_conds(&tx.conds);
             // Check conditions
             for cond in tx.conds {
-                let Cond::Eq(key, opt_value) = cond;
-                ensure!(
-                    States::<T>::get(name, key) == opt_value,
-                    Error::<T>::CondNotMet
-                );
+                ensure!(Self::cond_met(&name, cond), Error::<T>::CondNotMet);
             }
             // Apply updates
             for (key, opt_value) in tx.updates {
-                if let Some(v) = opt_value {
-                    States::<T>::insert(name, key, v);
-                } else {
-                    States::<T>::remove(name, key);
-                }
+                ensure!(!Self::is_queue_key(&key), Error::<T>::ReservedKey);
+                Self::state_set(&name, key, opt_value);
             }
             // Exec actions
             for raw_act in tx.actions {
                 let act: Action =
                     Decode::decode(&mut &raw_act[..]).or(Err(Error::<T>::FailedToDecodeAction))?;
-                match act {
-                    Action::Reply(data) => {
-                        T::OnResponse::on_response(name, who.clone(), data.into())?
-                    }
-                    Action::SetQueueHead(head) => {
-                        Self::queue_head_set(&name, head)?;
-                    }
-                }
+                Self::exec_action(name, &who, popped_queue, act)?;
             }
+            // Charge the kv-store usage
+            Self::settle_storage_deposit(&name, &who)?;
             Self::deposit_event(Event::RollupExecuted {
                 submitter: who,
                 name,
@@ -209,6 +423,77 @@ pub mod pallet {
             });
             Ok(())
         }
+
+        /// Changes the submitter account of a name
+        ///
+        /// Can be called by the current submitter or the governance, e.g. when the submitter key
+        /// is leaked or lost. The name deposit is moved to the new submitter.
+        #[pallet::call_index(2)]
+        #[pallet::weight(T::WeightInfo::rotate_submitter())]
+        #[transactional]
+        pub fn rotate_submitter(
+            origin: OriginFor<T>,
+            name: H256,
+            new_submitter: T::AccountId,
+        ) -> DispatchResult {
+            let old_submitter = Self::ensure_owner_or_governance(origin, &name)?;
+            let name_deposit = NameDeposits::<T>::get(name).unwrap_or_default();
+            let storage_deposit = StorageDeposits::<T>::get(name);
+            let missing = T::Currency::repatriate_reserved(
+                &old_submitter,
+                &new_submitter,
+                name_deposit.saturating_add(storage_deposit),
+                BalanceStatus::Reserved,
+            )?;
+            // Only the moved deposits are held from the new submitter. The storage deposit is
+            // topped up by the next rollup, so it absorbs the missing part first.
+            if !missing.is_zero() {
+                let storage_missing = missing.min(storage_deposit);
+                StorageDeposits::<T>::insert(name, storage_deposit - storage_missing);
+                NameDeposits::<T>::insert(name, name_deposit.saturating_sub(missing - storage_missing));
+            }
+            SubmitterByNames::<T>::insert(name, &new_submitter);
+            Self::deposit_event(Event::SubmitterRotated {
+                name,
+                old_submitter,
+                new_submitter,
+            });
+            Ok(())
+        }
+
+        /// Releases a name, clears its states and refunds the deposit to the submitter
+        ///
+        /// Can be called by the current submitter or the governance.
+        #[pallet::call_index(3)]
+        #[pallet::weight(Pallet::<T>::release_name_weight(Pallet::<T>::max_states()))]
+        #[transactional]
+        pub fn release_name(origin: OriginFor<T>, name: H256) -> DispatchResultWithPostInfo {
+            let submitter = Self::ensure_owner_or_governance(origin, &name)?;
+            let (deposit, removed) = Self::clear_name(name);
+            T::Currency::unreserve(&submitter, deposit);
+            Self::deposit_event(Event::NameReleased { submitter, name });
+            Ok(Some(Self::release_name_weight(removed)).into())
+        }
+
+        /// Releases a misbehaving name, clears its states and slashes the deposits of the
+        /// submitter
+        ///
+        /// Can be called by `Config::ForceOrigin`.
+        #[pallet::call_index(4)]
+        #[pallet::weight(Pallet::<T>::release_name_weight(Pallet::<T>::max_states()))]
+        #[transactional]
+        pub fn force_release_name(origin: OriginFor<T>, name: H256) -> DispatchResultWithPostInfo {
+            T::ForceOrigin::ensure_origin(origin)?;
+            let submitter = SubmitterByNames::<T>::get(name).ok_or(Error::<T>::NameNotExist)?;
+            let (deposit, removed) = Self::clear_name(name);
+            let (_, remaining) = T::Currency::slash_reserved(&submitter, deposit);
+            Self::deposit_event(Event::NameForceReleased {
+                submitter,
+                name,
+                slashed: deposit.saturating_sub(remaining),
+            });
+            Ok(Some(Self::release_name_weight(removed)).into())
+        }
     }
 
     impl<T: Config> Pallet<T> {
@@ -219,93 +504,396 @@ pub mod pallet {
             Ok(())
         }
 
-        /// Pushes a message to the target rollup instance by `name`
+        /// Removes a name and its states
+        ///
+        /// Returns the deposits reserved for the name and the number of removed states.
+        fn clear_name(name: H256) -> (BalanceOf<T>, u32) {
+            let deposit = NameDeposits::<T>::take(name)
+                .unwrap_or_default()
+                .saturating_add(StorageDeposits::<T>::take(name));
+            StorageUsageByNames::<T>::remove(name);
+            SubmitterByNames::<T>::remove(name);
+            AttestationKeys::<T>::remove(name);
+            let removed = States::<T>::clear_prefix(name, u32::MAX, None).unique;
+            (deposit, removed)
+        }
+
+        /// Checks the origin is either the name owner or the governance
+        ///
+        /// Returns the current owner of the name.
+        fn ensure_owner_or_governance(
+            origin: OriginFor<T>,
+            name: &H256,
+        ) -> Result<T::AccountId, DispatchError> {
+            let owner = SubmitterByNames::<T>::get(name).ok_or(Error::<T>::NameNotExist)?;
+            if T::GovernanceOrigin::ensure_origin(origin.clone()).is_err() {
+                let who = ensure_signed(origin)?;
+                ensure!(who == owner, Error::<T>::NotOwner);
+            }
+            Ok(owner)
+        }
+
+        /// Pushes a message to the `queue` of the target rollup instance by `name`
         ///
         /// Returns the index of the message if succeeded
-        pub fn push_message(name: &H256, data: ValueBytes) -> Result<u32, Error<T>> {
+        pub fn push_message(
+            name: &H256,
+            queue: QueueId,
+            data: ValueBytes,
+        ) -> Result<u32, Error<T>> {
             ensure!(
                 SubmitterByNames::<T>::contains_key(name),
                 Error::<T>::NameNotExist
             );
+            let capacity = Self::queue_capacity(queue).ok_or(Error::<T>::InvalidQueue)?;
             ensure!(
-                Self::queue_len(name) < T::QueueCapacity::get(),
+                Self::queue_len(name, queue) < capacity,
                 Error::<T>::QueueIsFull
             );
-            let end = Self::queue_tail(name);
-            Self::queue_set(name, &end, data);
-            Self::queue_tail_set(name, end + 1);
+            let end = Self::queue_tail(name, queue);
+            Self::queue_set(name, queue, &end, data);
+            Self::queue_tail_set(name, queue, end + 1);
             Ok(end)
         }
 
+        /// Pushes a message from the contract of `from` and emits `Event::MessagePushed`
+        fn push_message_from(
+            from: H256,
+            to: H256,
+            queue: QueueId,
+            data: ValueBytes,
+        ) -> DispatchResult {
+            let index = Self::push_message(&to, queue, data)?;
+            Self::deposit_event(Event::MessagePushed {
+                from,
+                to,
+                queue,
+                index,
+            });
+            Ok(())
+        }
+
         /// Returns the position of the message queue head element
         ///
         /// When `queue_head() == queue_tail()`, the queue is empty.
-        pub fn queue_head(name: &H256) -> u32 {
-            Self::queue_get_u32(name, b"_head").expect("BUG: Failed to decode queue head")
+        pub fn queue_head(name: &H256, queue: QueueId) -> u32 {
+            Self::queue_get_u32(name, queue, b"_head").expect("BUG: Failed to decode queue head")
         }
 
         /// Returns the position of the message queue tail element
         ///
         /// When `queue_head() == queue_tail()`, the queue is empty.
-        pub fn queue_tail(name: &H256) -> u32 {
-            Self::queue_get_u32(name, b"_tail").expect("BUG: Failed to decode queue tail")
+        pub fn queue_tail(name: &H256, queue: QueueId) -> u32 {
+            Self::queue_get_u32(name, queue, b"_tail").expect("BUG: Failed to decode queue tail")
         }
 
         /// Returns number of elements in the queue
-        pub fn queue_len(name: &H256) -> u32 {
-            Self::queue_tail(name).saturating_sub(Self::queue_head(name))
+        pub fn queue_len(name: &H256, queue: QueueId) -> u32 {
+            Self::queue_tail(name, queue).saturating_sub(Self::queue_head(name, queue))
+        }
+
+        /// Returns the number of message queues of each name
+        pub fn queue_count() -> u32 {
+            T::QueueCapacities::get().len() as u32
+        }
+
+        /// Returns the capacity of the queue, or `None` if the queue doesn't exist
+        pub fn queue_capacity(queue: QueueId) -> Option<u32> {
+            T::QueueCapacities::get().get(queue as usize).copied()
+        }
+
+        /// Returns up to `limit` unprocessed messages of the queue, starting from the position
+        /// `from`, along with their positions
+        ///
+        /// `limit` is capped by the capacity of the queue.
+        pub fn queue_items(
+            name: &H256,
+            queue: QueueId,
+            from: u32,
+            limit: u32,
+        ) -> Vec<(u32, Vec<u8>)> {
+            let Some(capacity) = Self::queue_capacity(queue) else {
+                return Vec::new();
+            };
+            let from = from.max(Self::queue_head(name, queue));
+            let to = Self::queue_tail(name, queue).min(from.saturating_add(limit.min(capacity)));
+            (from..to)
+                .filter_map(|i| Self::queue_get(name, queue, &i).map(|data| (i, data.into())))
+                .collect()
+        }
+
+        /// Returns the value of a key in the kv-store of the name
+        pub fn get_state(name: &H256, key: &[u8]) -> Option<Vec<u8>> {
+            let key: KeyBytes = key.to_vec().try_into().ok()?;
+            States::<T>::get(name, key).map(Into::into)
+        }
+
+        /// Returns the kv-store prefix of the queue
+        ///
+        /// The queue 0 is stored under `Config::QueuePrefix` for the compatibility with the single
+        /// queue clients, and the others under `QueuePrefix ++ [queue] ++ b"/"`.
+        pub fn queue_prefix(queue: QueueId) -> Vec<u8> {
+            let mut prefix = T::QueuePrefix::get().to_vec();
+            if queue != 0 {
+                prefix.push(queue);
+                prefix.push(b'/');
+            }
+            prefix
+        }
+
+        /// Returns the maximum number of kv-store entries of a name: the quota, plus the items and
+        /// the head and tail of each queue
+        pub fn max_states() -> u32 {
+            T::QueueCapacities::get()
+                .into_iter()
+                .fold(T::MaxStorageItems::get(), |max, capacity| {
+                    max.saturating_add(capacity).saturating_add(2)
+                })
+        }
+
+        /// Returns if the key belongs to the message queues, which are excluded from the storage
+        /// usage
+        pub fn is_queue_key(key: &[u8]) -> bool {
+            key.starts_with(T::QueuePrefix::get())
+        }
+
+        /// Returns the deposit required by a storage usage
+        pub fn storage_deposit_of(usage: &StorageUsage) -> BalanceOf<T> {
+            T::DepositPerItem::get()
+                .saturating_mul(usage.items.into())
+                .saturating_add(T::DepositPerByte::get().saturating_mul(usage.bytes.into()))
+        }
+
+        /// Returns the weight of releasing a name with `items` kv-store entries
+        pub fn release_name_weight(items: u32) -> Weight {
+            T::DbWeight::get()
+                .reads_writes(3, 6)
+                .saturating_add(T::DbWeight::get().writes(items.into()))
+        }
+
+        /// Returns the weight of a rollup transaction, charged by its conditions and updates, plus
+        /// the weight of the work done by its actions
+        pub fn rollup_weight(tx: &RollupTx) -> Weight {
+            let max_capacity = T::QueueCapacities::get().into_iter().max().unwrap_or_default();
+            let actions_weight = tx
+                .actions
+                .iter()
+                .filter_map(|raw_act| Action::decode(&mut &raw_act[..]).ok())
+                .fold(Weight::zero(), |weight, act| {
+                    weight.saturating_add(match act {
+                        Action::Reply(_) => {
+                            T::WeightInfo::action_reply().saturating_add(T::OnResponse::weight())
+                        }
+                        // Removes up to a full queue
+                        Action::SetQueueHead(_) => {
+                            T::WeightInfo::action_set_queue_head(max_capacity)
+                        }
+                        Action::SetQueueHeadOf(queue, _) => T::WeightInfo::action_set_queue_head(
+                            Self::queue_capacity(queue).unwrap_or_default(),
+                        ),
+                        Action::CallRuntime(raw_call) => T::WeightInfo::action_call_runtime()
+                            .saturating_add(
+                                <T as Config>::RuntimeCall::decode(&mut &raw_call[..])
+                                    .map(|call| call.get_dispatch_info().weight)
+                                    .unwrap_or_default(),
+                            ),
+                        Action::PushToQueue(..) | Action::PushToQueueOf(..) => {
+                            T::WeightInfo::action_push_to_queue()
+                        }
+                        Action::EmitEvent(_) => T::WeightInfo::action_emit_event(),
+                    })
+                });
+            tx.conds.iter().fold(
+                T::WeightInfo::rollup(tx.updates.len() as u32, tx.actions.len() as u32),
+                |weight, cond| {
+                    weight.saturating_add(match cond {
+                        Cond::Eq(..) => T::WeightInfo::cond_eq(),
+                        Cond::NotEq(..) => T::WeightInfo::cond_not_eq(),
+                        Cond::Exists(_) => T::WeightInfo::cond_exists(),
+                        Cond::Absent(_) => T::WeightInfo::cond_absent(),
+                        Cond::U32Ge(..) => T::WeightInfo::cond_u32_ge(),
+                        Cond::U32Le(..) => T::WeightInfo::cond_u32_le(),
+                        Cond::BlockNumberBefore(_) => T::WeightInfo::cond_block_number_before(),
+                    })
+                },
+            )
+            .saturating_add(actions_weight)
         }
     }
 
     /// Private helper methods
     impl<T: Config> Pallet<T> {
-        fn queue_get_u32(name: &H256, index: &[u8; 5]) -> Result<u32, impl Debug> {
-            let Some(bytes) = Self::queue_get(name, index) else {
+        /// Executes a rollup action on behalf of the submitter of the name
+        ///
+        /// `popped_queue` is the queue popped by the legacy `SetQueueHead` action.
+        pub(crate) fn exec_action(
+            name: H256,
+            who: &T::AccountId,
+            popped_queue: QueueId,
+            act: Action,
+        ) -> DispatchResult {
+            match act {
+                Action::Reply(data) => T::OnResponse::on_response(name, who.clone(), data.into())?,
+                Action::SetQueueHead(head) => {
+                    Self::queue_head_set(&name, popped_queue, head)?;
+                }
+                Action::SetQueueHeadOf(queue, head) => {
+                    Self::queue_head_set(&name, queue, head)?;
+                }
+                Action::CallRuntime(raw_call) => {
+                    let call = <T as Config>::RuntimeCall::decode(&mut &raw_call[..])
+                        .or(Err(Error::<T>::FailedToDecodeCall))?;
+                    ensure!(T::CallFilter::contains(&call), Error::<T>::CallFiltered);
+                    call.dispatch(frame_system::RawOrigin::Signed(who.clone()).into())
+                        .map_err(|e| e.error)?;
+                    Self::deposit_event(Event::CallDispatched { name });
+                }
+                Action::PushToQueue(to, data) => {
+                    Self::push_message_from(name, to, 0, data)?;
+                }
+                Action::PushToQueueOf(to, queue, data) => {
+                    Self::push_message_from(name, to, queue, data)?;
+                }
+                Action::EmitEvent(data) => {
+                    Self::deposit_event(Event::ContractEmitted { name, data });
+                }
+            }
+            Ok(())
+        }
+
+        /// Checks a rollup condition against the kv-store of the name
+        pub(crate) fn cond_met(name: &H256, cond: Cond) -> bool {
+            // Absent counters are treated as 0, and undecodable ones never meet the condition
+            let get_u32 = |key: KeyBytes| match States::<T>::get(name, key) {
+                Some(bytes) => u32::decode(&mut &bytes[..]).ok(),
+                None => Some(0),
+            };
+            match cond {
+                Cond::Eq(key, opt_value) => States::<T>::get(name, key) == opt_value,
+                Cond::NotEq(key, opt_value) => States::<T>::get(name, key) != opt_value,
+                Cond::Exists(key) => States::<T>::contains_key(name, key),
+                Cond::Absent(key) => !States::<T>::contains_key(name, key),
+                Cond::U32Ge(key, value) => get_u32(key).map_or(false, |v| v >= value),
+                Cond::U32Le(key, value) => get_u32(key).map_or(false, |v| v <= value),
+                Cond::BlockNumberBefore(deadline) => {
+                    frame_system::Pallet::<T>::block_number() < deadline.into()
+                }
+            }
+        }
+
+        /// Sets or removes a kv-store entry, keeping track of the storage usage
+        fn state_set(name: &H256, key: KeyBytes, value: Option<ValueBytes>) {
+            if Self::is_queue_key(&key) {
+                match value {
+                    Some(v) => States::<T>::insert(name, key, v),
+                    None => States::<T>::remove(name, key),
+                }
+                return;
+            }
+            StorageUsageByNames::<T>::mutate(name, |usage| {
+                if let Some(old) = States::<T>::get(name, &key) {
+                    usage.items = usage.items.saturating_sub(1);
+                    usage.bytes = usage
+                        .bytes
+                        .saturating_sub((key.len() + old.len()) as u32);
+                }
+                if let Some(new) = &value {
+                    usage.items = usage.items.saturating_add(1);
+                    usage.bytes = usage
+                        .bytes
+                        .saturating_add((key.len() + new.len()) as u32);
+                }
+            });
+            match value {
+                Some(v) => States::<T>::insert(name, key, v),
+                None => States::<T>::remove(name, key),
+            }
+        }
+
+        /// Checks the storage quota of the name and adjusts the reserved deposit of the
+        /// submitter to its storage usage
+        fn settle_storage_deposit(name: &H256, submitter: &T::AccountId) -> DispatchResult {
+            let usage = StorageUsageByNames::<T>::get(name);
+            ensure!(
+                usage.items <= T::MaxStorageItems::get()
+                    && usage.bytes <= T::MaxStorageBytes::get(),
+                Error::<T>::StorageQuotaExceeded
+            );
+            let required = Self::storage_deposit_of(&usage);
+            let held = StorageDeposits::<T>::get(name);
+            if required > held {
+                T::Currency::reserve(submitter, required - held)?;
+            } else {
+                T::Currency::unreserve(submitter, held - required);
+            }
+            StorageDeposits::<T>::insert(name, required);
+            Ok(())
+        }
+
+        fn queue_get_u32(name: &H256, queue: QueueId, index: &[u8; 5]) -> Result<u32, impl Debug> {
+            let Some(bytes) = Self::queue_get(name, queue, index) else {
 				return Ok(0);
 			};
             u32::decode(&mut &bytes[..])
         }
 
-        fn queue_set_u32(name: &H256, index: &[u8; 5], value: u32) {
+        fn queue_set_u32(name: &H256, queue: QueueId, index: &[u8; 5], value: u32) {
             let value = value
                 .encode()
                 .try_into()
                 .expect("BUG: Failed to encode u32");
-            Self::queue_set(name, index, value);
+            Self::queue_set(name, queue, index, value);
         }
 
-        fn queue_head_set(name: &H256, index: u32) -> DispatchResult {
-            let head = Self::queue_head(name);
-            let tail = Self::queue_tail(name);
+        fn queue_head_set(name: &H256, queue: QueueId, index: u32) -> DispatchResult {
+            let capacity = Self::queue_capacity(queue).ok_or(Error::<T>::InvalidQueue)?;
+            let head = Self::queue_head(name, queue);
+            let tail = Self::queue_tail(name, queue);
             ensure!(head <= index && index <= tail, Error::<T>::InvalidQueueHead);
+            // Bounds the removals by the weight charged in `rollup_weight`, even if the queue
+            // has been shrunk below its length
+            ensure!(index - head <= capacity, Error::<T>::InvalidQueueHead);
             for i in head..index {
-                Self::queue_remove(name, &i);
+                Self::queue_remove(name, queue, &i);
             }
-            Self::queue_set_u32(name, b"_head", index);
+            Self::queue_set_u32(name, queue, b"_head", index);
             Ok(())
         }
 
-        fn queue_tail_set(name: &H256, index: u32) {
-            Self::queue_set_u32(name, b"_tail", index)
+        fn queue_tail_set(name: &H256, queue: QueueId, index: u32) {
+            Self::queue_set_u32(name, queue, b"_tail", index)
+        }
+
+        /// Returns the queue whose head is checked by the conditions, defaulting to the queue 0
+        fn queue_of_conds(conds: &[Cond]) -> QueueId {
+            let last = Self::queue_count().saturating_sub(1).min(QueueId::MAX as u32) as QueueId;
+            (1..=last)
+                .find(|queue| {
+                    let head_key = Self::queue_key(*queue, b"_head");
+                    conds
+                        .iter()
+                        .any(|cond| matches!(cond, Cond::Eq(key, _) if *key == head_key))
+                })
+                .unwrap_or(0)
         }
 
-        fn queue_key(index: &impl Encode) -> KeyBytes {
-            let mut key = T::QueuePrefix::get().to_vec();
+        fn queue_key(queue: QueueId, index: &impl Encode) -> KeyBytes {
+            let mut key = Self::queue_prefix(queue);
             index.encode_to(&mut key);
             key.try_into()
                 .expect("BUG: Failed to make the queue key, the prefix might be too long")
         }
 
-        fn queue_set(name: &H256, index: &impl Encode, data: ValueBytes) {
-            States::<T>::insert(name, Self::queue_key(index), data);
+        fn queue_set(name: &H256, queue: QueueId, index: &impl Encode, data: ValueBytes) {
+            Self::state_set(name, Self::queue_key(queue, index), Some(data));
         }
 
-        fn queue_get(name: &H256, index: &impl Encode) -> Option<ValueBytes> {
-            States::<T>::get(name, Self::queue_key(index))
+        fn queue_get(name: &H256, queue: QueueId, index: &impl Encode) -> Option<ValueBytes> {
+            States::<T>::get(name, Self::queue_key(queue, index))
         }
 
-        fn queue_remove(name: &H256, index: &impl Encode) {
-            States::<T>::remove(name, Self::queue_key(index))
+        fn queue_remove(name: &H256, queue: QueueId, index: &impl Encode) {
+            Self::state_set(name, Self::queue_key(queue, index), None)
         }
     }
 
@@ -314,23 +902,34 @@ pub mod pallet {
         use super::*;
         use crate::{
             mock::{
-                bvec, new_test_ext, set_block_1, take_events, Anchor, RuntimeEvent,
-                RuntimeOrigin as Origin, Test,
+                bvec, new_test_ext, set_block_1, take_events, Anchor, Balances, Oracle, QueueCapacities,
+                Registry, RuntimeCall, RuntimeEvent, RuntimeOrigin as Origin, System, Test, DOLLARS,
             },
-            types::RollupTx,
+            types::{RollupTx, StorageUsage},
         };
         // Pallets
         use frame_support::{assert_noop, assert_ok};
-        use pallet_oracle::types::ResponseRecord;
+        use pallet_oracle::types::{PhatRequest, ResponseRecord, SignedResponse};
+        use pallet_registry::types::{ApiFeedStatus, RegistryFeedKey};
         use pink_kv_session::ReadTracker;
+        use sp_core::{ConstU8, Pair};
 
         const NAME1: H256 = H256([1u8; 32]);
+        const NAME2: H256 = H256([2u8; 32]);
+
+        fn attestation_pair() -> sr25519::Pair {
+            sr25519::Pair::from_seed(&[1u8; 32])
+        }
 
         #[test]
         fn rollup_works() {
             new_test_ext().execute_with(|| {
                 set_block_1();
-                assert_ok!(Anchor::claim_name(Origin::signed(1), NAME1));
+                assert_ok!(Anchor::claim_name(
+                    Origin::signed(1),
+                    NAME1,
+                    Some(attestation_pair().public())
+                ));
 
                 // Can apply updates
                 assert_ok!(Anchor::rollup(
@@ -390,14 +989,52 @@ pub mod pallet {
                 assert_eq!(Anchor::states(NAME1, bvec(b"key")), None);
 
                 // Action received
-                let response = ResponseRecord {
-                    owner: sp_runtime::AccountId32::from([0u8; 32]),
-                    contract_id: NAME1,
-                    pair: bvec(b"polkadot_usd"),
-                    price: 5_000000000000,
+                let key: RegistryFeedKey<Test> = bvec(b"polkadot_usd");
+                assert_ok!(Registry::register_feed(
+                    Origin::signed(1),
+                    key.clone(),
+                    bvec(b"https://example.com/price"),
+                    bvec(b"/polkadot/usd"),
+                    None,
+                    None,
+                ));
+                let request_id = H256([2u8; 32]);
+                pallet_oracle::FeedRequests::<Test>::insert(
+                    request_id,
+                    pallet_oracle::types::Request {
+                        registry_feed_key: bvec(b"polkadot_usd"),
+                        caller: 1,
+                        nonce: 0,
+                        requested_data: bvec(
+                            &PhatRequest::<Test>::Single {
+                                request_id,
+                                owner: 1,
+                                url: bvec(b"https://example.com/price"),
+                                path: bvec(b"/polkadot/usd"),
+                                secret_slot: None,
+                                body: None,
+                            }
+                            .encode(),
+                        ),
+                    },
+                );
+                pallet_oracle::RequestNames::<Test>::insert(request_id, NAME1);
+                let response = ResponseRecord::<Test> {
+                    owner: 1,
+                    phat_contract_id: NAME1,
+                    request_id,
+                    response_data: bvec(&5_000000000000u128.encode()),
                     timestamp_ms: 1000,
                 };
-                let act = Action::Reply(bvec(&response.encode()));
+                let sign = |response: &ResponseRecord<Test>| {
+                    let record = response.encode();
+                    SignedResponse {
+                        signature: attestation_pair().sign(&record),
+                        record,
+                    }
+                };
+                let signed = sign(&response);
+                let act = Action::Reply(bvec(&signed.encode()));
                 let _ = take_events();
                 assert_ok!(Anchor::rollup(
                     Origin::signed(1),
@@ -409,17 +1046,18 @@ pub mod pallet {
                     },
                     5u128
                 ));
-                dbg!(take_events());
                 assert_eq!(
                     take_events(),
                     vec![
-                        RuntimeEvent::Oracle(pallet_oracle::Event::<Test>::QuoteReceived {
-                            contract: NAME1,
-                            submitter: 1,
-                            owner: sp_runtime::AccountId32::from([0u8; 32]),
-                            pair: bvec(b"polkadot_usd"),
-                            price: 5000000000000,
-                        }),
+                        RuntimeEvent::Oracle(
+                            pallet_oracle::Event::<Test>::ResponseRecordReceived {
+                                submitter: 1,
+                                phat_contract_id: NAME1,
+                                request_id,
+                                response_data: bvec(&5_000000000000u128.encode()),
+                                timestamp_ms: 1000,
+                            }
+                        ),
                         RuntimeEvent::Anchor(crate::anchor::Event::<Test>::RollupExecuted {
                             submitter: 1,
                             name: NAME1,
@@ -427,26 +1065,590 @@ pub mod pallet {
                         }),
                     ]
                 );
+
+                assert_eq!(
+                    Oracle::latest_value(b"polkadot_usd".to_vec()).map(|v| v.value),
+                    Some(Some(5_000000000000))
+                );
+                // The response builds the reputation of the feed
+                assert_eq!(Registry::feed_reputations(1, &key).responses, 1);
+                assert_eq!(
+                    Oracle::get_result(request_id)
+                        .into_iter()
+                        .map(|r| (r.phat_contract_id, r.value))
+                        .collect::<Vec<_>>(),
+                    vec![(NAME1, Some(5_000000000000))]
+                );
+
+                // The same signed response cannot be replayed
+                assert_noop!(
+                    Anchor::rollup(
+                        Origin::signed(1),
+                        NAME1,
+                        RollupTx {
+                            conds: vec![],
+                            actions: vec![bvec(&act.encode())],
+                            updates: vec![],
+                        },
+                        6u128
+                    ),
+                    pallet_oracle::Error::<Test>::ResponseAlreadyReceived
+                );
+
+                // A signed response must answer a request pushed to the contract
+                let unknown = ResponseRecord::<Test> {
+                    request_id: H256([3u8; 32]),
+                    ..response.clone()
+                };
+                assert_noop!(
+                    Oracle::submit_response(Origin::signed(2), NAME1, sign(&unknown)),
+                    pallet_oracle::Error::<Test>::FailedToGetFeedRequest
+                );
+                let other_request_id = H256([4u8; 32]);
+                pallet_oracle::FeedRequests::<Test>::insert(
+                    other_request_id,
+                    pallet_oracle::FeedRequests::<Test>::get(request_id).unwrap(),
+                );
+                pallet_oracle::RequestNames::<Test>::insert(other_request_id, NAME2);
+                let misrouted = ResponseRecord::<Test> {
+                    request_id: other_request_id,
+                    ..response
+                };
+                assert_noop!(
+                    Oracle::submit_response(Origin::signed(2), NAME1, sign(&misrouted)),
+                    pallet_oracle::Error::<Test>::RequestNotSentToName
+                );
+            });
+        }
+
+        #[test]
+        fn conds_work() {
+            new_test_ext().execute_with(|| {
+                set_block_1();
+                assert_ok!(Anchor::claim_name(Origin::signed(1), NAME1, None));
+                assert_ok!(Anchor::rollup(
+                    Origin::signed(1),
+                    NAME1,
+                    RollupTx {
+                        conds: vec![],
+                        actions: vec![],
+                        updates: vec![
+                            (bvec(b"counter"), Some(bvec(&5u32.encode()))),
+                            (bvec(b"bad-counter"), Some(bvec(b"x"))),
+                        ],
+                    },
+                    1u128
+                ));
+
+                let check = |cond: Cond| {
+                    Anchor::rollup(
+                        Origin::signed(1),
+                        NAME1,
+                        RollupTx {
+                            conds: vec![cond],
+                            actions: vec![],
+                            updates: vec![],
+                        },
+                        2u128,
+                    )
+                };
+                let counter = bvec(&5u32.encode());
+                assert_ok!(check(Cond::NotEq(bvec(b"counter"), None)));
+                assert_noop!(
+                    check(Cond::NotEq(bvec(b"counter"), Some(counter))),
+                    Error::<Test>::CondNotMet
+                );
+                assert_ok!(check(Cond::Exists(bvec(b"counter"))));
+                assert_noop!(check(Cond::Exists(bvec(b"missing"))), Error::<Test>::CondNotMet);
+                assert_ok!(check(Cond::Absent(bvec(b"missing"))));
+                assert_noop!(check(Cond::Absent(bvec(b"counter"))), Error::<Test>::CondNotMet);
+                assert_ok!(check(Cond::U32Ge(bvec(b"counter"), 5)));
+                assert_noop!(check(Cond::U32Ge(bvec(b"counter"), 6)), Error::<Test>::CondNotMet);
+                assert_ok!(check(Cond::U32Le(bvec(b"counter"), 5)));
+                assert_noop!(check(Cond::U32Le(bvec(b"counter"), 4)), Error::<Test>::CondNotMet);
+                // Absent counters are read as 0
+                assert_ok!(check(Cond::U32Le(bvec(b"missing"), 0)));
+                assert_noop!(
+                    check(Cond::U32Ge(bvec(b"bad-counter"), 0)),
+                    Error::<Test>::CondNotMet
+                );
+                assert_ok!(check(Cond::BlockNumberBefore(2)));
+                assert_noop!(check(Cond::BlockNumberBefore(1)), Error::<Test>::CondNotMet);
+            });
+        }
+
+        #[test]
+        fn actions_v2_work() {
+            new_test_ext().execute_with(|| {
+                set_block_1();
+                assert_ok!(Anchor::claim_name(Origin::signed(1), NAME1, None));
+                assert_ok!(Anchor::claim_name(Origin::signed(2), NAME2, None));
+                let _ = take_events();
+
+                let transfer = RuntimeCall::Balances(pallet_balances::Call::transfer {
+                    dest: 3,
+                    value: DOLLARS,
+                });
+                let actions = vec![
+                    Action::CallRuntime(transfer.encode()),
+                    Action::PushToQueue(NAME2, bvec(b"ping")),
+                    Action::PushToQueueOf(NAME2, 1, bvec(b"pong")),
+                    Action::EmitEvent(bvec(b"hello")),
+                ];
+                assert_ok!(Anchor::rollup(
+                    Origin::signed(1),
+                    NAME1,
+                    RollupTx {
+                        conds: vec![],
+                        actions: actions.iter().map(|a| bvec(&a.encode())).collect(),
+                        updates: vec![],
+                    },
+                    1u128
+                ));
+                assert_eq!(Balances::free_balance(3), 1001 * DOLLARS);
+                assert_eq!(Anchor::queue_len(&NAME2, 0), 1);
+                assert_eq!(Anchor::queue_get(&NAME2, 0, &0u32), Some(bvec(b"ping")));
+                assert_eq!(Anchor::queue_len(&NAME2, 1), 1);
+                assert_eq!(Anchor::queue_get(&NAME2, 1, &0u32), Some(bvec(b"pong")));
+                let events = take_events();
+                assert!(events.contains(&RuntimeEvent::Anchor(Event::CallDispatched {
+                    name: NAME1
+                })));
+                assert!(events.contains(&RuntimeEvent::Anchor(Event::MessagePushed {
+                    from: NAME1,
+                    to: NAME2,
+                    queue: 0,
+                    index: 0,
+                })));
+                assert!(events.contains(&RuntimeEvent::Anchor(Event::MessagePushed {
+                    from: NAME1,
+                    to: NAME2,
+                    queue: 1,
+                    index: 0,
+                })));
+                assert!(events.contains(&RuntimeEvent::Anchor(Event::ContractEmitted {
+                    name: NAME1,
+                    data: bvec(b"hello"),
+                })));
+
+                // Calls outside of the filter are rejected
+                let remark = RuntimeCall::System(frame_system::Call::remark {
+                    remark: b"hi".to_vec(),
+                });
+                assert_noop!(
+                    Anchor::rollup(
+                        Origin::signed(1),
+                        NAME1,
+                        RollupTx {
+                            conds: vec![],
+                            actions: vec![bvec(&Action::CallRuntime(remark.encode()).encode())],
+                            updates: vec![],
+                        },
+                        2u128
+                    ),
+                    Error::<Test>::CallFiltered
+                );
+
+                // A failed call fails the whole rollup
+                let overdraw = RuntimeCall::Balances(pallet_balances::Call::transfer {
+                    dest: 3,
+                    value: 1_000_000 * DOLLARS,
+                });
+                assert_noop!(
+                    Anchor::rollup(
+                        Origin::signed(1),
+                        NAME1,
+                        RollupTx {
+                            conds: vec![],
+                            actions: vec![
+                                bvec(&Action::PushToQueue(NAME2, bvec(b"lost")).encode()),
+                                bvec(&Action::CallRuntime(overdraw.encode()).encode()),
+                            ],
+                            updates: vec![],
+                        },
+                        2u128
+                    ),
+                    pallet_balances::Error::<Test>::InsufficientBalance
+                );
+
+                // Pushing to an unknown queue is rejected
+                assert_noop!(
+                    Anchor::rollup(
+                        Origin::signed(1),
+                        NAME1,
+                        RollupTx {
+                            conds: vec![],
+                            actions: vec![bvec(
+                                &Action::PushToQueueOf(NAME2, 2, bvec(b"void")).encode()
+                            )],
+                            updates: vec![],
+                        },
+                        2u128
+                    ),
+                    Error::<Test>::InvalidQueue
+                );
             });
         }
 
+        #[test]
+        fn responses_are_routed() {
+            struct Accept;
+            impl OnResponse<u64> for Accept {
+                fn on_response(_name: H256, _submitter: u64, _data: Vec<u8>) -> DispatchResult {
+                    Ok(())
+                }
+            }
+            struct Reject;
+            impl OnResponse<u64> for Reject {
+                fn on_response(_name: H256, _submitter: u64, data: Vec<u8>) -> DispatchResult {
+                    assert_eq!(data, vec![42]);
+                    Err(DispatchError::Other("rejected"))
+                }
+            }
+            frame_support::parameter_types! {
+                pub const Name1: H256 = NAME1;
+            }
+            type ByPrefixRouter = (ByPrefix<ConstU8<1>, Reject>, Accept);
+            type ByNameRouter = (ByName<Name1, Reject>, Accept);
+
+            assert!(ByPrefixRouter::on_response(NAME1, 1, vec![1, 42]).is_err());
+            assert_ok!(ByPrefixRouter::on_response(NAME1, 1, vec![2, 42]));
+            assert!(ByNameRouter::on_response(NAME1, 1, vec![42]).is_err());
+            assert_ok!(ByNameRouter::on_response(NAME2, 1, vec![42]));
+            // Unrouted replies are dropped
+            assert_ok!(<(ByName<Name1, Reject>,)>::on_response(NAME2, 1, vec![42]));
+        }
+
         #[test]
         fn name_cannot_claim_twice() {
             new_test_ext().execute_with(|| {
                 set_block_1();
-                assert_ok!(Anchor::claim_name(Origin::signed(1), NAME1));
+                assert_ok!(Anchor::claim_name(Origin::signed(1), NAME1, None));
                 assert_noop!(
-                    Anchor::claim_name(Origin::signed(2), NAME1),
+                    Anchor::claim_name(Origin::signed(2), NAME1, None),
                     Error::<Test>::NameAlreadyClaimed
                 );
             });
         }
 
+        #[test]
+        fn rotate_submitter_works() {
+            new_test_ext().execute_with(|| {
+                set_block_1();
+                assert_ok!(Anchor::claim_name(Origin::signed(1), NAME1, None));
+                let deposit = <<Test as Config>::NameDeposit as Get<_>>::get();
+                assert_eq!(Balances::reserved_balance(1), deposit);
+
+                // Only the owner or the governance can rotate
+                assert_noop!(
+                    Anchor::rotate_submitter(Origin::signed(2), NAME1, 2),
+                    Error::<Test>::NotOwner
+                );
+                assert_ok!(Anchor::rotate_submitter(Origin::signed(1), NAME1, 2));
+                assert_eq!(Anchor::submitter_by_names(NAME1), Some(2));
+                assert_eq!(Balances::reserved_balance(1), 0);
+                assert_eq!(Balances::reserved_balance(2), deposit);
+
+                // The old submitter cannot rollup any more
+                assert_noop!(
+                    Anchor::rollup(Origin::signed(1), NAME1, RollupTx::default(), 1u128),
+                    Error::<Test>::NotOwner
+                );
+                assert_ok!(Anchor::rotate_submitter(Origin::root(), NAME1, 3));
+                assert_eq!(Anchor::submitter_by_names(NAME1), Some(3));
+                assert_eq!(Balances::reserved_balance(3), deposit);
+
+                // Only the deposit actually held by the old submitter is moved and recorded
+                use frame_support::traits::ReservableCurrency;
+                let _ = Balances::slash_reserved(&3, DOLLARS);
+                assert_ok!(Anchor::rotate_submitter(Origin::root(), NAME1, 2));
+                assert_eq!(Balances::reserved_balance(2), deposit - DOLLARS);
+                assert_eq!(Anchor::name_deposits(NAME1), Some(deposit - DOLLARS));
+                assert_ok!(Anchor::release_name(Origin::signed(2), NAME1));
+                assert_eq!(Balances::reserved_balance(2), 0);
+            });
+        }
+
+        #[test]
+        fn release_name_works() {
+            new_test_ext().execute_with(|| {
+                set_block_1();
+                assert_ok!(Anchor::claim_name(Origin::signed(1), NAME1, None));
+                assert_eq!(Anchor::push_message(&NAME1, 0, bvec(b"foo")), Ok(0));
+
+                assert_noop!(
+                    Anchor::release_name(Origin::signed(2), NAME1),
+                    Error::<Test>::NotOwner
+                );
+                assert_ok!(Anchor::release_name(Origin::signed(1), NAME1));
+                assert_eq!(Anchor::submitter_by_names(NAME1), None);
+                assert_eq!(Balances::reserved_balance(1), 0);
+                assert_eq!(States::<Test>::iter_prefix(NAME1).count(), 0);
+
+                // The name can be claimed again with a fresh queue
+                assert_ok!(Anchor::claim_name(Origin::signed(2), NAME1, None));
+                assert_eq!(Anchor::queue_len(&NAME1, 0), 0);
+            });
+        }
+
+        #[test]
+        fn force_release_name_works() {
+            new_test_ext().execute_with(|| {
+                set_block_1();
+                assert_ok!(Anchor::claim_name(Origin::signed(1), NAME1, None));
+                let deposit = Balances::reserved_balance(1);
+
+                assert_noop!(
+                    Anchor::force_release_name(Origin::signed(1), NAME1),
+                    DispatchError::BadOrigin
+                );
+                assert_ok!(Anchor::force_release_name(Origin::root(), NAME1));
+                assert_eq!(Anchor::submitter_by_names(NAME1), None);
+                assert_eq!(Balances::reserved_balance(1), 0);
+                assert_eq!(Balances::free_balance(1), 1000 * DOLLARS - deposit);
+                System::assert_last_event(
+                    Event::<Test>::NameForceReleased {
+                        submitter: 1,
+                        name: NAME1,
+                        slashed: deposit,
+                    }
+                    .into(),
+                );
+                assert_noop!(
+                    Anchor::force_release_name(Origin::root(), NAME1),
+                    Error::<Test>::NameNotExist
+                );
+            });
+        }
+
+        #[test]
+        fn force_freeze_topic_works() {
+            new_test_ext().execute_with(|| {
+                set_block_1();
+                let key: RegistryFeedKey<Test> = bvec(b"polkadot_usd");
+                assert_ok!(Registry::register_feed(
+                    Origin::signed(1),
+                    key.clone(),
+                    bvec(b"https://api.example.com/price"),
+                    bvec(b"/price"),
+                    None,
+                    None,
+                ));
+                pallet_registry::ApiFeeds::<Test>::mutate(1, key.clone(), |feed| {
+                    feed.as_mut().unwrap().status = ApiFeedStatus::Active
+                });
+
+                assert_noop!(
+                    Oracle::force_freeze_topic(Origin::signed(1), key.clone()),
+                    DispatchError::BadOrigin
+                );
+                assert_ok!(Oracle::force_freeze_topic(Origin::root(), key.clone()));
+                assert!(Oracle::frozen_topics(key.clone()));
+                System::assert_last_event(
+                    pallet_oracle::Event::<Test>::TopicFrozenSet {
+                        topic: key.clone(),
+                        frozen: true,
+                    }
+                    .into(),
+                );
+                assert_noop!(
+                    Oracle::request(Origin::signed(1), key.clone(), 0, 1),
+                    pallet_oracle::Error::<Test>::TopicFrozen
+                );
+
+                assert_noop!(
+                    Oracle::force_unfreeze_topic(Origin::signed(1), key.clone()),
+                    DispatchError::BadOrigin
+                );
+                assert_ok!(Oracle::force_unfreeze_topic(Origin::root(), key.clone()));
+                assert!(!Oracle::frozen_topics(key.clone()));
+                System::assert_last_event(
+                    pallet_oracle::Event::<Test>::TopicFrozenSet {
+                        topic: key.clone(),
+                        frozen: false,
+                    }
+                    .into(),
+                );
+            });
+        }
+
+        #[test]
+        fn storage_deposit_works() {
+            new_test_ext().execute_with(|| {
+                set_block_1();
+                assert_ok!(Anchor::claim_name(Origin::signed(1), NAME1, None));
+                let name_deposit = <<Test as Config>::NameDeposit as Get<_>>::get();
+                let per_item = <<Test as Config>::DepositPerItem as Get<_>>::get();
+                let per_byte = <<Test as Config>::DepositPerByte as Get<_>>::get();
+
+                assert_ok!(Anchor::rollup(
+                    Origin::signed(1),
+                    NAME1,
+                    RollupTx {
+                        conds: vec![],
+                        actions: vec![],
+                        updates: vec![(bvec(b"key"), Some(bvec(b"value")))],
+                    },
+                    1u128
+                ));
+                assert_eq!(
+                    Anchor::storage_usage(NAME1),
+                    StorageUsage { items: 1, bytes: 8 }
+                );
+                assert_eq!(
+                    Balances::reserved_balance(1),
+                    name_deposit + per_item + 8 * per_byte
+                );
+
+                // Overwriting only charges the difference
+                assert_ok!(Anchor::rollup(
+                    Origin::signed(1),
+                    NAME1,
+                    RollupTx {
+                        conds: vec![],
+                        actions: vec![],
+                        updates: vec![(bvec(b"key"), Some(bvec(b"v")))],
+                    },
+                    2u128
+                ));
+                assert_eq!(
+                    Anchor::storage_usage(NAME1),
+                    StorageUsage { items: 1, bytes: 4 }
+                );
+                assert_eq!(
+                    Balances::reserved_balance(1),
+                    name_deposit + per_item + 4 * per_byte
+                );
+
+                // Removing releases the deposit
+                assert_ok!(Anchor::rollup(
+                    Origin::signed(1),
+                    NAME1,
+                    RollupTx {
+                        conds: vec![],
+                        actions: vec![],
+                        updates: vec![(bvec(b"key"), None)],
+                    },
+                    3u128
+                ));
+                assert_eq!(Anchor::storage_usage(NAME1), StorageUsage::default());
+                assert_eq!(Balances::reserved_balance(1), name_deposit);
+            });
+        }
+
+        #[test]
+        fn queues_are_not_charged() {
+            new_test_ext().execute_with(|| {
+                set_block_1();
+                assert_ok!(Anchor::claim_name(Origin::signed(1), NAME1, None));
+                let name_deposit = <<Test as Config>::NameDeposit as Get<_>>::get();
+
+                // Messages pushed by others don't count in the usage of the name
+                assert_eq!(Anchor::push_message(&NAME1, 0, bvec(b"request")), Ok(0));
+                assert_ok!(Anchor::rollup(
+                    Origin::signed(1),
+                    NAME1,
+                    RollupTx {
+                        conds: vec![],
+                        actions: vec![bvec(&Action::SetQueueHead(1).encode())],
+                        updates: vec![],
+                    },
+                    1u128
+                ));
+                assert_eq!(Anchor::storage_usage(NAME1), StorageUsage::default());
+                assert_eq!(Balances::reserved_balance(1), name_deposit);
+
+                // The contract can't write to the queues for free
+                assert_noop!(
+                    Anchor::rollup(
+                        Origin::signed(1),
+                        NAME1,
+                        RollupTx {
+                            conds: vec![],
+                            actions: vec![],
+                            updates: vec![(bvec(b"_queue/free"), Some(bvec(b"data")))],
+                        },
+                        2u128
+                    ),
+                    Error::<Test>::ReservedKey
+                );
+            });
+        }
+
+        #[test]
+        fn migration_to_v1_charges_existing_states() {
+            use frame_support::traits::OnRuntimeUpgrade;
+            new_test_ext().execute_with(|| {
+                set_block_1();
+                assert_ok!(Anchor::claim_name(Origin::signed(1), NAME1, None));
+                let name_deposit = <<Test as Config>::NameDeposit as Get<_>>::get();
+                let per_item = <<Test as Config>::DepositPerItem as Get<_>>::get();
+                let per_byte = <<Test as Config>::DepositPerByte as Get<_>>::get();
+
+                // States written before the storage accounting
+                States::<Test>::insert(NAME1, bvec(b"key"), bvec(b"value"));
+                States::<Test>::insert(NAME1, bvec(b"_queue/_tail"), bvec(&1u32.encode()));
+                StorageVersion::new(0).put::<Pallet<Test>>();
+
+                crate::migrations::v1::MigrateToV1::<Test>::on_runtime_upgrade();
+                assert_eq!(StorageVersion::get::<Pallet<Test>>(), 1);
+                assert_eq!(
+                    Anchor::storage_usage(NAME1),
+                    StorageUsage { items: 1, bytes: 8 }
+                );
+                assert_eq!(
+                    Balances::reserved_balance(1),
+                    name_deposit + per_item + 8 * per_byte
+                );
+
+                // Only runs once
+                States::<Test>::insert(NAME1, bvec(b"other"), bvec(b"value"));
+                crate::migrations::v1::MigrateToV1::<Test>::on_runtime_upgrade();
+                assert_eq!(
+                    Anchor::storage_usage(NAME1),
+                    StorageUsage { items: 1, bytes: 8 }
+                );
+            });
+        }
+
+        #[test]
+        fn storage_quota_works() {
+            new_test_ext().execute_with(|| {
+                set_block_1();
+                assert_ok!(Anchor::claim_name(Origin::signed(1), NAME1, None));
+                let max_bytes = <<Test as Config>::MaxStorageBytes as Get<u32>>::get();
+                let value = vec![0u8; max_bytes as usize];
+                assert_noop!(
+                    Anchor::rollup(
+                        Origin::signed(1),
+                        NAME1,
+                        RollupTx {
+                            conds: vec![],
+                            actions: vec![],
+                            updates: vec![(bvec(b"key"), Some(bvec(&value)))],
+                        },
+                        1u128
+                    ),
+                    Error::<Test>::StorageQuotaExceeded
+                );
+            });
+        }
+
+        #[test]
+        fn claim_name_saves_attestation_key() {
+            new_test_ext().execute_with(|| {
+                set_block_1();
+                let key = sr25519::Public([7u8; 32]);
+                assert_ok!(Anchor::claim_name(Origin::signed(1), NAME1, Some(key)));
+                assert_eq!(Anchor::attestation_key(NAME1), Some(key));
+            });
+        }
+
         #[test]
         fn queue_key_is_correct() {
-            assert_eq!(&Anchor::queue_key(&1)[..], b"_queue/\x01\x00\x00\x00");
+            assert_eq!(&Anchor::queue_key(0, &1)[..], b"_queue/\x01\x00\x00\x00");
             assert_eq!(
-                &Anchor::queue_key(b"_head")[..],
+                &Anchor::queue_key(0, b"_head")[..],
                 [95, 113, 117, 101, 117, 101, 47, 95, 104, 101, 97, 100]
             );
         }
@@ -455,34 +1657,34 @@ pub mod pallet {
         fn queue_works() {
             new_test_ext().execute_with(|| {
                 set_block_1();
-                assert_ok!(Anchor::claim_name(Origin::signed(1), NAME1));
+                assert_ok!(Anchor::claim_name(Origin::signed(1), NAME1, None));
 
-                assert_eq!(Anchor::queue_head(&NAME1), 0);
-                assert_eq!(Anchor::queue_tail(&NAME1), 0);
-                assert_eq!(Anchor::queue_len(&NAME1), 0);
+                assert_eq!(Anchor::queue_head(&NAME1, 0), 0);
+                assert_eq!(Anchor::queue_tail(&NAME1, 0), 0);
+                assert_eq!(Anchor::queue_len(&NAME1, 0), 0);
 
-                assert_eq!(Anchor::push_message(&NAME1, bvec(b"foo")), Ok(0));
-                assert_eq!(Anchor::queue_head(&NAME1), 0);
-                assert_eq!(Anchor::queue_tail(&NAME1), 1);
-                assert_eq!(Anchor::queue_len(&NAME1), 1);
+                assert_eq!(Anchor::push_message(&NAME1, 0, bvec(b"foo")), Ok(0));
+                assert_eq!(Anchor::queue_head(&NAME1, 0), 0);
+                assert_eq!(Anchor::queue_tail(&NAME1, 0), 1);
+                assert_eq!(Anchor::queue_len(&NAME1, 0), 1);
 
-                let cap = <<Test as Config>::QueueCapacity as Get<_>>::get();
+                let cap = Anchor::queue_capacity(0).unwrap();
                 for i in 1..cap {
-                    assert_eq!(Anchor::push_message(&NAME1, bvec(b"foo")), Ok(i));
+                    assert_eq!(Anchor::push_message(&NAME1, 0, bvec(b"foo")), Ok(i));
                 }
-                assert_eq!(Anchor::queue_head(&NAME1), 0);
-                assert_eq!(Anchor::queue_tail(&NAME1), cap);
-                assert_eq!(Anchor::queue_len(&NAME1), cap);
+                assert_eq!(Anchor::queue_head(&NAME1, 0), 0);
+                assert_eq!(Anchor::queue_tail(&NAME1, 0), cap);
+                assert_eq!(Anchor::queue_len(&NAME1, 0), cap);
                 assert_eq!(
-                    Anchor::push_message(&NAME1, bvec(b"foo")),
+                    Anchor::push_message(&NAME1, 0, bvec(b"foo")),
                     Err(Error::<Test>::QueueIsFull)
                 );
-                assert_eq!(Anchor::queue_head(&NAME1), 0);
-                assert_eq!(Anchor::queue_tail(&NAME1), cap);
-                assert_eq!(Anchor::queue_len(&NAME1), cap);
+                assert_eq!(Anchor::queue_head(&NAME1, 0), 0);
+                assert_eq!(Anchor::queue_tail(&NAME1, 0), cap);
+                assert_eq!(Anchor::queue_len(&NAME1, 0), cap);
 
                 for i in 0..cap {
-                    assert!(Anchor::queue_get(&NAME1, &i).is_some());
+                    assert!(Anchor::queue_get(&NAME1, 0, &i).is_some());
                 }
 
                 // Pop all elements except the last one
@@ -497,14 +1699,14 @@ pub mod pallet {
                     1u128
                 ));
 
-                assert_eq!(Anchor::queue_head(&NAME1), cap - 1);
-                assert_eq!(Anchor::queue_tail(&NAME1), cap);
-                assert_eq!(Anchor::queue_len(&NAME1), 1);
+                assert_eq!(Anchor::queue_head(&NAME1, 0), cap - 1);
+                assert_eq!(Anchor::queue_tail(&NAME1, 0), cap);
+                assert_eq!(Anchor::queue_len(&NAME1, 0), 1);
                 for i in 0..cap - 1 {
-                    assert!(Anchor::queue_get(&NAME1, &i).is_none());
+                    assert!(Anchor::queue_get(&NAME1, 0, &i).is_none());
                 }
                 let last = cap - 1;
-                assert!(Anchor::queue_get(&NAME1, &last).is_some());
+                assert!(Anchor::queue_get(&NAME1, 0, &last).is_some());
 
                 // Pop the last one
                 assert_ok!(Anchor::rollup(
@@ -517,7 +1719,7 @@ pub mod pallet {
                     },
                     1u128
                 ));
-                assert_eq!(Anchor::queue_len(&NAME1), 0);
+                assert_eq!(Anchor::queue_len(&NAME1, 0), 0);
 
                 // Pop more should fail
                 assert_noop!(
@@ -536,6 +1738,138 @@ pub mod pallet {
             });
         }
 
+        #[test]
+        fn priority_queues_work() {
+            new_test_ext().execute_with(|| {
+                set_block_1();
+                assert_ok!(Anchor::claim_name(Origin::signed(1), NAME1, None));
+                assert_eq!(Anchor::queue_count(), 2);
+                assert_eq!(&Anchor::queue_key(1, &1)[..], b"_queue/\x01/\x01\x00\x00\x00");
+
+                // The queues are independent
+                assert_eq!(Anchor::push_message(&NAME1, 1, bvec(b"routine")), Ok(0));
+                assert_eq!(Anchor::push_message(&NAME1, 0, bvec(b"urgent")), Ok(0));
+                assert_eq!(Anchor::push_message(&NAME1, 1, bvec(b"routine")), Ok(1));
+                assert_eq!(Anchor::queue_len(&NAME1, 0), 1);
+                assert_eq!(Anchor::queue_len(&NAME1, 1), 2);
+                assert_eq!(
+                    Anchor::queue_items(&NAME1, 1, 0, 10),
+                    vec![(0, b"routine".to_vec()), (1, b"routine".to_vec())]
+                );
+                assert_eq!(
+                    Anchor::queue_items(&NAME1, 1, 1, 10),
+                    vec![(1, b"routine".to_vec())]
+                );
+                assert_eq!(Anchor::queue_items(&NAME1, 0, 0, 0), vec![]);
+                assert_eq!(
+                    Anchor::get_state(&NAME1, &Anchor::queue_key(0, &0u32)),
+                    Some(b"urgent".to_vec())
+                );
+                assert_eq!(
+                    Anchor::push_message(&NAME1, 2, bvec(b"foo")),
+                    Err(Error::<Test>::InvalidQueue)
+                );
+                // Each queue has its own capacity
+                let cap = Anchor::queue_capacity(1).unwrap();
+                for i in 2..cap {
+                    assert_eq!(Anchor::push_message(&NAME1, 1, bvec(b"routine")), Ok(i));
+                }
+                assert_eq!(
+                    Anchor::push_message(&NAME1, 1, bvec(b"routine")),
+                    Err(Error::<Test>::QueueIsFull)
+                );
+                assert_eq!(Anchor::push_message(&NAME1, 0, bvec(b"urgent")), Ok(1));
+
+                // Pop by the explicit action
+                assert_ok!(Anchor::rollup(
+                    Origin::signed(1),
+                    NAME1,
+                    RollupTx {
+                        conds: vec![],
+                        actions: vec![bvec(&Action::SetQueueHeadOf(1, 1).encode())],
+                        updates: vec![],
+                    },
+                    1u128
+                ));
+                assert_eq!(Anchor::queue_head(&NAME1, 1), 1);
+                assert_eq!(Anchor::queue_head(&NAME1, 0), 0);
+
+                // Pop by the legacy action, applied to the queue checked by the conditions
+                assert_ok!(Anchor::rollup(
+                    Origin::signed(1),
+                    NAME1,
+                    RollupTx {
+                        conds: vec![Cond::Eq(
+                            Anchor::queue_key(1, b"_head"),
+                            Some(bvec(&1u32.encode()))
+                        )],
+                        actions: vec![bvec(&Action::SetQueueHead(2).encode())],
+                        updates: vec![],
+                    },
+                    2u128
+                ));
+                assert_eq!(Anchor::queue_head(&NAME1, 1), 2);
+                assert_eq!(Anchor::queue_head(&NAME1, 0), 0);
+
+                // Without such a condition, the legacy action pops the queue 0
+                assert_ok!(Anchor::rollup(
+                    Origin::signed(1),
+                    NAME1,
+                    RollupTx {
+                        conds: vec![],
+                        actions: vec![bvec(&Action::SetQueueHead(1).encode())],
+                        updates: vec![],
+                    },
+                    3u128
+                ));
+                assert_eq!(Anchor::queue_head(&NAME1, 0), 1);
+                assert_eq!(Anchor::queue_head(&NAME1, 1), 2);
+            });
+        }
+
+        #[test]
+        fn shrunk_queues_are_drained() {
+            new_test_ext().execute_with(|| {
+                set_block_1();
+                assert_ok!(Anchor::claim_name(Origin::signed(1), NAME1, None));
+                for i in 0..4 {
+                    assert_eq!(Anchor::push_message(&NAME1, 1, bvec(b"msg")), Ok(i));
+                }
+                QueueCapacities::set(&vec![3, 2]);
+
+                // The messages are kept, but no new one is accepted
+                assert_eq!(Anchor::queue_len(&NAME1, 1), 4);
+                assert_eq!(
+                    Anchor::push_message(&NAME1, 1, bvec(b"msg")),
+                    Err(Error::<Test>::QueueIsFull)
+                );
+                assert_eq!(Anchor::queue_items(&NAME1, 1, 0, 10).len(), 2);
+
+                // A head move pops at most `capacity` messages
+                let pop = |head: u32, nonce: u128| {
+                    Anchor::rollup(
+                        Origin::signed(1),
+                        NAME1,
+                        RollupTx {
+                            conds: vec![],
+                            actions: vec![bvec(&Action::SetQueueHeadOf(1, head).encode())],
+                            updates: vec![],
+                        },
+                        nonce,
+                    )
+                };
+                assert_noop!(pop(3, 1), Error::<Test>::InvalidQueueHead);
+                assert_ok!(pop(2, 1));
+                assert_eq!(
+                    Anchor::push_message(&NAME1, 1, bvec(b"msg")),
+                    Err(Error::<Test>::QueueIsFull)
+                );
+                assert_ok!(pop(3, 2));
+                assert_eq!(Anchor::push_message(&NAME1, 1, bvec(b"msg")), Ok(4));
+                assert_eq!(Anchor::queue_len(&NAME1, 1), 2);
+            });
+        }
+
         #[test]
         fn queue_e2e() {
             use pink_kv_session::traits::KvSession;
@@ -631,18 +1965,18 @@ pub mod pallet {
 
             new_test_ext().execute_with(|| {
                 set_block_1();
-                assert_ok!(Anchor::claim_name(Origin::signed(1), NAME1));
+                assert_ok!(Anchor::claim_name(Origin::signed(1), NAME1, None));
 
-                assert_eq!(Anchor::queue_head(&NAME1), 0);
-                assert_eq!(Anchor::queue_tail(&NAME1), 0);
-                assert_eq!(Anchor::queue_len(&NAME1), 0);
+                assert_eq!(Anchor::queue_head(&NAME1, 0), 0);
+                assert_eq!(Anchor::queue_tail(&NAME1, 0), 0);
+                assert_eq!(Anchor::queue_len(&NAME1, 0), 0);
 
                 // Put some data in the queue on-chain.
-                assert_eq!(Anchor::push_message(&NAME1, bvec(b"Tom")), Ok(0));
-                assert_eq!(Anchor::push_message(&NAME1, bvec(b"Kitty")), Ok(1));
-                assert_eq!(Anchor::queue_head(&NAME1), 0);
-                assert_eq!(Anchor::queue_tail(&NAME1), 2);
-                assert_eq!(Anchor::queue_len(&NAME1), 2);
+                assert_eq!(Anchor::push_message(&NAME1, 0, bvec(b"Tom")), Ok(0));
+                assert_eq!(Anchor::push_message(&NAME1, 0, bvec(b"Kitty")), Ok(1));
+                assert_eq!(Anchor::queue_head(&NAME1, 0), 0);
+                assert_eq!(Anchor::queue_tail(&NAME1, 0), 2);
+                assert_eq!(Anchor::queue_len(&NAME1, 0), 2);
 
                 let rollup = {
                     // Create an client to consume the elements.
@@ -670,9 +2004,9 @@ pub mod pallet {
 
                 // Apply the session updates
                 assert_ok!(Anchor::rollup(Origin::signed(1), NAME1, rollup, 1u128));
-                assert_eq!(Anchor::queue_head(&NAME1), 2);
-                assert_eq!(Anchor::queue_tail(&NAME1), 2);
-                assert_eq!(Anchor::queue_len(&NAME1), 0);
+                assert_eq!(Anchor::queue_head(&NAME1, 0), 2);
+                assert_eq!(Anchor::queue_tail(&NAME1, 0), 2);
+                assert_eq!(Anchor::queue_len(&NAME1, 0), 0);
 
                 // The kv set should be applied
                 let mut client = test_client();
@@ -0,0 +1,165 @@
+//! Benchmarking setup for phat-offchain-rollup
+
+use crate::{
+    anchor::{BalanceOf, Call, Config, Pallet, States},
+    types::*,
+};
+use codec::Encode;
+use frame_benchmarking::{account, benchmarks, impl_benchmark_test_suite, whitelisted_caller};
+use frame_support::traits::Currency;
+use frame_system::RawOrigin;
+use sp_core::{sr25519, H256};
+use sp_runtime::traits::Bounded;
+use sp_std::prelude::*;
+
+const NAME: H256 = H256([1u8; 32]);
+const MAX_ITEMS: u32 = 32;
+/// Size of the action payloads, leaving room for the encoding of the action in `ActionBytes`
+const MAX_ACTION_DATA: usize = 200;
+
+/// Claims `NAME` with a funded caller
+fn setup_name<T: Config>() -> T::AccountId {
+    let caller: T::AccountId = whitelisted_caller();
+    T::Currency::make_free_balance_be(&caller, BalanceOf::<T>::max_value() / 2u32.into());
+    Pallet::<T>::claim_name(RawOrigin::Signed(caller.clone()).into(), NAME, None)
+        .expect("claim_name should work");
+    caller
+}
+
+fn key(i: u32) -> KeyBytes {
+    KeyBytes::truncate_from(i.encode())
+}
+
+/// Stores the SCALE-encoded `0u32` under `key(0)` in the kv-store of `NAME`
+fn setup_state<T: Config>() {
+    States::<T>::insert(NAME, key(0), ValueBytes::truncate_from(0u32.encode()));
+}
+
+benchmarks! {
+    where_clause { where <T as Config>::RuntimeCall: From<frame_system::Call<T>> }
+
+    claim_name {
+        let caller: T::AccountId = whitelisted_caller();
+        T::Currency::make_free_balance_be(&caller, BalanceOf::<T>::max_value() / 2u32.into());
+    }: _(RawOrigin::Signed(caller), NAME, Some(sr25519::Public([7u8; 32])))
+
+    // Moves both the name and the storage deposits
+    rotate_submitter {
+        let caller = setup_name::<T>();
+        let tx = RollupTx {
+            conds: vec![],
+            actions: vec![],
+            updates: vec![(key(0), Some(ValueBytes::truncate_from(vec![0u8; 32])))],
+        };
+        Pallet::<T>::rollup(RawOrigin::Signed(caller.clone()).into(), NAME, tx, 0u128)
+            .expect("rollup should work");
+        let new_submitter: T::AccountId = account("new_submitter", 0, 0);
+        let balance = BalanceOf::<T>::max_value() / 2u32.into();
+        T::Currency::make_free_balance_be(&new_submitter, balance);
+    }: _(RawOrigin::Signed(caller), NAME, new_submitter)
+
+    rollup {
+        let u in 0 .. MAX_ITEMS;
+        let a in 0 .. MAX_ITEMS;
+        let caller = setup_name::<T>();
+        let tx = RollupTx {
+            conds: vec![],
+            actions: (0..a)
+                .map(|_| Action::EmitEvent(Default::default()).encode())
+                .map(ActionBytes::truncate_from)
+                .collect(),
+            updates: (0..u).map(|i| (key(i), Some(ValueBytes::truncate_from(vec![0u8; 32])))).collect(),
+        };
+    }: _(RawOrigin::Signed(caller), NAME, tx, 0u128)
+
+    // One condition of each variant, the rollup itself is weighed by `rollup`
+    cond_eq {
+        setup_state::<T>();
+        let cond = Cond::Eq(key(0), Some(ValueBytes::truncate_from(0u32.encode())));
+    }: { assert!(Pallet::<T>::cond_met(&NAME, cond)); }
+
+    cond_not_eq {
+        setup_state::<T>();
+        let cond = Cond::NotEq(key(0), None);
+    }: { assert!(Pallet::<T>::cond_met(&NAME, cond)); }
+
+    cond_exists {
+        setup_state::<T>();
+        let cond = Cond::Exists(key(0));
+    }: { assert!(Pallet::<T>::cond_met(&NAME, cond)); }
+
+    cond_absent {
+        setup_state::<T>();
+        let cond = Cond::Absent(key(1));
+    }: { assert!(Pallet::<T>::cond_met(&NAME, cond)); }
+
+    cond_u32_ge {
+        setup_state::<T>();
+        let cond = Cond::U32Ge(key(0), 0);
+    }: { assert!(Pallet::<T>::cond_met(&NAME, cond)); }
+
+    cond_u32_le {
+        setup_state::<T>();
+        let cond = Cond::U32Le(key(0), 0);
+    }: { assert!(Pallet::<T>::cond_met(&NAME, cond)); }
+
+    cond_block_number_before {
+        let cond = Cond::BlockNumberBefore(u32::MAX);
+    }: { assert!(Pallet::<T>::cond_met(&NAME, cond)); }
+
+    // One action of each variant, the replies and runtime calls are weighed by their handlers
+    action_reply {
+        let caller = setup_name::<T>();
+        let act = Action::Reply(ActionBytes::truncate_from(vec![0u8; MAX_ACTION_DATA]));
+    }: {
+        // The handler rejects the data, only the dispatch to it is measured
+        let _ = Pallet::<T>::exec_action(NAME, &caller, 0, act);
+    }
+
+    action_set_queue_head {
+        let n in 0 .. Pallet::<T>::queue_capacity(0).unwrap_or_default();
+        let caller = setup_name::<T>();
+        for _ in 0..n {
+            Pallet::<T>::push_message(&NAME, 0, ValueBytes::truncate_from(vec![0u8; 32]))
+                .expect("the queue has room; qed.");
+        }
+    }: {
+        Pallet::<T>::exec_action(NAME, &caller, 0, Action::SetQueueHeadOf(0, n))
+            .expect("the head is in the queue; qed.");
+    }
+    verify {
+        assert_eq!(Pallet::<T>::queue_len(&NAME, 0), 0);
+    }
+
+    action_call_runtime {
+        let caller = setup_name::<T>();
+        let call: <T as Config>::RuntimeCall = frame_system::Call::<T>::remark {
+            remark: vec![0u8; MAX_ACTION_DATA],
+        }
+        .into();
+        let act = Action::CallRuntime(call.encode());
+    }: {
+        // The call may be filtered, its dispatch is weighed by its own dispatch info
+        let _ = Pallet::<T>::exec_action(NAME, &caller, 0, act);
+    }
+
+    action_push_to_queue {
+        let caller = setup_name::<T>();
+        let data = ValueBytes::truncate_from(vec![0u8; MAX_ACTION_DATA]);
+        let act = Action::PushToQueueOf(H256([2u8; 32]), 0, data);
+    }: {
+        Pallet::<T>::exec_action(NAME, &caller, 0, act).expect("the queue has room; qed.");
+    }
+    verify {
+        assert_eq!(Pallet::<T>::queue_len(&H256([2u8; 32]), 0), 1);
+    }
+
+    action_emit_event {
+        let caller = setup_name::<T>();
+        let act = Action::EmitEvent(ActionBytes::truncate_from(vec![0u8; MAX_ACTION_DATA]));
+    }: {
+        Pallet::<T>::exec_action(NAME, &caller, 0, act).expect("events can be emitted; qed.");
+    }
+}
+
+impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
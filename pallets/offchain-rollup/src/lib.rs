@@ -2,7 +2,12 @@
 //! # Phat Contract Offchain Rollup
 
 pub mod anchor;
+pub mod migrations;
 pub mod types;
+pub mod weights;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
 
 #[cfg(test)]
 mod mock;
@@ -0,0 +1,65 @@
+//! Storage migrations of the anchor pallet
+
+use crate::{
+    anchor::{
+        Config, Pallet, States, StorageDeposits, StorageUsageByNames, SubmitterByNames,
+    },
+    types::StorageUsage,
+};
+use frame_support::{
+    traits::{Get, OnRuntimeUpgrade, ReservableCurrency, StorageVersion},
+    weights::Weight,
+};
+use sp_std::marker::PhantomData;
+
+pub mod v1 {
+    use super::*;
+
+    /// Accounts the kv-store states written before the storage deposits were introduced
+    ///
+    /// The usage of each name is recomputed from its states, and the deposit of the submitter is
+    /// adjusted to it. A deposit the submitter can't afford is topped up by its next rollup
+    /// transaction.
+    pub struct MigrateToV1<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+        fn on_runtime_upgrade() -> Weight {
+            if StorageVersion::get::<Pallet<T>>() != 0 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            let (mut reads, mut writes) = (1u64, 1u64);
+            for (name, submitter) in SubmitterByNames::<T>::iter() {
+                let mut usage = StorageUsage::default();
+                for (key, value) in States::<T>::iter_prefix(name) {
+                    reads += 1;
+                    if Pallet::<T>::is_queue_key(&key) {
+                        continue;
+                    }
+                    usage.items = usage.items.saturating_add(1);
+                    usage.bytes = usage.bytes.saturating_add((key.len() + value.len()) as u32);
+                }
+
+                let required = Pallet::<T>::storage_deposit_of(&usage);
+                let held = StorageDeposits::<T>::get(name);
+                let held = if required > held {
+                    match T::Currency::reserve(&submitter, required - held) {
+                        Ok(()) => required,
+                        Err(_) => held,
+                    }
+                } else {
+                    T::Currency::unreserve(&submitter, held - required);
+                    required
+                };
+                StorageUsageByNames::<T>::insert(name, usage);
+                StorageDeposits::<T>::insert(name, held);
+                reads += 3;
+                writes += 3;
+            }
+
+            StorageVersion::new(1).put::<Pallet<T>>();
+            log::info!(target: "runtime::anchor", "Migrated the storage usage to v1");
+            T::DbWeight::get().reads_writes(reads, writes)
+        }
+    }
+}
@@ -2,12 +2,16 @@ use crate::anchor as pallet_anchor;
 use pallet_oracle;
 use pallet_registry;
 
-use frame_support::{pallet_prelude::ConstU32, parameter_types};
+use frame_support::{
+    pallet_prelude::ConstU32,
+    parameter_types,
+    traits::{ConstU128, Contains},
+};
 use frame_system as system;
 use sp_core::H256;
 use sp_runtime::{
-    testing::Header,
-    traits::{BlakeTwo256, IdentityLookup},
+    testing::{Header, TestSignature, TestXt, UintAuthorityId},
+    traits::{BlakeTwo256, Hash, IdentityLookup},
 };
 
 pub(crate) type Balance = u128;
@@ -97,6 +101,57 @@ impl pallet_timestamp::Config for Test {
 impl pallet_oracle::Config for Test {
     type RuntimeEvent = RuntimeEvent;
     type QuotesCount = ConstU32<1>;
+    type DataCount = ConstU32<1>;
+    type OracleRandomness = TestRandomness;
+    type AuthorityId = TestAuthId;
+    type Quorum = ConstU32<1>;
+    type MaxDeviationBps = ConstU32<10_000>;
+    type RequestFee = ConstU128<0>;
+    type OnRequestFee = ();
+    type ForceOrigin = frame_system::EnsureRoot<u64>;
+    type WeightInfo = ();
+}
+
+pub struct TestAuthId;
+impl frame_system::offchain::AppCrypto<UintAuthorityId, TestSignature> for TestAuthId {
+    type RuntimeAppPublic = UintAuthorityId;
+    type GenericSignature = TestSignature;
+    type GenericPublic = UintAuthorityId;
+}
+
+impl frame_system::offchain::SigningTypes for Test {
+    type Public = UintAuthorityId;
+    type Signature = TestSignature;
+}
+
+impl<C> frame_system::offchain::SendTransactionTypes<C> for Test
+where
+    RuntimeCall: From<C>,
+{
+    type OverarchingCall = RuntimeCall;
+    type Extrinsic = TestXt<RuntimeCall, ()>;
+}
+
+impl<C> frame_system::offchain::CreateSignedTransaction<C> for Test
+where
+    RuntimeCall: From<C>,
+{
+    fn create_transaction<A: frame_system::offchain::AppCrypto<Self::Public, Self::Signature>>(
+        call: RuntimeCall,
+        _public: UintAuthorityId,
+        account: u64,
+        _nonce: u64,
+    ) -> Option<(RuntimeCall, (u64, ()))> {
+        Some((call, (account, ())))
+    }
+}
+
+/// Deterministic randomness derived from the subject, good enough for tests
+pub struct TestRandomness;
+impl frame_support::traits::Randomness<H256, u64> for TestRandomness {
+    fn random(subject: &[u8]) -> (H256, u64) {
+        (BlakeTwo256::hash(subject), System::block_number())
+    }
 }
 
 impl pallet_registry::Config for Test {
@@ -104,17 +159,44 @@ impl pallet_registry::Config for Test {
     type MaxUrlSize = ConstU32<256>;
     type MaxKeySize = ConstU32<256>;
     type MaxPathSize = ConstU32<256>;
+    type MaxBodySize = ConstU32<1024>;
+    type Currency = Balances;
+    type FeedBond = ConstU128<0>;
+    type ForceOrigin = frame_system::EnsureRoot<u64>;
 }
 
 impl pallet_anchor::Config for Test {
     type RuntimeEvent = RuntimeEvent;
     type OnResponse = Oracle;
     type QueuePrefix = QueuePrefix;
-    type QueueCapacity = ConstU32<3>;
+    type QueueCapacities = QueueCapacities;
+    type Currency = Balances;
+    type NameDeposit = NameDeposit;
+    type GovernanceOrigin = frame_system::EnsureRoot<u64>;
+    type ForceOrigin = frame_system::EnsureRoot<u64>;
+    type DepositPerItem = DepositPerItem;
+    type DepositPerByte = DepositPerByte;
+    type MaxStorageItems = ConstU32<64>;
+    type MaxStorageBytes = ConstU32<1024>;
+    type WeightInfo = ();
+    type RuntimeCall = RuntimeCall;
+    type CallFilter = AnchorCallFilter;
+}
+
+/// Only allows the contracts to dispatch balance calls
+pub struct AnchorCallFilter;
+impl Contains<RuntimeCall> for AnchorCallFilter {
+    fn contains(call: &RuntimeCall) -> bool {
+        matches!(call, RuntimeCall::Balances(_))
+    }
 }
 
 parameter_types! {
     pub const QueuePrefix: &'static [u8] = b"_queue/";
+    pub storage QueueCapacities: Vec<u32> = vec![3, 4];
+    pub const NameDeposit: Balance = 10 * DOLLARS;
+    pub const DepositPerItem: Balance = CENTS;
+    pub const DepositPerByte: Balance = CENTS / 100;
 }
 
 pub const DOLLARS: Balance = 1_000_000_000_000;
@@ -1,11 +1,13 @@
 use codec::{Decode, Encode};
 use frame_support::BoundedVec;
-use sp_core::ConstU32;
+use sp_core::{ConstU32, H256};
 use sp_std::vec::Vec;
 
 pub type ActionBytes = BoundedVec<u8, ConstU32<256>>;
 pub type KeyBytes = BoundedVec<u8, ConstU32<128>>;
 pub type ValueBytes = BoundedVec<u8, ConstU32<2048>>;
+/// The id of a message queue of a name, lower ids are drained first
+pub type QueueId = u8;
 
 // Almost copied from `phat-offchain-rollup/phat/src/lib.rs`.
 #[derive(Debug, Default, PartialEq, Eq, Encode, Decode, Clone, scale_info::TypeInfo)]
@@ -17,13 +19,68 @@ pub struct RollupTx {
 
 #[derive(Debug, PartialEq, Eq, Encode, Decode, Clone, scale_info::TypeInfo)]
 pub enum Cond {
+    /// The value of the key equals to the given one (`None` for absent)
     Eq(KeyBytes, Option<ValueBytes>),
+    /// The value of the key differs from the given one (`None` for absent)
+    NotEq(KeyBytes, Option<ValueBytes>),
+    /// The key exists
+    Exists(KeyBytes),
+    /// The key doesn't exist
+    Absent(KeyBytes),
+    /// The SCALE-encoded `u32` value of the key (0 if absent) is at least the given one
+    U32Ge(KeyBytes, u32),
+    /// The SCALE-encoded `u32` value of the key (0 if absent) is at most the given one
+    U32Le(KeyBytes, u32),
+    /// The current block number is lower than the given one
+    BlockNumberBefore(u32),
 }
 
 // Defined for our own usage for now
-
+//
+// The action enum is versioned by the codec indices of its variants. The contracts built against
+// an older version keep working as long as the existing indices are never changed or reused, so
+// new actions must always be appended with a new index.
 #[derive(Debug, PartialEq, Eq, Encode, Decode, Clone, scale_info::TypeInfo)]
 pub enum Action {
+    /// Sends the data to the `OnResponse` handler (v1)
+    #[codec(index = 0)]
     Reply(ActionBytes),
+    /// Pops the processed messages from the queue of the name (v1)
+    #[codec(index = 1)]
     SetQueueHead(u32),
+    /// Dispatches a SCALE-encoded `RuntimeCall` as the submitter of the name (v2)
+    #[codec(index = 2)]
+    CallRuntime(Vec<u8>),
+    /// Pushes a message to the queue of another name (v2)
+    #[codec(index = 3)]
+    PushToQueue(H256, ValueBytes),
+    /// Emits the bytes as an event of the anchor pallet (v2)
+    #[codec(index = 4)]
+    EmitEvent(ActionBytes),
+    /// Pops the processed messages from the given queue of the name (v3)
+    #[codec(index = 5)]
+    SetQueueHeadOf(QueueId, u32),
+    /// Pushes a message to the given queue of another name (v3)
+    #[codec(index = 6)]
+    PushToQueueOf(H256, QueueId, ValueBytes),
+}
+
+/// The kv-store usage of a name
+#[derive(
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Encode,
+    Decode,
+    Clone,
+    Copy,
+    scale_info::TypeInfo,
+    codec::MaxEncodedLen,
+)]
+pub struct StorageUsage {
+    /// Number of entries
+    pub items: u32,
+    /// Total size of the keys and values
+    pub bytes: u32,
 }
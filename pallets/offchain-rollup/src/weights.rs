@@ -0,0 +1,179 @@
+//! Weights for phat_offchain_rollup
+//!
+//! Generated with `benchmark pallet --pallet phat_offchain_rollup --extrinsic '*'` from the
+//! benchmarks in `benchmarking.rs`. Until it is run on the reference hardware, the values are
+//! estimates.
+//!
+//! `rollup` only covers the decoding of the actions. `Pallet::rollup_weight` adds the weight of
+//! each condition and action, plus the weight of the replies and runtime calls themselves.
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{
+    traits::Get,
+    weights::{constants::RocksDbWeight, Weight},
+};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for phat_offchain_rollup.
+pub trait WeightInfo {
+    fn claim_name() -> Weight;
+    fn rollup(u: u32, a: u32) -> Weight;
+    fn rotate_submitter() -> Weight;
+    fn cond_eq() -> Weight;
+    fn cond_not_eq() -> Weight;
+    fn cond_exists() -> Weight;
+    fn cond_absent() -> Weight;
+    fn cond_u32_ge() -> Weight;
+    fn cond_u32_le() -> Weight;
+    fn cond_block_number_before() -> Weight;
+    fn action_reply() -> Weight;
+    fn action_set_queue_head(n: u32) -> Weight;
+    fn action_call_runtime() -> Weight;
+    fn action_push_to_queue() -> Weight;
+    fn action_emit_event() -> Weight;
+}
+
+/// Weights for phat_offchain_rollup using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+    fn claim_name() -> Weight {
+        Weight::from_ref_time(28_000_000)
+            .saturating_add(T::DbWeight::get().reads(2 as u64))
+            .saturating_add(T::DbWeight::get().writes(4 as u64))
+    }
+    fn rotate_submitter() -> Weight {
+        Weight::from_ref_time(36_000_000)
+            .saturating_add(T::DbWeight::get().reads(5 as u64))
+            .saturating_add(T::DbWeight::get().writes(5 as u64))
+    }
+    fn rollup(u: u32, a: u32, ) -> Weight {
+        Weight::from_ref_time(45_000_000)
+            .saturating_add(Weight::from_ref_time(9_500_000).saturating_mul(u as u64))
+            .saturating_add(Weight::from_ref_time(12_000_000).saturating_mul(a as u64))
+            .saturating_add(T::DbWeight::get().reads(4 as u64))
+            .saturating_add(T::DbWeight::get().writes(2 as u64))
+            .saturating_add(T::DbWeight::get().reads((2 as u64).saturating_mul(u as u64)))
+            .saturating_add(T::DbWeight::get().writes((2 as u64).saturating_mul(u as u64)))
+    }
+    fn cond_eq() -> Weight {
+        Weight::from_ref_time(4_100_000)
+            .saturating_add(T::DbWeight::get().reads(1 as u64))
+    }
+    fn cond_not_eq() -> Weight {
+        Weight::from_ref_time(4_100_000)
+            .saturating_add(T::DbWeight::get().reads(1 as u64))
+    }
+    fn cond_exists() -> Weight {
+        Weight::from_ref_time(3_200_000)
+            .saturating_add(T::DbWeight::get().reads(1 as u64))
+    }
+    fn cond_absent() -> Weight {
+        Weight::from_ref_time(3_200_000)
+            .saturating_add(T::DbWeight::get().reads(1 as u64))
+    }
+    fn cond_u32_ge() -> Weight {
+        Weight::from_ref_time(4_300_000)
+            .saturating_add(T::DbWeight::get().reads(1 as u64))
+    }
+    fn cond_u32_le() -> Weight {
+        Weight::from_ref_time(4_300_000)
+            .saturating_add(T::DbWeight::get().reads(1 as u64))
+    }
+    fn cond_block_number_before() -> Weight {
+        Weight::from_ref_time(1_000_000)
+    }
+    fn action_reply() -> Weight {
+        Weight::from_ref_time(3_100_000)
+    }
+    fn action_set_queue_head(n: u32, ) -> Weight {
+        Weight::from_ref_time(8_200_000)
+            .saturating_add(Weight::from_ref_time(2_900_000).saturating_mul(n as u64))
+            .saturating_add(RocksDbWeight::get().reads(2 as u64))
+            .saturating_add(RocksDbWeight::get().writes(1 as u64))
+            .saturating_add(RocksDbWeight::get().writes((1 as u64).saturating_mul(n as u64)))
+    }
+    fn action_call_runtime() -> Weight {
+        Weight::from_ref_time(6_400_000)
+    }
+    fn action_push_to_queue() -> Weight {
+        Weight::from_ref_time(14_500_000)
+            .saturating_add(RocksDbWeight::get().reads(3 as u64))
+            .saturating_add(RocksDbWeight::get().writes(2 as u64))
+    }
+    fn action_emit_event() -> Weight {
+        Weight::from_ref_time(2_600_000)
+    }
+    fn action_reply() -> Weight {
+        Weight::from_ref_time(3_100_000)
+    }
+    fn action_set_queue_head(n: u32, ) -> Weight {
+        Weight::from_ref_time(8_200_000)
+            .saturating_add(Weight::from_ref_time(2_900_000).saturating_mul(n as u64))
+            .saturating_add(T::DbWeight::get().reads(2 as u64))
+            .saturating_add(T::DbWeight::get().writes(1 as u64))
+            .saturating_add(T::DbWeight::get().writes((1 as u64).saturating_mul(n as u64)))
+    }
+    fn action_call_runtime() -> Weight {
+        Weight::from_ref_time(6_400_000)
+    }
+    fn action_push_to_queue() -> Weight {
+        Weight::from_ref_time(14_500_000)
+            .saturating_add(T::DbWeight::get().reads(3 as u64))
+            .saturating_add(T::DbWeight::get().writes(2 as u64))
+    }
+    fn action_emit_event() -> Weight {
+        Weight::from_ref_time(2_600_000)
+    }
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+    fn claim_name() -> Weight {
+        Weight::from_ref_time(28_000_000)
+            .saturating_add(RocksDbWeight::get().reads(2 as u64))
+            .saturating_add(RocksDbWeight::get().writes(4 as u64))
+    }
+    fn rotate_submitter() -> Weight {
+        Weight::from_ref_time(36_000_000)
+            .saturating_add(RocksDbWeight::get().reads(5 as u64))
+            .saturating_add(RocksDbWeight::get().writes(5 as u64))
+    }
+    fn rollup(u: u32, a: u32, ) -> Weight {
+        Weight::from_ref_time(45_000_000)
+            .saturating_add(Weight::from_ref_time(9_500_000).saturating_mul(u as u64))
+            .saturating_add(Weight::from_ref_time(12_000_000).saturating_mul(a as u64))
+            .saturating_add(RocksDbWeight::get().reads(4 as u64))
+            .saturating_add(RocksDbWeight::get().writes(2 as u64))
+            .saturating_add(RocksDbWeight::get().reads((2 as u64).saturating_mul(u as u64)))
+            .saturating_add(RocksDbWeight::get().writes((2 as u64).saturating_mul(u as u64)))
+    }
+    fn cond_eq() -> Weight {
+        Weight::from_ref_time(4_100_000)
+            .saturating_add(RocksDbWeight::get().reads(1 as u64))
+    }
+    fn cond_not_eq() -> Weight {
+        Weight::from_ref_time(4_100_000)
+            .saturating_add(RocksDbWeight::get().reads(1 as u64))
+    }
+    fn cond_exists() -> Weight {
+        Weight::from_ref_time(3_200_000)
+            .saturating_add(RocksDbWeight::get().reads(1 as u64))
+    }
+    fn cond_absent() -> Weight {
+        Weight::from_ref_time(3_200_000)
+            .saturating_add(RocksDbWeight::get().reads(1 as u64))
+    }
+    fn cond_u32_ge() -> Weight {
+        Weight::from_ref_time(4_300_000)
+            .saturating_add(RocksDbWeight::get().reads(1 as u64))
+    }
+    fn cond_u32_le() -> Weight {
+        Weight::from_ref_time(4_300_000)
+            .saturating_add(RocksDbWeight::get().reads(1 as u64))
+    }
+    fn cond_block_number_before() -> Weight {
+        Weight::from_ref_time(1_000_000)
+    }
+}
@@ -0,0 +1,71 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+//! Runtime API to query the oracle feeds, requests and results
+
+use codec::{Codec, Decode, Encode};
+use scale_info::TypeInfo;
+use sp_core::H256;
+use sp_std::vec::Vec;
+
+pub use pallet_oracle::types::{FeedInfo, LatestValue, RequestInfo, ResultInfo};
+
+/// Status of a value combined by `para_oracle`
+#[derive(Debug, PartialEq, Eq, Encode, Decode, Clone, Copy, TypeInfo)]
+pub enum ValueStatus {
+    Valid,
+    /// Older than the max age of the circuit breaker of the key
+    Stale,
+    /// The key is frozen
+    Frozen,
+}
+
+/// A value combined from the feeders by `para_oracle`
+#[derive(Debug, PartialEq, Eq, Encode, Decode, Clone, TypeInfo)]
+pub struct CombinedValue {
+    pub value: i64,
+    pub timestamp: u128,
+    pub status: ValueStatus,
+}
+
+/// The latest reporting round of a `para_oracle` key
+#[derive(Debug, PartialEq, Eq, Encode, Decode, Clone, TypeInfo)]
+pub struct RoundInfo<BlockNumber> {
+    pub round_id: u32,
+    pub started_at: BlockNumber,
+    pub deadline: BlockNumber,
+    pub submissions: u32,
+    pub answer: Option<i64>,
+    pub updated_at: Option<u128>,
+    pub answered_in_round: u32,
+}
+
+sp_api::decl_runtime_apis! {
+    /// Version 2 serves the values combined by `para_oracle`. The `combined_value` of version 1
+    /// is a stub always returning `None`, which the clients must not rely on.
+    #[api_version(2)]
+    pub trait OracleApi<AccountId, BlockNumber>
+    where
+        AccountId: Codec,
+        BlockNumber: Codec,
+    {
+        /// Returns the registry feed `key` of `owner`
+        fn get_feed(owner: AccountId, key: Vec<u8>) -> Option<FeedInfo<AccountId, BlockNumber>>;
+        /// Returns the registry feeds, optionally filtered by owner and by topic (feed key)
+        fn list_feeds(
+            owner: Option<AccountId>,
+            topic: Option<Vec<u8>>,
+        ) -> Vec<FeedInfo<AccountId, BlockNumber>>;
+        /// Returns the request `request_id`
+        fn get_request(request_id: H256) -> Option<RequestInfo<AccountId>>;
+        /// Returns the responses of the Phat contracts to the request `request_id`
+        fn get_result(request_id: H256) -> Vec<ResultInfo>;
+        /// Returns the latest response received for the topic (feed key)
+        fn latest_value(topic: Vec<u8>) -> Option<LatestValue<BlockNumber>>;
+        /// Returns the value combined by `para_oracle` for the key
+        fn combined_value(key: Vec<u8>) -> Option<CombinedValue>;
+        /// Returns the time-weighted average of the value combined by `para_oracle` for the key
+        /// over the last `window` milliseconds
+        fn twap(key: Vec<u8>, window: u128) -> Option<CombinedValue>;
+        /// Returns the latest reporting round of the `para_oracle` key
+        fn latest_round_data(key: Vec<u8>) -> Option<RoundInfo<BlockNumber>>;
+    }
+}
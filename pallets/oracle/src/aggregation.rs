@@ -0,0 +1,56 @@
+//! Aggregation of multi-source values
+//!
+//! The Phat Contract aggregates the sources of a multi-source request off-chain, and the pallet
+//! replays the same computation to verify the reply. Must be aligned with the Phat Contract.
+
+use sp_std::vec::Vec;
+
+/// Returns the median of the values, the mean of the two middle values (rounded down) for an even
+/// count
+pub fn median(values: &[u128]) -> Option<u128> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 0 {
+        // Won't overflow since both are below `u128::MAX`
+        Some(sorted[mid - 1] / 2 + sorted[mid] / 2 + (sorted[mid - 1] % 2 + sorted[mid] % 2) / 2)
+    } else {
+        Some(sorted[mid])
+    }
+}
+
+/// Returns the median of the values within `max_deviation_bps` of the median of all the values
+pub fn aggregate(values: &[u128], max_deviation_bps: u32) -> Option<u128> {
+    let center = median(values)?;
+    let max_deviation = center.saturating_mul(max_deviation_bps as u128) / 10_000;
+    let kept: Vec<u128> = values
+        .iter()
+        .copied()
+        .filter(|v| v.abs_diff(center) <= max_deviation)
+        .collect();
+    median(&kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn median_works() {
+        assert_eq!(median(&[]), None);
+        assert_eq!(median(&[3, 1, 2]), Some(2));
+        assert_eq!(median(&[4, 1, 3, 2]), Some(2));
+        assert_eq!(median(&[u128::MAX, u128::MAX]), Some(u128::MAX));
+    }
+
+    #[test]
+    fn aggregate_drops_outliers() {
+        // 1% tolerance drops the 200
+        assert_eq!(aggregate(&[100, 101, 99, 200], 100), Some(100));
+        // Everything is kept with a loose tolerance
+        assert_eq!(aggregate(&[100, 200, 300], 10_000), Some(200));
+    }
+}
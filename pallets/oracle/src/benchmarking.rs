@@ -0,0 +1,68 @@
+//! Benchmarking setup for pallet-oracle
+
+use crate::{types::MAX_SOURCES, Call, Config, Pallet};
+use frame_benchmarking::{benchmarks, whitelisted_caller};
+use frame_support::{traits::Currency, BoundedVec};
+use frame_system::RawOrigin;
+use pallet_registry::types::{ApiFeedStatus, RegistryFeedKey};
+use phat_offchain_rollup::anchor as pallet_anchor;
+use sp_core::H256;
+use sp_runtime::traits::Bounded;
+use sp_std::prelude::*;
+
+const NAME: H256 = H256([1u8; 32]);
+
+/// Returns a funded caller, with `NAME` claimed so that the requests have a queue to go to
+fn setup_caller<T: Config>() -> T::AccountId {
+    let caller: T::AccountId = whitelisted_caller();
+    <T as pallet_anchor::Config>::Currency::make_free_balance_be(
+        &caller,
+        pallet_anchor::BalanceOf::<T>::max_value() / 2u32.into(),
+    );
+    <T as pallet_registry::Config>::Currency::make_free_balance_be(
+        &caller,
+        pallet_registry::BalanceOf::<T>::max_value() / 2u32.into(),
+    );
+    pallet_anchor::Pallet::<T>::claim_name(RawOrigin::Signed(caller.clone()).into(), NAME, None)
+        .expect("claim_name should work");
+    caller
+}
+
+/// Registers the active feed `i` of the caller
+fn setup_feed<T: Config>(caller: &T::AccountId, i: u32) -> RegistryFeedKey<T> {
+    let key = BoundedVec::truncate_from(i.to_le_bytes().to_vec());
+    pallet_registry::Pallet::<T>::register_feed(
+        RawOrigin::Signed(caller.clone()).into(),
+        key.clone(),
+        BoundedVec::truncate_from(b"https://api.example.com/price".to_vec()),
+        BoundedVec::truncate_from(b"/price".to_vec()),
+        None,
+        None,
+    )
+    .expect("register_feed should work");
+    pallet_registry::ApiFeeds::<T>::mutate(caller, &key, |feed| {
+        if let Some(feed) = feed {
+            feed.status = ApiFeedStatus::Active;
+        }
+    });
+    key
+}
+
+benchmarks! {
+    request_multi_source {
+        let s in 1 .. MAX_SOURCES;
+        let caller = setup_caller::<T>();
+        let keys: Vec<_> = (0..s).map(|i| setup_feed::<T>(&caller, i)).collect();
+        let keys = BoundedVec::truncate_from(keys);
+    }: _(RawOrigin::Signed(caller), keys, s, 10_000, 0, 0u128)
+
+    feeds {
+        let caller: T::AccountId = whitelisted_caller();
+    }: _(RawOrigin::Signed(caller), NAME, Default::default(), 0u128)
+
+    average {
+        let caller: T::AccountId = whitelisted_caller();
+    }: {
+        let _ = Pallet::<T>::average(RawOrigin::Signed(caller).into());
+    }
+}
@@ -0,0 +1,295 @@
+//! Off-chain worker fallback executor
+//!
+//! When no Phat contract serves the requests, the off-chain worker can act as the oracle contract
+//! of the anchor name `FallbackName`, set at genesis. It claims the name with its local key,
+//! drains the queues of the name by fetching the requested feeds over HTTP, and replies in signed
+//! `rollup` transactions, exactly as the oracle Phat contract does. It's meant to provide a
+//! self-contained oracle on the development chains, and is disabled on the other ones.
+//!
+//! The requests relying on the secrets of the Phat contract can't be served and are skipped, and
+//! so are the sources relying on them in the multi-source requests.
+
+use codec::{Decode, Encode};
+use frame_system::offchain::{AppCrypto, SendSignedTransaction, Signer};
+use phat_offchain_rollup::{
+    anchor as pallet_anchor,
+    types::{Action, ActionBytes, Cond, QueueId, RollupTx},
+};
+use sp_core::{sr25519, H256};
+use sp_runtime::{
+    offchain::{
+        http,
+        storage::{MutateStorageError, StorageRetrievalError, StorageValueRef},
+        Duration,
+    },
+    traits::{IdentifyAccount, UniqueSaturatedInto},
+    RuntimeAppPublic,
+};
+use sp_std::{prelude::*, str};
+
+use crate::{
+    aggregation,
+    types::{MultiSourceResponse, PhatRequest, ResponseRecord, SignedResponse},
+    Config, FallbackName, Pallet,
+};
+
+/// Number of decimals of the fixed-point values, shared with the Phat contract
+pub use paraxiom_feed_value::VALUE_DECIMALS;
+
+/// Maximum number of requests served by a single rollup transaction
+const MAX_BATCH: u32 = 4;
+
+/// Number of blocks to wait for the inclusion of a transaction before sending the next one
+const GRACE_PERIOD: u32 = 3;
+
+/// Time limit of the HTTP requests of a feed request
+const HTTP_DEADLINE_MS: u64 = 10_000;
+
+type AppPublicOf<T> = <<T as Config>::AuthorityId as AppCrypto<
+    <T as frame_system::offchain::SigningTypes>::Public,
+    <T as frame_system::offchain::SigningTypes>::Signature,
+>>::RuntimeAppPublic;
+
+impl<T: Config> Pallet<T> {
+    /// Serves the queues of the fallback name, claiming it first if needed
+    pub(crate) fn run_fallback_executor(block_number: T::BlockNumber) -> Result<(), &'static str> {
+        let Some(name) = FallbackName::<T>::get() else {
+            return Ok(());
+        };
+        let keys = AppPublicOf::<T>::all();
+        if keys.is_empty() {
+            return Ok(());
+        }
+
+        let submitter = pallet_anchor::SubmitterByNames::<T>::get(name);
+        // The name may be claimed by another account, e.g. a Phat contract took over
+        let key = match &submitter {
+            Some(submitter) => {
+                let Some(key) = keys.into_iter().find(|key| Self::account_of(key) == *submitter)
+                else {
+                    return Ok(());
+                };
+                key
+            }
+            None => keys[0].clone(),
+        };
+        if !Self::acquire_fallback_slot(block_number) {
+            return Ok(());
+        }
+
+        let call = match submitter {
+            None => {
+                let attestation_key = sr25519::Public::try_from(&key.to_raw_vec()[..])
+                    .map_err(|_| "Fallback key is not sr25519")?;
+                pallet_anchor::Call::claim_name {
+                    name,
+                    attestation_key: Some(attestation_key),
+                }
+            }
+            Some(submitter) => {
+                let Some(tx) = Self::fallback_rollup_tx(name, &key, submitter) else {
+                    return Ok(());
+                };
+                pallet_anchor::Call::rollup {
+                    name,
+                    tx,
+                    nonce: block_number.unique_saturated_into(),
+                }
+            }
+        };
+
+        let public: T::Public = Self::generic_public(&key).into();
+        let result = Signer::<T, T::AuthorityId>::any_account()
+            .with_filter(vec![public])
+            .send_signed_transaction(|_| call.clone());
+        match result {
+            Some((_, Ok(()))) => Ok(()),
+            Some((_, Err(()))) => Err("Failed to submit the transaction"),
+            None => Err("No local account available"),
+        }
+    }
+
+    /// Returns the rollup transaction serving the first non-empty queue of the name, if any
+    fn fallback_rollup_tx(
+        name: H256,
+        key: &AppPublicOf<T>,
+        submitter: T::AccountId,
+    ) -> Option<RollupTx> {
+        let (queue, items) = (0..pallet_anchor::Pallet::<T>::queue_count())
+            .map(|queue| queue as QueueId)
+            .map(|queue| {
+                let items = pallet_anchor::Pallet::<T>::queue_items(&name, queue, 0, MAX_BATCH);
+                (queue, items)
+            })
+            .find(|(_, items)| !items.is_empty())?;
+        let (last, _) = items.last()?;
+        let head = last + 1;
+
+        let mut actions = Vec::new();
+        for (index, raw) in items {
+            match Self::serve_request(name, key, &submitter, &raw) {
+                Ok(reply) => actions.push(reply),
+                // Skip the request, it would block the queue otherwise
+                Err(err) => log::warn!(
+                    target: "runtime::oracle",
+                    "Skipped request {} of queue {}: {}",
+                    index,
+                    queue,
+                    err
+                ),
+            }
+        }
+        actions.push(
+            Action::SetQueueHeadOf(queue, head)
+                .encode()
+                .try_into()
+                .expect("SetQueueHeadOf fits in an action; qed."),
+        );
+
+        // Guard against replying twice if the previous transaction is eventually included
+        let mut head_key = pallet_anchor::Pallet::<T>::queue_prefix(queue);
+        head_key.extend_from_slice(b"_head");
+        let head_value = pallet_anchor::Pallet::<T>::get_state(&name, &head_key)
+            .map(|value| value.try_into().expect("Stored values fit; qed."));
+        let head_key = head_key.try_into().ok()?;
+
+        Some(RollupTx {
+            conds: vec![Cond::Eq(head_key, head_value)],
+            actions,
+            updates: Vec::new(),
+        })
+    }
+
+    /// Fetches a request popped from the queue and returns the action replying the signed
+    /// response
+    fn serve_request(
+        name: H256,
+        key: &AppPublicOf<T>,
+        submitter: &T::AccountId,
+        raw: &[u8],
+    ) -> Result<ActionBytes, &'static str> {
+        let (request_id, data) =
+            match PhatRequest::<T>::decode(&mut &raw[..]).map_err(|_| "Undecodable request")? {
+                PhatRequest::Single {
+                    request_id,
+                    url,
+                    path,
+                    secret_slot,
+                    body,
+                    ..
+                } => {
+                    if secret_slot.is_some() {
+                        return Err("Secrets are only available to the Phat contract");
+                    }
+                    let value = fetch_value(&url, &path, body.as_ref().map(|body| &body[..]))?;
+                    (request_id, value.encode())
+                }
+                PhatRequest::MultiSource {
+                    request_id,
+                    sources,
+                    min_sources,
+                    max_deviation_bps,
+                    ..
+                } => {
+                    // The sources relying on secrets count as failed
+                    let values: Vec<Option<u128>> = sources
+                        .iter()
+                        .map(|source| match source.secret_slot {
+                            Some(_) => None,
+                            None => fetch_value(
+                                &source.url,
+                                &source.path,
+                                source.body.as_ref().map(|body| &body[..]),
+                            )
+                            .ok(),
+                        })
+                        .collect();
+                    let succeeded: Vec<u128> = values.iter().flatten().copied().collect();
+                    if (succeeded.len() as u32) < min_sources {
+                        return Err("Not enough sources succeeded");
+                    }
+                    let value = aggregation::aggregate(&succeeded, max_deviation_bps)
+                        .ok_or("No source succeeded")?;
+                    let response = MultiSourceResponse {
+                        value,
+                        values,
+                        succeeded: succeeded.len() as u32,
+                    };
+                    (request_id, response.encode())
+                }
+            };
+
+        let record = ResponseRecord::<T> {
+            owner: submitter.clone(),
+            phat_contract_id: name,
+            request_id,
+            response_data: data.try_into().map_err(|_| "Response too large")?,
+            timestamp_ms: sp_io::offchain::timestamp().unix_millis(),
+        }
+        .encode();
+        let signature = key.sign(&record).ok_or("Failed to sign the response")?;
+        let signature = sr25519::Signature::decode(&mut &signature.encode()[..])
+            .map_err(|_| "Fallback key is not sr25519")?;
+        let reply = SignedResponse { record, signature }.encode();
+        Action::Reply(reply.try_into().map_err(|_| "Response too large")?)
+            .encode()
+            .try_into()
+            .map_err(|_| "Response too large for a reply action")
+    }
+
+    /// Acquires the right to send a transaction at this block
+    ///
+    /// Waits for `GRACE_PERIOD` blocks after each transaction, so that the workers running
+    /// concurrently or on consecutive blocks don't serve the same requests.
+    fn acquire_fallback_slot(block_number: T::BlockNumber) -> bool {
+        const RECENTLY_SENT: () = ();
+
+        let val = StorageValueRef::persistent(b"pallet_oracle::fallback_next_at");
+        let res = val.mutate(
+            |next_at: Result<Option<T::BlockNumber>, StorageRetrievalError>| match next_at {
+                Ok(Some(next_at)) if block_number < next_at => Err(RECENTLY_SENT),
+                _ => Ok(block_number + GRACE_PERIOD.into()),
+            },
+        );
+        match res {
+            Ok(_) => true,
+            Err(MutateStorageError::ValueFunctionFailed(RECENTLY_SENT)) => false,
+            Err(MutateStorageError::ConcurrentModification(_)) => false,
+        }
+    }
+
+    fn generic_public(
+        key: &AppPublicOf<T>,
+    ) -> <T::AuthorityId as AppCrypto<T::Public, T::Signature>>::GenericPublic {
+        key.clone().into()
+    }
+
+    fn account_of(key: &AppPublicOf<T>) -> T::AccountId {
+        let public: T::Public = Self::generic_public(key).into();
+        public.into_account()
+    }
+}
+
+/// Fetches the feed and returns its value at the JSON pointer `path`
+fn fetch_value(url: &[u8], path: &[u8], body: Option<&[u8]>) -> Result<u128, &'static str> {
+    let url = str::from_utf8(url).map_err(|_| "Invalid url")?;
+    let path = str::from_utf8(path).map_err(|_| "Invalid path")?;
+    let deadline = sp_io::offchain::timestamp().add(Duration::from_millis(HTTP_DEADLINE_MS));
+    let request = match body {
+        Some(body) => http::Request::post(url, vec![body])
+            .add_header("content-type", "application/json"),
+        None => http::Request::get(url),
+    };
+    let response = request
+        .deadline(deadline)
+        .send()
+        .map_err(|_| "Failed to send the request")?
+        .try_wait(deadline)
+        .map_err(|_| "Deadline reached")?
+        .map_err(|_| "Failed to fetch the feed")?;
+    if response.code != 200 {
+        return Err("Unexpected status code");
+    }
+    paraxiom_feed_value::extract_value(&response.body().collect::<Vec<u8>>(), path)
+        .map_err(|err| err.as_str())
+}
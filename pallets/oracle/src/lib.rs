@@ -4,37 +4,110 @@
 pub use self::pallet::*;
 pub use pallet::*;
 
+pub mod aggregation;
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod fallback;
 pub mod types;
+pub mod weights;
+pub use weights::WeightInfo;
+
+use sp_core::crypto::KeyTypeId;
+
+/// Key type of the off-chain worker fallback executor
+///
+/// The executor submits the rollup transactions of its anchor name and signs the responses with
+/// the sr25519 keys of this type, inserted via the `author_insertKey` RPC.
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"orfb");
+
+/// The crypto of the off-chain worker fallback executor
+pub mod crypto {
+    use super::KEY_TYPE;
+    use sp_runtime::{
+        app_crypto::{app_crypto, sr25519},
+        MultiSignature, MultiSigner,
+    };
+    app_crypto!(sr25519, KEY_TYPE);
+
+    pub struct FallbackAuthId;
+
+    impl frame_system::offchain::AppCrypto<MultiSigner, MultiSignature> for FallbackAuthId {
+        type RuntimeAppPublic = Public;
+        type GenericSignature = sp_core::sr25519::Signature;
+        type GenericPublic = sp_core::sr25519::Public;
+    }
+}
 
 #[frame_support::pallet]
 pub mod pallet {
     use core::convert::TryFrom;
 
-    use crate::types::*;
+    use crate::{aggregation, types::*, WeightInfo};
     use frame_support::{
-        dispatch::DispatchResult, pallet_prelude::*, traits::Randomness, traits::StorageVersion,
+        dispatch::DispatchResult,
+        pallet_prelude::*,
+        traits::{
+            Currency, ExistenceRequirement, OnUnbalanced, Randomness, StorageVersion,
+            WithdrawReasons,
+        },
         transactional, Blake2_128Concat, Twox64Concat,
     };
-    use frame_system::pallet_prelude::*;
+    use frame_system::{
+        offchain::{AppCrypto, CreateSignedTransaction},
+        pallet_prelude::*,
+    };
     use pallet_registry::types::RegistryFeedKey;
     use pallet_registry::ApiFeed;
-    use phat_offchain_rollup::anchor as pallet_anchor;
+    use phat_offchain_rollup::{anchor as pallet_anchor, types::QueueId};
     use sp_core::H256;
-    use sp_runtime::AccountId32;
+    use sp_runtime::{traits::Zero, AccountId32};
     use sp_std::vec::Vec;
 
     #[pallet::config]
     pub trait Config:
-        frame_system::Config + pallet_registry::Config + pallet_anchor::Config
+        frame_system::Config
+        + pallet_registry::Config
+        + pallet_anchor::Config
+        + CreateSignedTransaction<pallet_anchor::Call<Self>>
     {
         type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
         type QuotesCount: Get<u32>;
         type DataCount: Get<u32>;
         type OracleRandomness: Randomness<Self::Hash, Self::BlockNumber>;
+
+        /// The keys of the off-chain worker fallback executor, must be sr25519
+        type AuthorityId: AppCrypto<Self::Public, Self::Signature>;
+
+        /// Minimum number of sources of the multi-source requests
+        type Quorum: Get<u32>;
+
+        /// Maximum deviation from the median the multi-source requests may tolerate, in basis
+        /// points
+        type MaxDeviationBps: Get<u32>;
+
+        /// Fee charged to the caller of each request, in the anchor currency
+        type RequestFee: Get<pallet_anchor::BalanceOf<Self>>;
+
+        /// Handler of the request fees
+        type OnRequestFee: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+        /// The origin of the emergency operations, e.g. the technical committee
+        type ForceOrigin: EnsureOrigin<<Self as frame_system::Config>::RuntimeOrigin>;
+
+        type WeightInfo: WeightInfo;
     }
 
+    pub type NegativeImbalanceOf<T> = <<T as pallet_anchor::Config>::Currency as Currency<
+        <T as frame_system::Config>::AccountId,
+    >>::NegativeImbalance;
+
     const STORAGE_VERSION: StorageVersion = StorageVersion::new(0);
 
+    /// Weight of an sr25519 signature verification
+    const SR25519_VERIFY_WEIGHT: u64 = 60_000_000;
+    /// Weight of replaying the aggregation of up to `MAX_SOURCES` values
+    const AGGREGATE_WEIGHT: u64 = 5_000_000;
+
     #[pallet::pallet]
     #[pallet::generate_store(pub(super) trait Store)]
     #[pallet::storage_version(STORAGE_VERSION)]
@@ -63,6 +136,48 @@ pub mod pallet {
     #[pallet::storage]
     pub type FeedRequests<T: Config> = StorageMap<_, Twox64Concat, RequestId, Request<T>>;
 
+    /// Mapping for request ID -> the anchor name the request was pushed to
+    #[pallet::storage]
+    #[pallet::getter(fn request_names)]
+    pub type RequestNames<T: Config> = StorageMap<_, Twox64Concat, RequestId, H256>;
+
+    /// Mapping for multi-source request ID -> the registry feed keys of its sources, until the
+    /// request is responded
+    #[pallet::storage]
+    pub type RequestSources<T: Config> = StorageMap<
+        _,
+        Twox64Concat,
+        RequestId,
+        BoundedVec<RegistryFeedKey<T>, ConstU32<MAX_SOURCES>>,
+    >;
+
+    /// Mapping from (request ID, responding contract) -> response data
+    #[pallet::storage]
+    #[pallet::getter(fn responses)]
+    pub type Responses<T: Config> =
+        StorageDoubleMap<_, Twox64Concat, RequestId, Blake2_128Concat, H256, ResponseData>;
+
+    /// Mapping from registry feed key -> latest response to its requests
+    #[pallet::storage]
+    #[pallet::getter(fn latest_values)]
+    pub type LatestValues<T: Config> =
+        StorageMap<_, Blake2_128Concat, RegistryFeedKey<T>, LatestValue<T::BlockNumber>>;
+
+    /// The topics (registry feed keys) frozen by `Config::ForceOrigin`, which can't be
+    /// requested and whose latest value isn't updated
+    #[pallet::storage]
+    #[pallet::getter(fn frozen_topics)]
+    pub type FrozenTopics<T: Config> =
+        StorageMap<_, Blake2_128Concat, RegistryFeedKey<T>, bool, ValueQuery>;
+
+    /// The anchor name claimed by the off-chain worker fallback executor, the executor is disabled
+    /// without it
+    ///
+    /// Only set at genesis, by the chain specs of the development chains.
+    #[pallet::storage]
+    #[pallet::getter(fn fallback_name)]
+    pub type FallbackName<T: Config> = StorageValue<_, H256>;
+
     #[pallet::storage]
     #[pallet::getter(fn averages)]
     pub type Averages<T: Config> = StorageMap<_, Twox64Concat, Bytes, u128>;
@@ -94,6 +209,12 @@ pub mod pallet {
             request_id: RequestId,
             registry_feed_key: RegistryFeedKey<T>,
         },
+
+        /// A topic is frozen or unfrozen by `Config::ForceOrigin`
+        TopicFrozenSet {
+            topic: RegistryFeedKey<T>,
+            frozen: bool,
+        },
     }
 
     #[pallet::error]
@@ -106,7 +227,51 @@ pub mod pallet {
         ApiFeedNotActive,
         FailedToEncodeData,
         FailedToFindOracleFeeds,
+        /// The response doesn't match any request
         FailedToGetFeedRequest,
+        /// The request wasn't pushed to the responding contract
+        RequestNotSentToName,
+        /// The minimum number of sources must be within the number of requested feeds
+        InvalidMinSources,
+        /// The multi-source aggregate doesn't match the replied source values
+        InvalidAggregate,
+        /// The responding contract didn't register an attestation key
+        AttestationKeyNotFound,
+        /// The contract already responded to the request
+        ResponseAlreadyReceived,
+        /// The minimum number of sources is below the quorum
+        BelowQuorum,
+        /// The tolerated deviation is above the maximum
+        DeviationTooLarge,
+        /// The topic is frozen
+        TopicFrozen,
+        /// The average aggregator isn't implemented yet
+        AverageNotImplemented,
+    }
+
+    #[pallet::genesis_config]
+    #[derive(Default)]
+    pub struct GenesisConfig {
+        /// The anchor name of the off-chain worker fallback executor, `None` to disable it
+        pub fallback_name: Option<H256>,
+    }
+
+    #[pallet::genesis_build]
+    impl<T: Config> GenesisBuild<T> for GenesisConfig {
+        fn build(&self) {
+            if let Some(name) = self.fallback_name {
+                FallbackName::<T>::put(name);
+            }
+        }
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn offchain_worker(block_number: T::BlockNumber) {
+            if let Err(err) = Self::run_fallback_executor(block_number) {
+                log::error!(target: "runtime::oracle", "Fallback executor failed: {}", err);
+            }
+        }
     }
 
     #[pallet::call]
@@ -114,16 +279,19 @@ pub mod pallet {
         /// An oracle request for information.
         ///
         /// `registry_feed_key`: a feed identifier that can be found in the Registry.
+        /// `queue`: the anchor queue to send the request to, lower ids are processed first.
         /// `nonce`: an incrementing number provided by the client.
         ///
         /// The method fetches the URL and path associated with the feed requested
         /// and sends off a message to the phat contract(s) via rollup request-response.
-        #[pallet::weight(0)]
+        /// The caller pays `Config::RequestFee`.
+        #[pallet::weight(Pallet::<T>::request_weight())]
         #[pallet::call_index(0)]
         #[transactional]
         pub fn request(
             origin: OriginFor<T>,
             registry_feed_key: RegistryFeedKey<T>,
+            queue: QueueId,
             nonce: u128,
         ) -> DispatchResult {
             // TODO: weight should be set dynamically based on how many oracle phat contracts
@@ -136,21 +304,210 @@ pub mod pallet {
                     .ok_or(Error::<T>::FailedToGetApiFeed)?;
             let feed_status = api_feed.status;
             ensure!(feed_status.is_active(), Error::<T>::ApiFeedNotActive);
-            let feed_path = api_feed.path;
-            let feed_url = api_feed.url;
+            ensure!(!Self::frozen_topics(&registry_feed_key), Error::<T>::TopicFrozen);
+
+            let request_id = Self::next_request_id(nonce);
+            Self::do_request(
+                who.clone(),
+                registry_feed_key,
+                queue,
+                nonce,
+                request_id,
+                PhatRequest::Single {
+                    request_id,
+                    owner: who,
+                    url: api_feed.url,
+                    path: api_feed.path,
+                    secret_slot: api_feed.secret_slot,
+                    body: api_feed.body,
+                },
+            )
+        }
+
+        #[pallet::weight(<T as Config>::WeightInfo::feeds())]
+        #[pallet::call_index(1)]
+        #[transactional]
+        pub fn feeds(
+            origin: OriginFor<T>,
+            _name: H256,
+            _data: Bytes,
+            _nonce: u128,
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+            Ok(())
+        }
+
+        #[pallet::weight(<T as Config>::WeightInfo::average())]
+        #[pallet::call_index(2)]
+        pub fn average(origin: OriginFor<T>) -> DispatchResult {
+            ensure_signed(origin)?;
+
+            // TODO: implement an average aggergator
+            Err(Error::<T>::AverageNotImplemented.into())
+
+            // let storage_map = PriceFeeds::<T>::iter().collect::<Vec<_>>();
+            // let mut averages: Vec<(Bytes, u128)> = Vec::new();
+
+            // storage_map.iter().for_each(|(_i, _j, k)| {
+            //     let sum: u128 = k.iter().map(|j| j.price).sum::<u128>();
+            //     let count = k.len() as u128;
+            //     let average = sum / count;
+            //     averages.push((_j.clone(), average));
+            // });
+
+            // for (pair, average) in averages {
+            //     Averages::<T>::insert(&pair, average);
+            // }
+            // Ok(())
+        }
+
+        /// An oracle request aggregating several feeds in a single Phat contract invocation.
+        ///
+        /// `registry_feed_keys`: the feeds to query, owned by the caller.
+        /// `min_sources`: the minimum number of sources which must reply successfully, at least
+        /// `Config::Quorum`.
+        /// `max_deviation_bps`: sources deviating more than this from the median are dropped, at
+        /// most `Config::MaxDeviationBps`.
+        /// `queue`: the anchor queue to send the request to, lower ids are processed first.
+        /// `nonce`: an incrementing number provided by the client.
+        ///
+        /// The Phat contract replies with the aggregate along with the value of each source, so
+        /// the aggregate can be verified on-chain.
+        #[pallet::weight(<T as Config>::WeightInfo::request_multi_source(registry_feed_keys.len() as u32))]
+        #[pallet::call_index(3)]
+        #[transactional]
+        pub fn request_multi_source(
+            origin: OriginFor<T>,
+            registry_feed_keys: BoundedVec<RegistryFeedKey<T>, ConstU32<MAX_SOURCES>>,
+            min_sources: u32,
+            max_deviation_bps: u32,
+            queue: QueueId,
+            nonce: u128,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(
+                min_sources > 0 && min_sources as usize <= registry_feed_keys.len(),
+                Error::<T>::InvalidMinSources
+            );
+            ensure!(min_sources >= T::Quorum::get(), Error::<T>::BelowQuorum);
+            ensure!(
+                max_deviation_bps <= T::MaxDeviationBps::get(),
+                Error::<T>::DeviationTooLarge
+            );
+
+            let mut sources = Vec::new();
+            for key in registry_feed_keys.iter() {
+                let api_feed: ApiFeed<T> = pallet_registry::ApiFeeds::<T>::get(&who, key)
+                    .ok_or(Error::<T>::FailedToGetApiFeed)?;
+                ensure!(api_feed.status.is_active(), Error::<T>::ApiFeedNotActive);
+                ensure!(!Self::frozen_topics(key), Error::<T>::TopicFrozen);
+                sources.push(FeedSource {
+                    url: api_feed.url,
+                    path: api_feed.path,
+                    secret_slot: api_feed.secret_slot,
+                    body: api_feed.body,
+                });
+            }
+
+            let request_id = Self::next_request_id(nonce);
+            // the first feed identifies the request
+            let registry_feed_key = registry_feed_keys[0].clone();
+            RequestSources::<T>::insert(request_id, registry_feed_keys);
+            Self::do_request(
+                who.clone(),
+                registry_feed_key,
+                queue,
+                nonce,
+                request_id,
+                PhatRequest::MultiSource {
+                    request_id,
+                    owner: who,
+                    sources,
+                    min_sources,
+                    max_deviation_bps,
+                },
+            )
+        }
+
+        /// Relays a response signed by the oracle Phat contract `name`.
+        ///
+        /// Can be called by any account since the response is authenticated by the attestation
+        /// key registered by the contract in the anchor pallet.
+        #[pallet::weight(Pallet::<T>::response_weight())]
+        #[pallet::call_index(4)]
+        #[transactional]
+        pub fn submit_response(
+            origin: OriginFor<T>,
+            name: H256,
+            response: SignedResponse,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::process_signed_response(name, who, response)
+        }
 
-            // generate a random request ID
+        /// Freezes a topic (registry feed key) in an emergency: it can't be requested and its
+        /// latest value isn't updated by the responses.
+        ///
+        /// Can be called by `Config::ForceOrigin`.
+        #[pallet::weight(T::DbWeight::get().writes(1))]
+        #[pallet::call_index(5)]
+        pub fn force_freeze_topic(origin: OriginFor<T>, topic: RegistryFeedKey<T>) -> DispatchResult {
+            <T as Config>::ForceOrigin::ensure_origin(origin)?;
+            FrozenTopics::<T>::insert(&topic, true);
+            Self::deposit_event(Event::TopicFrozenSet { topic, frozen: true });
+            Ok(())
+        }
+
+        /// Unfreezes a topic frozen by `force_freeze_topic`.
+        ///
+        /// Can be called by `Config::ForceOrigin`.
+        #[pallet::weight(T::DbWeight::get().writes(1))]
+        #[pallet::call_index(6)]
+        pub fn force_unfreeze_topic(
+            origin: OriginFor<T>,
+            topic: RegistryFeedKey<T>,
+        ) -> DispatchResult {
+            <T as Config>::ForceOrigin::ensure_origin(origin)?;
+            FrozenTopics::<T>::remove(&topic);
+            Self::deposit_event(Event::TopicFrozenSet { topic, frozen: false });
+            Ok(())
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        /// Generates a request ID from the randomness source and the client nonce
+        pub fn next_request_id(nonce: u128) -> RequestId {
             let seed = (T::OracleRandomness::random_seed(), nonce).0.encode();
 
             // FIXME: randomness not appropriate
             let (request_id, _) = T::OracleRandomness::random(&seed);
-            let request_id = H256::from_slice(request_id.as_ref());
+            H256::from_slice(request_id.as_ref())
+        }
 
-            // encode the phat contract request
-            let data_raw = (request_id, feed_url, feed_path);
-            let data = BoundedVec::try_from(data_raw.encode())
+        /// Tracks the request and sends it off to the phat contract(s) via rollup
+        /// request-response
+        fn do_request(
+            who: T::AccountId,
+            registry_feed_key: RegistryFeedKey<T>,
+            queue: QueueId,
+            nonce: u128,
+            request_id: RequestId,
+            request: PhatRequest<T>,
+        ) -> DispatchResult {
+            let data = BoundedVec::try_from(request.encode())
                 .map_err(|_| Error::<T>::FailedToEncodeData)?;
 
+            let fee = T::RequestFee::get();
+            if !fee.is_zero() {
+                let imbalance = <T as pallet_anchor::Config>::Currency::withdraw(
+                    &who,
+                    fee,
+                    WithdrawReasons::FEE,
+                    ExistenceRequirement::KeepAlive,
+                )?;
+                T::OnRequestFee::on_unbalanced(imbalance);
+            }
+
             // update storage to keep track of this request
             FeedRequests::<T>::insert(
                 request_id,
@@ -171,11 +528,12 @@ pub mod pallet {
                 .ok_or(Error::<T>::FailedToFindOracleFeeds)?;
 
             // send request to rollup
-            phat_offchain_rollup::anchor::pallet::Pallet::<T>::push_message(&name, data)
+            phat_offchain_rollup::anchor::pallet::Pallet::<T>::push_message(&name, queue, data)
                 .map_err(|_| Error::<T>::FailedToPushMessageToAnchor)?;
+            RequestNames::<T>::insert(request_id, name);
 
             Self::deposit_event(Event::OracleRequest {
-                caller: who.clone(),
+                caller: who,
                 request_id,
                 registry_feed_key,
             });
@@ -183,56 +541,115 @@ pub mod pallet {
             Ok(())
         }
 
-        #[pallet::weight(0)]
-        #[pallet::call_index(1)]
-        #[transactional]
-        pub fn feeds(
-            origin: OriginFor<T>,
-            _name: H256,
-            _data: Bytes,
-            _nonce: u128,
-        ) -> DispatchResult {
-            ensure_signed(origin)?;
-            Ok(())
+        /// Returns the weight of a single-source request: the feed, topic, randomness and fee
+        /// reads, the fee withdrawal, the tracked request, and the message pushed to the anchor
+        pub fn request_weight() -> Weight {
+            Weight::from_ref_time(10_000).saturating_add(T::DbWeight::get().reads_writes(8, 5))
         }
 
-        #[pallet::weight(0)]
-        #[pallet::call_index(2)]
-        pub fn average(origin: OriginFor<T>) -> DispatchResult {
-            ensure_signed(origin)?;
-
-            todo!("implement an average aggergator");
-
-            // let storage_map = PriceFeeds::<T>::iter().collect::<Vec<_>>();
-            // let mut averages: Vec<(Bytes, u128)> = Vec::new();
-
-            // storage_map.iter().for_each(|(_i, _j, k)| {
-            //     let sum: u128 = k.iter().map(|j| j.price).sum::<u128>();
-            //     let count = k.len() as u128;
-            //     let average = sum / count;
-            //     averages.push((_j.clone(), average));
-            // });
+        /// Returns the weight of processing a signed response: the signature verification, the
+        /// replayed aggregation and the storage accesses of `process_signed_response`
+        pub fn response_weight() -> Weight {
+            // The reputation of each source is read and written
+            Weight::from_ref_time(SR25519_VERIFY_WEIGHT + AGGREGATE_WEIGHT)
+                .saturating_add(T::DbWeight::get().reads_writes(5, 3))
+                .saturating_add(T::DbWeight::get().reads_writes(
+                    1 + 2 * MAX_SOURCES as u64,
+                    1 + MAX_SOURCES as u64,
+                ))
+        }
 
-            // for (pair, average) in averages {
-            //     Averages::<T>::insert(&pair, average);
-            // }
-            // Ok(())
+        /// Verifies the response is signed by the attestation key of the contract `name`
+        fn verify_attestation(name: &H256, signed: &SignedResponse) -> DispatchResult {
+            let key = pallet_anchor::Pallet::<T>::attestation_key(name)
+                .ok_or(Error::<T>::AttestationKeyNotFound)?;
+            ensure!(
+                sp_io::crypto::sr25519_verify(&signed.signature, &signed.record, &key),
+                Error::<T>::FailedToAuthenticateResponse
+            );
+            Ok(())
         }
-    }
 
-    impl<T: Config> phat_offchain_rollup::anchor::OnResponse<T::AccountId> for Pallet<T> {
-        fn on_response(name: H256, submitter: T::AccountId, data: Vec<u8>) -> DispatchResult {
-            let resp: ResponseRecord<T> =
-                Decode::decode(&mut &data[..]).map_err(|_| Error::<T>::FailedToDecodeResponse)?;
+        /// Authenticates and stores a response from the oracle Phat contract `name`
+        ///
+        /// `submitter` is the account which delivered the response, which is not necessarily the
+        /// submitter account of the contract since signed responses can be relayed by anyone.
+        fn process_signed_response(
+            name: H256,
+            submitter: T::AccountId,
+            signed: SignedResponse,
+        ) -> DispatchResult {
+            Self::verify_attestation(&name, &signed)?;
+            let resp: ResponseRecord<T> = Decode::decode(&mut &signed.record[..])
+                .map_err(|_| Error::<T>::FailedToDecodeResponse)?;
 
             ensure!(
                 resp.phat_contract_id == name,
                 Error::<T>::FailedToAuthenticateResponse
             );
-
+            // the attestation only proves the contract signed the record, the request must be
+            // one actually pushed to the contract
             let requested_data = FeedRequests::<T>::get(resp.request_id)
                 .ok_or(Error::<T>::FailedToGetFeedRequest)?;
+            ensure!(
+                RequestNames::<T>::get(resp.request_id) == Some(name),
+                Error::<T>::RequestNotSentToName
+            );
+            // a signed response can only be delivered once
+            ensure!(
+                !Responses::<T>::contains_key(resp.request_id, name),
+                Error::<T>::ResponseAlreadyReceived
+            );
 
+            // the responses build the reputation of the requested feeds
+            match Decode::decode(&mut &requested_data.requested_data[..]) {
+                Ok(PhatRequest::<T>::MultiSource {
+                    sources,
+                    min_sources,
+                    max_deviation_bps,
+                    ..
+                }) => {
+                    let aggregate = Self::ensure_valid_aggregate(
+                        &resp.response_data,
+                        sources.len(),
+                        min_sources,
+                        max_deviation_bps,
+                    )?;
+                    let keys = RequestSources::<T>::take(resp.request_id).unwrap_or_default();
+                    for (key, value) in keys.iter().zip(aggregate.values.iter()) {
+                        pallet_registry::Pallet::<T>::note_feed_response(
+                            &requested_data.caller,
+                            key,
+                            value.is_some(),
+                        );
+                    }
+                }
+                Ok(PhatRequest::<T>::Single { .. }) => {
+                    pallet_registry::Pallet::<T>::note_feed_response(
+                        &requested_data.caller,
+                        &requested_data.registry_feed_key,
+                        true,
+                    );
+                }
+                Err(_) => {}
+            }
+
+            Responses::<T>::insert(resp.request_id, name, resp.response_data.clone());
+            if !Self::frozen_topics(&requested_data.registry_feed_key) {
+                LatestValues::<T>::insert(
+                    &requested_data.registry_feed_key,
+                    LatestValue {
+                        request_id: resp.request_id,
+                        phat_contract_id: name,
+                        value: Self::response_value(
+                            &requested_data.requested_data,
+                            &resp.response_data,
+                        ),
+                        timestamp_ms: resp.timestamp_ms,
+                        block_number: frame_system::Pallet::<T>::block_number(),
+                    },
+                );
+            }
             FeedData::<T>::insert(
                 submitter.clone(),
                 requested_data,
@@ -249,5 +666,132 @@ pub mod pallet {
 
             Ok(())
         }
+
+        /// Replays the aggregation of a multi-source response to verify it, and returns the
+        /// decoded response
+        fn ensure_valid_aggregate(
+            response_data: &[u8],
+            num_sources: usize,
+            min_sources: u32,
+            max_deviation_bps: u32,
+        ) -> Result<MultiSourceResponse, DispatchError> {
+            let resp = MultiSourceResponse::decode(&mut &response_data[..])
+                .map_err(|_| Error::<T>::FailedToDecodeResponse)?;
+            ensure!(
+                resp.values.len() == num_sources,
+                Error::<T>::InvalidAggregate
+            );
+            let values: Vec<u128> = resp.values.iter().flatten().copied().collect();
+            ensure!(
+                values.len() as u32 == resp.succeeded && resp.succeeded >= min_sources,
+                Error::<T>::InvalidAggregate
+            );
+            ensure!(
+                aggregation::aggregate(&values, max_deviation_bps) == Some(resp.value),
+                Error::<T>::InvalidAggregate
+            );
+            Ok(resp)
+        }
+    }
+
+    /// Queries backing the runtime API
+    impl<T: Config> Pallet<T> {
+        /// Returns the value of a response, decoded according to the kind of the request
+        pub fn response_value(requested_data: &[u8], response_data: &[u8]) -> Option<u128> {
+            match PhatRequest::<T>::decode(&mut &requested_data[..]).ok()? {
+                PhatRequest::Single { .. } => u128::decode(&mut &response_data[..]).ok(),
+                PhatRequest::MultiSource { .. } => {
+                    MultiSourceResponse::decode(&mut &response_data[..])
+                        .ok()
+                        .map(|resp| resp.value)
+                }
+            }
+        }
+
+        /// Returns the registry feed `key` of `owner`
+        pub fn get_feed(
+            owner: T::AccountId,
+            key: Vec<u8>,
+        ) -> Option<FeedInfo<T::AccountId, T::BlockNumber>> {
+            let key = RegistryFeedKey::<T>::try_from(key).ok()?;
+            let feed = pallet_registry::ApiFeeds::<T>::get(&owner, &key)?;
+            Some(Self::feed_info(owner, key, feed))
+        }
+
+        /// Returns the registry feeds, optionally filtered by owner and by topic (feed key)
+        pub fn list_feeds(
+            owner: Option<T::AccountId>,
+            topic: Option<Vec<u8>>,
+        ) -> Vec<FeedInfo<T::AccountId, T::BlockNumber>> {
+            let feeds: Vec<_> = match owner {
+                Some(owner) => pallet_registry::ApiFeeds::<T>::iter_prefix(&owner)
+                    .map(|(key, feed)| (owner.clone(), key, feed))
+                    .collect(),
+                None => pallet_registry::ApiFeeds::<T>::iter().collect(),
+            };
+            feeds
+                .into_iter()
+                .filter(|(_, key, _)| topic.as_ref().map_or(true, |topic| key[..] == topic[..]))
+                .map(|(owner, key, feed)| Self::feed_info(owner, key, feed))
+                .collect()
+        }
+
+        /// Returns the request `request_id`
+        pub fn get_request(request_id: RequestId) -> Option<RequestInfo<T::AccountId>> {
+            FeedRequests::<T>::get(request_id).map(|request| RequestInfo {
+                request_id,
+                caller: request.caller,
+                registry_feed_key: request.registry_feed_key.into(),
+                nonce: request.nonce,
+            })
+        }
+
+        /// Returns the responses of the Phat contracts to the request `request_id`
+        pub fn get_result(request_id: RequestId) -> Vec<ResultInfo> {
+            let requested_data = FeedRequests::<T>::get(request_id)
+                .map(|request| request.requested_data.into_inner())
+                .unwrap_or_default();
+            Responses::<T>::iter_prefix(request_id)
+                .map(|(phat_contract_id, data)| ResultInfo {
+                    phat_contract_id,
+                    value: Self::response_value(&requested_data, &data),
+                    data: data.into(),
+                })
+                .collect()
+        }
+
+        /// Returns the latest response received for the topic (feed key)
+        pub fn latest_value(topic: Vec<u8>) -> Option<LatestValue<T::BlockNumber>> {
+            LatestValues::<T>::get(RegistryFeedKey::<T>::try_from(topic).ok()?)
+        }
+
+        fn feed_info(
+            owner: T::AccountId,
+            key: RegistryFeedKey<T>,
+            feed: ApiFeed<T>,
+        ) -> FeedInfo<T::AccountId, T::BlockNumber> {
+            FeedInfo {
+                owner,
+                key: key.into(),
+                url: feed.url.into(),
+                path: feed.path.into(),
+                secret_slot: feed.secret_slot,
+                body: feed.body.map(Into::into),
+                status: feed.status,
+                started_at: feed.started_at,
+            }
+        }
+    }
+
+    impl<T: Config> phat_offchain_rollup::anchor::OnResponse<T::AccountId> for Pallet<T> {
+        fn on_response(name: H256, submitter: T::AccountId, data: Vec<u8>) -> DispatchResult {
+            let signed: SignedResponse =
+                Decode::decode(&mut &data[..]).map_err(|_| Error::<T>::FailedToDecodeResponse)?;
+            Self::process_signed_response(name, submitter, signed)
+        }
+
+        fn weight() -> Weight {
+            Self::response_weight()
+        }
     }
 }
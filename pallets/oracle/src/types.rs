@@ -1,17 +1,24 @@
 use crate::Config;
 use frame_support::pallet_prelude::{ConstU32, Decode, Encode, MaxEncodedLen, TypeInfo};
-use frame_support::BoundedVec;
+use frame_support::{BoundedVec, RuntimeDebug};
 
 use phat_offchain_rollup::types::ValueBytes;
-use sp_core::H256;
+use sp_core::{sr25519, H256};
+use sp_std::vec::Vec;
 
-use pallet_registry::types::RegistryFeedKey;
+use pallet_registry::types::{
+    ApiFeedStatus, RegistryFeedBody, RegistryFeedKey, RegistryFeedPath, RegistryFeedUrl,
+    SecretSlotId,
+};
 
 pub type RequestId = H256;
 pub type Bytes = BoundedVec<u8, ConstU32<64>>;
 pub type ResponseData = ValueBytes;
 pub type RequestData = ValueBytes;
 
+/// Maximum number of sources fetched by a single multi-source request
+pub const MAX_SOURCES: u32 = 5;
+
 /// A Request for feed data
 #[derive(Encode, Decode, Clone, PartialEq, Eq, TypeInfo, MaxEncodedLen)]
 #[scale_info(skip_type_params(T))]
@@ -30,6 +37,14 @@ pub struct PriceQuote {
     pub timestamp_ms: u64,
 }
 
+/// An encoded `ResponseRecord` signed by the attestation key of the oracle Phat Contract
+/// (copied from Phat Contract)
+#[derive(Debug, PartialEq, Eq, Encode, Decode, Clone, scale_info::TypeInfo)]
+pub struct SignedResponse {
+    pub record: Vec<u8>,
+    pub signature: sr25519::Signature,
+}
+
 /// The reponse from the oracle Phat Contract (copied from Phat Contract)
 #[derive(Debug, PartialEq, Eq, Encode, Decode, Clone, scale_info::TypeInfo)]
 pub struct ResponseRecord<T: Config> {
@@ -39,3 +54,95 @@ pub struct ResponseRecord<T: Config> {
     pub response_data: ResponseData,
     pub timestamp_ms: u64,
 }
+
+/// The request pushed to the queue of the oracle Phat Contract (copied to Phat Contract)
+#[derive(Encode, Decode, Clone, PartialEq, Eq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub enum PhatRequest<T: Config> {
+    /// Fetches a single feed
+    Single {
+        request_id: RequestId,
+        /// The owner of the feed, allowed or not to use the secret slot
+        owner: T::AccountId,
+        url: RegistryFeedUrl<T>,
+        path: RegistryFeedPath<T>,
+        secret_slot: Option<SecretSlotId>,
+        body: Option<RegistryFeedBody<T>>,
+    },
+    /// Fetches several feeds concurrently and replies with their aggregate
+    MultiSource {
+        request_id: RequestId,
+        /// The owner of the feeds, allowed or not to use their secret slots
+        owner: T::AccountId,
+        sources: Vec<FeedSource<T>>,
+        /// Minimum number of sources which must succeed
+        min_sources: u32,
+        /// Sources deviating more than this from the median are dropped as outliers
+        max_deviation_bps: u32,
+    },
+}
+
+/// A feed fetched by a multi-source request (copied to Phat Contract)
+#[derive(Encode, Decode, Clone, PartialEq, Eq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct FeedSource<T: Config> {
+    pub url: RegistryFeedUrl<T>,
+    pub path: RegistryFeedPath<T>,
+    pub secret_slot: Option<SecretSlotId>,
+    pub body: Option<RegistryFeedBody<T>>,
+}
+
+/// The aggregate of a multi-source request (copied from Phat Contract)
+#[derive(Debug, PartialEq, Eq, Encode, Decode, Clone, scale_info::TypeInfo)]
+pub struct MultiSourceResponse {
+    /// The median of the sources after dropping the outliers
+    pub value: u128,
+    /// The value of each source, `None` if it failed
+    pub values: Vec<Option<u128>>,
+    /// The number of sources which succeeded
+    pub succeeded: u32,
+}
+
+/// The latest response received for a feed
+#[derive(Debug, PartialEq, Eq, Encode, Decode, Clone, TypeInfo, MaxEncodedLen)]
+pub struct LatestValue<BlockNumber> {
+    pub request_id: RequestId,
+    pub phat_contract_id: H256,
+    /// The value of the response, if it's a (possibly aggregated) number
+    pub value: Option<u128>,
+    pub timestamp_ms: u64,
+    /// The block the response was received at
+    pub block_number: BlockNumber,
+}
+
+/// A registry feed, as returned by the runtime API
+#[derive(RuntimeDebug, PartialEq, Eq, Encode, Decode, Clone, TypeInfo)]
+pub struct FeedInfo<AccountId, BlockNumber> {
+    pub owner: AccountId,
+    pub key: Vec<u8>,
+    pub url: Vec<u8>,
+    pub path: Vec<u8>,
+    pub secret_slot: Option<SecretSlotId>,
+    pub body: Option<Vec<u8>>,
+    pub status: ApiFeedStatus,
+    pub started_at: BlockNumber,
+}
+
+/// An oracle request, as returned by the runtime API
+#[derive(Debug, PartialEq, Eq, Encode, Decode, Clone, TypeInfo)]
+pub struct RequestInfo<AccountId> {
+    pub request_id: RequestId,
+    pub caller: AccountId,
+    pub registry_feed_key: Vec<u8>,
+    pub nonce: u128,
+}
+
+/// The response of a Phat contract to a request, as returned by the runtime API
+#[derive(Debug, PartialEq, Eq, Encode, Decode, Clone, TypeInfo)]
+pub struct ResultInfo {
+    pub phat_contract_id: H256,
+    /// The raw response data
+    pub data: Vec<u8>,
+    /// The value of the response, if it's a (possibly aggregated) number
+    pub value: Option<u128>,
+}
@@ -0,0 +1,55 @@
+//! Weights for pallet_oracle
+//!
+//! Generated with `benchmark pallet --pallet pallet_oracle --extrinsic '*'` from the benchmarks
+//! in `benchmarking.rs`. Until it is run on the reference hardware, the values are estimates.
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{
+    traits::Get,
+    weights::{constants::RocksDbWeight, Weight},
+};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_oracle.
+pub trait WeightInfo {
+    fn request_multi_source(s: u32) -> Weight;
+    fn feeds() -> Weight;
+    fn average() -> Weight;
+}
+
+/// Weights for pallet_oracle using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+    fn request_multi_source(s: u32, ) -> Weight {
+        Weight::from_ref_time(48_000_000)
+            .saturating_add(Weight::from_ref_time(14_500_000).saturating_mul(s as u64))
+            .saturating_add(T::DbWeight::get().reads(7 as u64))
+            .saturating_add(T::DbWeight::get().reads((2 as u64).saturating_mul(s as u64)))
+            .saturating_add(T::DbWeight::get().writes(6 as u64))
+    }
+    fn feeds() -> Weight {
+        Weight::from_ref_time(9_000_000)
+    }
+    fn average() -> Weight {
+        Weight::from_ref_time(9_000_000)
+    }
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+    fn request_multi_source(s: u32, ) -> Weight {
+        Weight::from_ref_time(48_000_000)
+            .saturating_add(Weight::from_ref_time(14_500_000).saturating_mul(s as u64))
+            .saturating_add(RocksDbWeight::get().reads(7 as u64))
+            .saturating_add(RocksDbWeight::get().reads((2 as u64).saturating_mul(s as u64)))
+            .saturating_add(RocksDbWeight::get().writes(6 as u64))
+    }
+    fn feeds() -> Weight {
+        Weight::from_ref_time(9_000_000)
+    }
+    fn average() -> Weight {
+        Weight::from_ref_time(9_000_000)
+    }
+}
@@ -1,6 +1,211 @@
 #![cfg_attr(not(feature = "std"), no_std)]
+//! Governable parameters of the oracle pallets
+//!
+//! The parameters are stored on-chain and set by `Config::GovernanceOrigin`, e.g. root through a
+//! `pallet_democracy` referendum. The registry, oracle and anchor pallets read them through the
+//! `Governed*` adapters, wired as `Get` types in their runtime config.
 
-pub use pallet_democracy;
+pub use pallet::*;
 
-/// Configure the pallet by specifying the parameters and types on which it depends.
-pub trait Config: frame_system::Config + pallet_democracy::Config {}
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+pub mod weights;
+pub use weights::WeightInfo;
+
+#[frame_support::pallet]
+pub mod pallet {
+    use frame_support::{pallet_prelude::*, traits::Currency};
+    use frame_system::pallet_prelude::*;
+    use sp_std::{marker::PhantomData, vec::Vec};
+
+    use crate::WeightInfo;
+
+    pub type BalanceOf<T> =
+        <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+    /// The capacities of the anchor queues, indexed by queue id
+    pub type QueueCapacitiesOf<T> = BoundedVec<u32, <T as Config>::MaxQueues>;
+
+    /// A governable parameter with its new value
+    #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+    pub enum Parameter<Balance> {
+        /// Amount reserved from the owner of each registry feed
+        FeedBond(Balance),
+        /// Minimum number of sources of the multi-source oracle requests
+        Quorum(u32),
+        /// Maximum deviation from the median the multi-source oracle requests may tolerate, in
+        /// basis points
+        MaxDeviationBps(u32),
+        /// Capacity of an existing anchor queue
+        QueueCapacity { queue: u8, capacity: u32 },
+        /// Fee charged for each oracle request
+        RequestFee(Balance),
+    }
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+        /// The currency of the bonds and fees
+        type Currency: Currency<Self::AccountId>;
+        /// The origin allowed to change the parameters
+        type GovernanceOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+        /// Maximum number of anchor queues
+        #[pallet::constant]
+        type MaxQueues: Get<u32>;
+
+        /// The values of the parameters until governance changes them
+        type DefaultFeedBond: Get<BalanceOf<Self>>;
+        type DefaultQuorum: Get<u32>;
+        type DefaultMaxDeviationBps: Get<u32>;
+        type DefaultQueueCapacities: Get<Vec<u32>>;
+        type DefaultRequestFee: Get<BalanceOf<Self>>;
+
+        type WeightInfo: WeightInfo;
+    }
+
+    #[pallet::pallet]
+    #[pallet::generate_store(pub(super) trait Store)]
+    pub struct Pallet<T>(_);
+
+    /// Adapts `Config::DefaultQueueCapacities` to the storage bound
+    pub struct DefaultQueueCapacitiesOf<T>(PhantomData<T>);
+    impl<T: Config> Get<QueueCapacitiesOf<T>> for DefaultQueueCapacitiesOf<T> {
+        fn get() -> QueueCapacitiesOf<T> {
+            BoundedVec::try_from(T::DefaultQueueCapacities::get())
+                .expect("the default queues are checked against MaxQueues in integrity_test; qed")
+        }
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn integrity_test() {
+            assert!(
+                T::DefaultQueueCapacities::get().len() <= T::MaxQueues::get() as usize,
+                "DefaultQueueCapacities has more queues than MaxQueues"
+            );
+        }
+    }
+
+    #[pallet::storage]
+    #[pallet::getter(fn feed_bond)]
+    pub type FeedBond<T: Config> = StorageValue<_, BalanceOf<T>, ValueQuery, T::DefaultFeedBond>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn quorum)]
+    pub type Quorum<T: Config> = StorageValue<_, u32, ValueQuery, T::DefaultQuorum>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn max_deviation_bps)]
+    pub type MaxDeviationBps<T: Config> =
+        StorageValue<_, u32, ValueQuery, T::DefaultMaxDeviationBps>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn queue_capacities)]
+    pub type QueueCapacities<T: Config> =
+        StorageValue<_, QueueCapacitiesOf<T>, ValueQuery, DefaultQueueCapacitiesOf<T>>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn request_fee)]
+    pub type RequestFee<T: Config> =
+        StorageValue<_, BalanceOf<T>, ValueQuery, T::DefaultRequestFee>;
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        /// A parameter is changed
+        ParameterSet { parameter: Parameter<BalanceOf<T>> },
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        /// The quorum must be positive
+        InvalidQuorum,
+        /// The deviation must be within 10000 basis points
+        InvalidDeviation,
+        /// The queue doesn't exist
+        InvalidQueue,
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Changes a parameter.
+        ///
+        /// Can be called by `Config::GovernanceOrigin`.
+        #[pallet::call_index(0)]
+        #[pallet::weight(T::WeightInfo::set_parameter())]
+        pub fn set_parameter(
+            origin: OriginFor<T>,
+            parameter: Parameter<BalanceOf<T>>,
+        ) -> DispatchResult {
+            T::GovernanceOrigin::ensure_origin(origin)?;
+
+            match parameter.clone() {
+                Parameter::FeedBond(bond) => FeedBond::<T>::put(bond),
+                Parameter::Quorum(quorum) => {
+                    ensure!(quorum > 0, Error::<T>::InvalidQuorum);
+                    Quorum::<T>::put(quorum);
+                }
+                Parameter::MaxDeviationBps(bps) => {
+                    ensure!(bps <= 10_000, Error::<T>::InvalidDeviation);
+                    MaxDeviationBps::<T>::put(bps);
+                }
+                Parameter::QueueCapacity { queue, capacity } => {
+                    QueueCapacities::<T>::try_mutate(|capacities| {
+                        let slot = capacities
+                            .get_mut(queue as usize)
+                            .ok_or(Error::<T>::InvalidQueue)?;
+                        *slot = capacity;
+                        Ok::<_, Error<T>>(())
+                    })?;
+                }
+                Parameter::RequestFee(fee) => RequestFee::<T>::put(fee),
+            }
+
+            Self::deposit_event(Event::ParameterSet { parameter });
+            Ok(())
+        }
+    }
+
+    /// `Get` adapter of the governed feed bond
+    pub struct GovernedFeedBond<T>(PhantomData<T>);
+    impl<T: Config> Get<BalanceOf<T>> for GovernedFeedBond<T> {
+        fn get() -> BalanceOf<T> {
+            FeedBond::<T>::get()
+        }
+    }
+
+    /// `Get` adapter of the governed quorum
+    pub struct GovernedQuorum<T>(PhantomData<T>);
+    impl<T: Config> Get<u32> for GovernedQuorum<T> {
+        fn get() -> u32 {
+            Quorum::<T>::get()
+        }
+    }
+
+    /// `Get` adapter of the governed maximum deviation
+    pub struct GovernedMaxDeviationBps<T>(PhantomData<T>);
+    impl<T: Config> Get<u32> for GovernedMaxDeviationBps<T> {
+        fn get() -> u32 {
+            MaxDeviationBps::<T>::get()
+        }
+    }
+
+    /// `Get` adapter of the governed queue capacities
+    pub struct GovernedQueueCapacities<T>(PhantomData<T>);
+    impl<T: Config> Get<Vec<u32>> for GovernedQueueCapacities<T> {
+        fn get() -> Vec<u32> {
+            QueueCapacities::<T>::get().into_inner()
+        }
+    }
+
+    /// `Get` adapter of the governed request fee
+    pub struct GovernedRequestFee<T>(PhantomData<T>);
+    impl<T: Config> Get<BalanceOf<T>> for GovernedRequestFee<T> {
+        fn get() -> BalanceOf<T> {
+            RequestFee::<T>::get()
+        }
+    }
+}
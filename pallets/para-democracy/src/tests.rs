@@ -0,0 +1,75 @@
+use crate::{mock::*, *};
+use frame_support::{assert_noop, assert_ok, traits::{Get, Hooks}};
+use sp_runtime::DispatchError;
+
+fn set(parameter: Parameter<Balance>) {
+    assert_ok!(ParaDemocracy::set_parameter(RuntimeOrigin::root(), parameter.clone()));
+    System::assert_last_event(Event::<Test>::ParameterSet { parameter }.into());
+}
+
+#[test]
+fn default_queue_capacities_fit_the_max_queues() {
+    new_test_ext().execute_with(|| {
+        <ParaDemocracy as Hooks<u64>>::integrity_test();
+    });
+}
+
+#[test]
+fn adapters_return_the_defaults_until_governance_changes_them() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(GovernedFeedBond::<Test>::get(), 10);
+        assert_eq!(GovernedQuorum::<Test>::get(), 1);
+        assert_eq!(GovernedMaxDeviationBps::<Test>::get(), 10_000);
+        assert_eq!(GovernedQueueCapacities::<Test>::get(), vec![3, 4]);
+        assert_eq!(GovernedRequestFee::<Test>::get(), 0);
+    });
+}
+
+#[test]
+fn set_parameter_requires_the_governance_origin() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            ParaDemocracy::set_parameter(RuntimeOrigin::signed(1), Parameter::Quorum(2)),
+            DispatchError::BadOrigin
+        );
+    });
+}
+
+#[test]
+fn adapters_return_the_governed_parameters() {
+    new_test_ext().execute_with(|| {
+        set(Parameter::FeedBond(50));
+        set(Parameter::Quorum(3));
+        set(Parameter::MaxDeviationBps(500));
+        set(Parameter::QueueCapacity { queue: 1, capacity: 16 });
+        set(Parameter::RequestFee(2));
+
+        assert_eq!(GovernedFeedBond::<Test>::get(), 50);
+        assert_eq!(GovernedQuorum::<Test>::get(), 3);
+        assert_eq!(GovernedMaxDeviationBps::<Test>::get(), 500);
+        assert_eq!(GovernedQueueCapacities::<Test>::get(), vec![3, 16]);
+        assert_eq!(GovernedRequestFee::<Test>::get(), 2);
+    });
+}
+
+#[test]
+fn invalid_parameters_are_rejected() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            ParaDemocracy::set_parameter(RuntimeOrigin::root(), Parameter::Quorum(0)),
+            Error::<Test>::InvalidQuorum
+        );
+        assert_noop!(
+            ParaDemocracy::set_parameter(RuntimeOrigin::root(), Parameter::MaxDeviationBps(10_001)),
+            Error::<Test>::InvalidDeviation
+        );
+        // Only the existing queues have a capacity
+        assert_noop!(
+            ParaDemocracy::set_parameter(
+                RuntimeOrigin::root(),
+                Parameter::QueueCapacity { queue: 2, capacity: 8 }
+            ),
+            Error::<Test>::InvalidQueue
+        );
+    });
+}
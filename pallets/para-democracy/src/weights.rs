@@ -0,0 +1,37 @@
+//! Weights for para_democracy
+//!
+//! These are hand-estimated placeholders, not the output of the benchmark CLI. `set_parameter`
+//! is weighted by its most expensive parameter, the queue capacity read and written back.
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{
+    traits::Get,
+    weights::{constants::RocksDbWeight, Weight},
+};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for para_democracy.
+pub trait WeightInfo {
+    fn set_parameter() -> Weight;
+}
+
+/// Weights for para_democracy using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+    fn set_parameter() -> Weight {
+        Weight::from_ref_time(12_000_000)
+            .saturating_add(T::DbWeight::get().reads(1 as u64))
+            .saturating_add(T::DbWeight::get().writes(1 as u64))
+    }
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+    fn set_parameter() -> Weight {
+        Weight::from_ref_time(12_000_000)
+            .saturating_add(RocksDbWeight::get().reads(1 as u64))
+            .saturating_add(RocksDbWeight::get().writes(1 as u64))
+    }
+}
@@ -0,0 +1,321 @@
+use crate::{Config, CreatorId, OracleKeyOf, Pallet, TimestampedValue, TimestampedValueT};
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{
+	traits::{Get, UnixTime},
+	RuntimeDebug,
+};
+use orml_traits::CombineData;
+use scale_info::TypeInfo;
+use sp_runtime::{Percent, Permill};
+use sp_std::{marker, prelude::*};
+use hex::ToHex;
+
+/// The way the raw values of a key are combined, selected per key by
+/// `set_combine_strategy`.
+///
+/// The keys without strategy are combined by `T::CombineData`.
+#[derive(Encode, Decode, RuntimeDebug, Eq, PartialEq, Clone, Copy, TypeInfo, MaxEncodedLen)]
+pub enum CombineStrategy {
+	/// Median of the values fed in the last `expires_in` milliseconds.
+	Median { minimum_count: u32, expires_in: u128 },
+	/// Mean of the values fed in the last `expires_in` milliseconds, without the `trim` lowest
+	/// and the `trim` highest values.
+	TrimmedMean { minimum_count: u32, expires_in: u128, trim: Percent },
+	/// Average of the values fed in the last `window` milliseconds, each value weighted by the
+	/// time it has been the latest value.
+	Twap { minimum_count: u32, window: u128 },
+	/// Median of the values fed in the last `expires_in` milliseconds, each value weighted by
+	/// the stake of its feeder.
+	StakeWeightedMedian { minimum_count: u32, expires_in: u128 },
+	/// Median of the values fed in the last `expires_in` milliseconds, each value weighted by
+	/// the reputation of its feeder.
+	ReputationWeightedMedian { minimum_count: u32, expires_in: u128 },
+	/// Latest value, if fed in the last `max_age` milliseconds.
+	LastValue { max_age: u128 },
+}
+
+impl CombineStrategy {
+	/// Combines the raw values of the feeders, `stake` and `reputation` returning the stake
+	/// and the reputation of a feeder.
+	/// Returns prev_value if not enough valid values.
+	pub fn combine<AccountId>(
+		&self,
+		values: Vec<(CreatorId<AccountId>, TimestampedValueT)>,
+		prev_value: Option<TimestampedValueT>,
+		now: u128,
+		stake: impl Fn(&CreatorId<AccountId>) -> u128,
+		reputation: impl Fn(&CreatorId<AccountId>) -> Permill,
+	) -> Option<TimestampedValueT> {
+		let (minimum_count, max_age) = match *self {
+			Self::Median { minimum_count, expires_in } => (minimum_count, expires_in),
+			Self::TrimmedMean { minimum_count, expires_in, .. } => (minimum_count, expires_in),
+			Self::Twap { minimum_count, window } => (minimum_count, window),
+			Self::StakeWeightedMedian { minimum_count, expires_in } => (minimum_count, expires_in),
+			Self::ReputationWeightedMedian { minimum_count, expires_in } => {
+				(minimum_count, expires_in)
+			}
+			Self::LastValue { max_age } => (1, max_age),
+		};
+		let values: Vec<_> = match *self {
+			// The latest value before the window still holds at its start
+			Self::Twap { window, .. } => with_value_at_start(values, now.saturating_sub(window)),
+			_ => values
+				.into_iter()
+				.filter(|(_, x)| x.timestamp.saturating_add(max_age) > now)
+				.collect(),
+		};
+		if values.is_empty() || (values.len() as u32) < minimum_count {
+			return prev_value;
+		}
+
+		let combined = match *self {
+			Self::Median { .. } => median(values.into_iter().map(|(_, x)| x).collect()),
+			Self::TrimmedMean { trim, .. } => {
+				trimmed_mean(values.into_iter().map(|(_, x)| x).collect(), trim)
+			}
+			Self::Twap { window, .. } => {
+				twap(values.into_iter().map(|(_, x)| x).collect(), window, now)
+			}
+			Self::StakeWeightedMedian { .. } => weighted_median(
+				values.into_iter().map(|(feeder, x)| (x, stake(&feeder))).collect(),
+			),
+			Self::ReputationWeightedMedian { .. } => weighted_median(
+				values
+					.into_iter()
+					.map(|(feeder, x)| (x, reputation(&feeder).deconstruct() as u128))
+					.collect(),
+			),
+			Self::LastValue { .. } => values.into_iter().map(|(_, x)| x).max_by_key(|x| x.timestamp),
+		};
+		combined.or(prev_value)
+	}
+}
+
+/// Returns the values fed after `start`, and the latest value fed before, which holds at `start`.
+fn with_value_at_start<AccountId>(
+	values: Vec<(CreatorId<AccountId>, TimestampedValueT)>,
+	start: u128,
+) -> Vec<(CreatorId<AccountId>, TimestampedValueT)> {
+	let (mut kept, before): (Vec<_>, Vec<_>) =
+		values.into_iter().partition(|(_, x)| x.timestamp > start);
+	if let Some(at_start) = before.into_iter().max_by_key(|(_, x)| x.timestamp) {
+		kept.push(at_start);
+	}
+	kept
+}
+
+/// Returns the median value, with its timestamp.
+pub fn median(mut values: Vec<TimestampedValueT>) -> Option<TimestampedValueT> {
+	if values.is_empty() {
+		return None;
+	}
+	let mid_index = values.len() / 2;
+	let (_, value, _) = values.select_nth_unstable_by(mid_index, |a, b| a.value.cmp(&b.value));
+	Some(*value)
+}
+
+/// Returns the mean of the values without the `trim` lowest and the `trim` highest ones,
+/// timestamped with the latest kept value.
+pub fn trimmed_mean(mut values: Vec<TimestampedValueT>, trim: Percent) -> Option<TimestampedValueT> {
+	values.sort_by_key(|x| x.value);
+	let cut = trim.mul_floor(values.len() as u32) as usize;
+	let kept = values.get(cut..values.len().saturating_sub(cut))?;
+	if kept.is_empty() {
+		return None;
+	}
+	let sum: i128 = kept.iter().map(|x| x.value as i128).sum();
+	Some(TimestampedValue {
+		value: (sum / kept.len() as i128) as i64,
+		timestamp: kept.iter().map(|x| x.timestamp).max()?,
+	})
+}
+
+/// Returns the time-weighted average of the values over the last `window` milliseconds, each
+/// value holding until the next one, a value fed before the window counting from its start.
+/// The values fed at `now` only, are averaged.
+pub fn twap(mut values: Vec<TimestampedValueT>, window: u128, now: u128) -> Option<TimestampedValueT> {
+	let latest = values.iter().map(|x| x.timestamp).max()?;
+	let start = now.saturating_sub(window);
+	values.sort_by_key(|x| x.timestamp);
+
+	let mut weighted_sum: i128 = 0;
+	let mut total_time: u128 = 0;
+	for (i, x) in values.iter().enumerate() {
+		let end = values.get(i + 1).map_or(now, |next| next.timestamp);
+		let elapsed = end.saturating_sub(x.timestamp.max(start));
+		weighted_sum = weighted_sum.saturating_add((x.value as i128).saturating_mul(elapsed as i128));
+		total_time = total_time.saturating_add(elapsed);
+	}
+	let value = if total_time == 0 {
+		values.iter().map(|x| x.value as i128).sum::<i128>() / values.len() as i128
+	} else {
+		weighted_sum / total_time as i128
+	};
+	Some(TimestampedValue { value: value as i64, timestamp: latest })
+}
+
+/// Returns the value where the cumulated weight of the sorted values reaches half of the total
+/// weight. Returns None if the total weight is zero.
+pub fn weighted_median(mut values: Vec<(TimestampedValueT, u128)>) -> Option<TimestampedValueT> {
+	let total: u128 = values.iter().fold(0u128, |acc, (_, w)| acc.saturating_add(*w));
+	if total == 0 {
+		return None;
+	}
+	values.sort_by_key(|(x, _)| x.value);
+	let half = total / 2 + total % 2;
+	let mut cumulated = 0u128;
+	values.into_iter().find_map(|(x, w)| {
+		cumulated = cumulated.saturating_add(w);
+		(cumulated >= half).then_some(x)
+	})
+}
+
+/// Time-weighted average of the values fed in the last `Window` milliseconds.
+/// Returns prev_value if not enough valid values.
+pub struct TwapCombineData<T, MinimumCount, Window>(marker::PhantomData<(T, MinimumCount, Window)>);
+
+impl<T, MinimumCount, Window> CombineData<OracleKeyOf<T>, TimestampedValueT>
+	for TwapCombineData<T, MinimumCount, Window>
+where
+	T: Config,
+	T::AccountId: AsRef<[u8]> + ToHex,
+	MinimumCount: Get<u32>,
+	Window: Get<u128>,
+{
+	fn combine_data(
+		_key: &OracleKeyOf<T>,
+		values: Vec<TimestampedValueT>,
+		prev_value: Option<TimestampedValueT>,
+	) -> Option<TimestampedValueT> {
+		let strategy = CombineStrategy::Twap { minimum_count: MinimumCount::get(), window: Window::get() };
+		combine_anonymous(strategy, values, prev_value, T::UnixTime::now().as_millis())
+	}
+}
+
+/// Mean of the values without the `Trim` lowest and highest ones.
+/// Returns prev_value if not enough valid values.
+pub struct TrimmedMeanCombineData<T, MinimumCount, ExpiresIn, Trim>(
+	marker::PhantomData<(T, MinimumCount, ExpiresIn, Trim)>,
+);
+
+impl<T, MinimumCount, ExpiresIn, Trim> CombineData<OracleKeyOf<T>, TimestampedValueT>
+	for TrimmedMeanCombineData<T, MinimumCount, ExpiresIn, Trim>
+where
+	T: Config,
+	T::AccountId: AsRef<[u8]> + ToHex,
+	MinimumCount: Get<u32>,
+	ExpiresIn: Get<u128>,
+	Trim: Get<Percent>,
+{
+	fn combine_data(
+		_key: &OracleKeyOf<T>,
+		values: Vec<TimestampedValueT>,
+		prev_value: Option<TimestampedValueT>,
+	) -> Option<TimestampedValueT> {
+		let strategy = CombineStrategy::TrimmedMean {
+			minimum_count: MinimumCount::get(),
+			expires_in: ExpiresIn::get(),
+			trim: Trim::get(),
+		};
+		combine_anonymous(strategy, values, prev_value, T::UnixTime::now().as_millis())
+	}
+}
+
+/// Median of the values weighted by the stake of their feeder.
+/// Returns prev_value if not enough valid values.
+pub struct StakeWeightedMedianCombineData<T, MinimumCount, ExpiresIn>(
+	marker::PhantomData<(T, MinimumCount, ExpiresIn)>,
+);
+
+impl<T, MinimumCount, ExpiresIn> CombineData<OracleKeyOf<T>, TimestampedValueT>
+	for StakeWeightedMedianCombineData<T, MinimumCount, ExpiresIn>
+where
+	T: Config,
+	T::AccountId: AsRef<[u8]> + ToHex,
+	MinimumCount: Get<u32>,
+	ExpiresIn: Get<u128>,
+{
+	fn combine_data(
+		key: &OracleKeyOf<T>,
+		_values: Vec<TimestampedValueT>,
+		prev_value: Option<TimestampedValueT>,
+	) -> Option<TimestampedValueT> {
+		// The feeders are needed for the weights, so the raw values are read again.
+		let strategy = CombineStrategy::StakeWeightedMedian {
+			minimum_count: MinimumCount::get(),
+			expires_in: ExpiresIn::get(),
+		};
+		strategy.combine(
+			Pallet::<T>::read_feeder_values(key),
+			prev_value,
+			T::UnixTime::now().as_millis(),
+			Pallet::<T>::feeder_stake,
+			Pallet::<T>::reputation,
+		)
+	}
+}
+
+/// Median of the values weighted by the reputation of their feeder.
+/// Returns prev_value if not enough valid values.
+pub struct ReputationWeightedMedianCombineData<T, MinimumCount, ExpiresIn>(
+	marker::PhantomData<(T, MinimumCount, ExpiresIn)>,
+);
+
+impl<T, MinimumCount, ExpiresIn> CombineData<OracleKeyOf<T>, TimestampedValueT>
+	for ReputationWeightedMedianCombineData<T, MinimumCount, ExpiresIn>
+where
+	T: Config,
+	T::AccountId: AsRef<[u8]> + ToHex,
+	MinimumCount: Get<u32>,
+	ExpiresIn: Get<u128>,
+{
+	fn combine_data(
+		key: &OracleKeyOf<T>,
+		_values: Vec<TimestampedValueT>,
+		prev_value: Option<TimestampedValueT>,
+	) -> Option<TimestampedValueT> {
+		// The feeders are needed for the weights, so the raw values are read again.
+		let strategy = CombineStrategy::ReputationWeightedMedian {
+			minimum_count: MinimumCount::get(),
+			expires_in: ExpiresIn::get(),
+		};
+		strategy.combine(
+			Pallet::<T>::read_feeder_values(key),
+			prev_value,
+			T::UnixTime::now().as_millis(),
+			Pallet::<T>::feeder_stake,
+			Pallet::<T>::reputation,
+		)
+	}
+}
+
+/// Latest value if fed in the last `MaxAge` milliseconds.
+/// Returns prev_value otherwise.
+pub struct LastValueCombineData<T, MaxAge>(marker::PhantomData<(T, MaxAge)>);
+
+impl<T, MaxAge> CombineData<OracleKeyOf<T>, TimestampedValueT> for LastValueCombineData<T, MaxAge>
+where
+	T: Config,
+	T::AccountId: AsRef<[u8]> + ToHex,
+	MaxAge: Get<u128>,
+{
+	fn combine_data(
+		_key: &OracleKeyOf<T>,
+		values: Vec<TimestampedValueT>,
+		prev_value: Option<TimestampedValueT>,
+	) -> Option<TimestampedValueT> {
+		let strategy = CombineStrategy::LastValue { max_age: MaxAge::get() };
+		combine_anonymous(strategy, values, prev_value, T::UnixTime::now().as_millis())
+	}
+}
+
+/// Combines values whose feeders are unknown, by a strategy not using them.
+fn combine_anonymous(
+	strategy: CombineStrategy,
+	values: Vec<TimestampedValueT>,
+	prev_value: Option<TimestampedValueT>,
+	now: u128,
+) -> Option<TimestampedValueT> {
+	let values = values.into_iter().map(|x| (CreatorId::<()>::AccountId(()), x)).collect();
+	strategy.combine(values, prev_value, now, |_| 0, |_| Permill::one())
+}
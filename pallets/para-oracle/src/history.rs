@@ -0,0 +1,94 @@
+use crate::TimestampedValueT;
+use codec::{Decode, Encode};
+use frame_support::{
+	traits::Get, BoundedVec, CloneNoBound, EqNoBound, PartialEqNoBound, RuntimeDebug,
+	RuntimeDebugNoBound,
+};
+use scale_info::TypeInfo;
+use sp_std::prelude::*;
+
+/// A combined value of a key, with the value accumulated since the first observation
+#[derive(Encode, Decode, RuntimeDebug, Eq, PartialEq, Clone, Copy, TypeInfo)]
+pub struct Observation {
+	pub value: i64,
+	pub timestamp: u128,
+	/// Sum of the previous values, each multiplied by the milliseconds it held
+	pub cumulative: i128,
+}
+
+impl Observation {
+	/// Returns the accumulated value at `timestamp`, the value holding since the observation
+	pub fn cumulative_at(&self, timestamp: u128) -> i128 {
+		let elapsed = timestamp.saturating_sub(self.timestamp) as i128;
+		self.cumulative.saturating_add((self.value as i128).saturating_mul(elapsed))
+	}
+}
+
+/// Ring buffer of the latest `Depth` observations of a key
+#[derive(Encode, Decode, RuntimeDebugNoBound, CloneNoBound, PartialEqNoBound, EqNoBound, TypeInfo)]
+#[scale_info(skip_type_params(Depth))]
+pub struct ValueHistory<Depth: Get<u32>> {
+	observations: BoundedVec<Observation, Depth>,
+	/// Index of the oldest observation, overwritten by the next one once the buffer is full
+	oldest: u32,
+}
+
+impl<Depth: Get<u32>> Default for ValueHistory<Depth> {
+	fn default() -> Self {
+		Self { observations: Default::default(), oldest: 0 }
+	}
+}
+
+impl<Depth: Get<u32>> ValueHistory<Depth> {
+	/// Returns the observations from the oldest to the latest
+	pub fn observations(&self) -> Vec<Observation> {
+		let (newer, older) = self.observations.split_at(self.oldest as usize);
+		older.iter().chain(newer).copied().collect()
+	}
+
+	fn latest_index(&self) -> Option<usize> {
+		let len = self.observations.len();
+		(len > 0).then(|| (self.oldest as usize + len - 1) % len)
+	}
+
+	/// Returns the latest observation
+	pub fn latest(&self) -> Option<&Observation> {
+		self.latest_index().and_then(|i| self.observations.get(i))
+	}
+
+	/// Records a combined value, overwriting the oldest observation if the buffer is full.
+	///
+	/// A value not newer than the latest observation replaces its value.
+	pub fn record(&mut self, value: TimestampedValueT) {
+		let cumulative = match self.latest_index() {
+			Some(i) if self.observations[i].timestamp >= value.timestamp => {
+				self.observations[i].value = value.value;
+				return;
+			}
+			Some(i) => self.observations[i].cumulative_at(value.timestamp),
+			None => 0,
+		};
+		let observation = Observation { value: value.value, timestamp: value.timestamp, cumulative };
+		if self.observations.try_push(observation).is_err() {
+			let len = self.observations.len() as u32;
+			if let Some(slot) = self.observations.get_mut(self.oldest as usize) {
+				*slot = observation;
+				self.oldest = (self.oldest + 1) % len;
+			}
+		}
+	}
+
+	/// Returns the time-weighted average value over the last `window` milliseconds before `now`.
+	///
+	/// Returns None if the history doesn't cover the window.
+	pub fn twap(&self, window: u128, now: u128) -> Option<i64> {
+		let latest = self.latest()?;
+		if window == 0 {
+			return Some(latest.value);
+		}
+		let start = now.checked_sub(window)?;
+		let at_start = self.observations().into_iter().rev().find(|x| x.timestamp <= start)?;
+		let accumulated = latest.cumulative_at(now).saturating_sub(at_start.cumulative_at(start));
+		Some((accumulated / window as i128) as i64)
+	}
+}
@@ -20,7 +20,8 @@ use frame_support::{
 use frame_system::{
     self as system,
     offchain::{
-        AppCrypto, CreateSignedTransaction, SendSignedTransaction, Signer, SubmitTransaction,
+        AppCrypto, CreateSignedTransaction, SendSignedTransaction, SendUnsignedTransaction,
+        SignedPayload, Signer, SigningTypes, SubmitTransaction,
     },
     pallet_prelude::*,
     Config as SystemConfig,
@@ -40,7 +41,8 @@ use sp_runtime::{
         storage::{MutateStorageError, StorageRetrievalError, StorageValueRef},
         Duration,
     },
-    traits::{Hash, UniqueSaturatedInto, Zero},
+    traits::{Hash, IdentifyAccount, UniqueSaturatedInto, Zero},
+    Permill,
 };
 use xcm::latest::{prelude::*, Junction, OriginKind, SendXcm, Xcm};
 use orml_traits::{CombineData, DataFeeder, DataProvider, DataProviderExtended, OnNewData};
@@ -48,12 +50,23 @@ use orml_utilities::OrderedSet;
 //use weights::WeightInfo;
 
 pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
 #[cfg(test)]
 mod tests;
 
 mod default_combine_data;
 pub use default_combine_data::DefaultCombineData;
 
+pub mod combine_data;
+pub use combine_data::CombineStrategy;
+
+pub mod history;
+pub use history::{Observation, ValueHistory};
+
+pub mod reputation;
+
 // Runtime benchmarking features
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
@@ -65,6 +78,12 @@ pub type BalanceOf<T> =
 
 pub type OracleKeyOf<T> = BoundedVec<u8, <T as Config>::StrLimit>;
 
+/// Maximum priority added to the unsigned feeds for the staleness of their keys: one hour
+pub const MAX_STALENESS_BONUS: TransactionPriority = 60 * 60;
+
+/// Maximum number of keys of a feed transaction checked for its staleness bonus
+pub const MAX_STALENESS_KEYS: usize = 16;
+
 /// Defines application identifier for crypto keys of this module.
 ///
 /// Every module that deals with signatures needs to declare its unique identifier for
@@ -136,16 +155,81 @@ pub struct ApiFeed<BlockNumber> {
     requested_block_number: BlockNumber,
     url: Option<Vec<u8>>,
     vpath: Option<Vec<u8>>,
+    /// Number of blocks between two fetches of the feed
+    interval_blocks: BlockNumber,
+}
+
+/// Guards of the combined value of a key
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct CircuitBreaker {
+    /// Maximum change of the combined value per update, in basis points of the previous
+    /// value. A larger change freezes the key.
+    pub max_change_bps: Option<u32>,
+    /// Maximum age in milliseconds of the combined value, older values are stale
+    pub max_age: Option<u128>,
 }
 
-enum TransactionType {
-    Signed,
-    UnsignedForAny,
-    UnsignedForAll,
-    Raw,
-    None,
+/// Status of a combined value
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub enum ValueStatus {
+    Valid,
+    /// Older than the max age of the circuit breaker of the key
+    Stale,
+    /// The key is frozen
+    Frozen,
 }
 
+/// Identifier of a reporting round, increasing from 1 for each key
+pub type RoundId = u32;
+
+/// Reporting round config of a key
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct RoundConfig<BlockNumber> {
+    /// Number of blocks after its start a round accepts submissions
+    pub duration: BlockNumber,
+    /// Number of submissions a round needs to be answered
+    pub min_submissions: u32,
+}
+
+/// A reporting round of a key
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct RoundData<BlockNumber> {
+    pub round_id: RoundId,
+    pub started_at: BlockNumber,
+    /// Last block of the round
+    pub deadline: BlockNumber,
+    pub submissions: u32,
+    /// The answer of the round, or of the latest answered round
+    pub answer: Option<i64>,
+    /// Timestamp of the answer
+    pub updated_at: Option<u128>,
+    /// The round the answer was computed in, lower than `round_id` if the round is not
+    /// answered yet
+    pub answered_in_round: RoundId,
+}
+
+/// The values fetched by an oracle member, fed by an unsigned transaction signed by the member
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct FeedPayload<Public, BlockNumber, Key> {
+    /// The block the values were fetched at
+    pub block_number: BlockNumber,
+    pub values: Vec<(Key, i64)>,
+    /// The key of the member signing the payload
+    pub public: Public,
+}
+
+impl<T: SigningTypes, Key: Encode> SignedPayload<T> for FeedPayload<T::Public, T::BlockNumber, Key> {
+    fn public(&self) -> T::Public {
+        self.public.clone()
+    }
+}
+
+pub type FeedPayloadOf<T> = FeedPayload<
+    <T as SigningTypes>::Public,
+    <T as frame_system::Config>::BlockNumber,
+    OracleKeyOf<T>,
+>;
+
 #[frame_support::pallet]
 pub mod pallet {
     use super::*;
@@ -187,17 +271,25 @@ pub mod pallet {
         #[pallet::constant]
         type UnsignedPriority: Get<TransactionPriority>;
 
+        /// Number of blocks a member must wait between two unsigned submissions
+        #[pallet::constant]
+        type UnsignedInterval: Get<Self::BlockNumber>;
+
         /// Type representing the weight of this pallet
         type WeightInfo: WeightInfo;
 
         type EstimateCallFee: EstimateCallFee<Call<Self>, BalanceOf<Self>>;
 
-        type Currency: frame_support::traits::Currency<Self::AccountId>;
+        /// The currency whose reserved balance is the stake of the feeders
+        type Currency: frame_support::traits::ReservableCurrency<Self::AccountId>;
 
         /// Provide the implementation to combine raw values to produce
 		/// aggregated value
 		type CombineData: CombineData<OracleKeyOf<Self>, TimestampedValueT>;
 
+        /// The origin allowed to select the combine strategy of the keys
+        type UpdateOrigin: EnsureOrigin<<Self as frame_system::Config>::RuntimeOrigin>;
+
         /// Oracle operators.
 		type Members: SortedMembers<Self::AccountId>;
 
@@ -208,6 +300,23 @@ pub mod pallet {
 		#[pallet::constant]
 		type MaxHasDispatchedSize: Get<u32>;
 
+		/// Number of combined values kept in the history of each key
+		#[pallet::constant]
+		type HistoryDepth: Get<u32>;
+
+		/// Weight of a new deviation in the reputation of a feeder, the previous reputation
+		/// decaying by its complement
+		#[pallet::constant]
+		type ReputationDecay: Get<Permill>;
+
+		/// Deviation from the combined value, relative to it, at which a raw value scores zero
+		#[pallet::constant]
+		type MaxDeviation: Get<Permill>;
+
+		/// Reputation below which the values of a feeder are excluded from the combined value
+		#[pallet::constant]
+		type MinReputation: Get<Permill>;
+
     }
 
     #[pallet::pallet]
@@ -215,9 +324,11 @@ pub mod pallet {
     #[pallet::without_storage_info]
     pub struct Pallet<T>(_);
 
+    /// The block from which each member can submit an unsigned feed again
     #[pallet::storage]
     #[pallet::getter(fn next_unsigned_at)]
-    pub(super) type NextUnsignedAt<T: Config> = StorageValue<_, T::BlockNumber, ValueQuery>;
+    pub(super) type NextUnsignedAt<T: Config> =
+        StorageMap<_, Twox64Concat, T::AccountId, T::BlockNumber, ValueQuery>;
 
     /// Storage map for the feed URL Endpoint
     #[pallet::storage]
@@ -225,11 +336,16 @@ pub mod pallet {
 	pub type ApiFeeds<T: Config> =
 		StorageDoubleMap<_, Twox64Concat, CreatorId<T::AccountId>, Twox64Concat, OracleKeyOf<T>, ApiFeed<T::BlockNumber>>;
 
-    /// Raw values for each oracle operators
+    /// Number of API feeds of each key, so that the keys with a feed are found without iterating
+    /// `ApiFeeds`
+    #[pallet::storage]
+	pub type ApiFeedCount<T: Config> = StorageMap<_, Twox64Concat, OracleKeyOf<T>, u32, ValueQuery>;
+
+    /// Raw values for each oracle operators, by key then by feeder
 	#[pallet::storage]
 	#[pallet::getter(fn raw_values)]
 	pub type RawValues<T: Config> =
-		StorageDoubleMap<_, Twox64Concat, CreatorId<T::AccountId>, Twox64Concat, OracleKeyOf<T>, TimestampedValueT>;
+		StorageDoubleMap<_, Twox64Concat, OracleKeyOf<T>, Twox64Concat, CreatorId<T::AccountId>, TimestampedValueT>;
 
 	/// Up to date combined value from Raw Values
 	#[pallet::storage]
@@ -237,6 +353,89 @@ pub mod pallet {
 	pub type Values<T: Config> =
 		StorageMap<_, Twox64Concat, OracleKeyOf<T>, TimestampedValueT>;
 
+	/// History of the combined values of each key, to compute the TWAPs
+	#[pallet::storage]
+	#[pallet::getter(fn history)]
+	pub type History<T: Config> =
+		StorageMap<_, Twox64Concat, OracleKeyOf<T>, ValueHistory<T::HistoryDepth>, ValueQuery>;
+
+	/// The combine strategy of the keys, the others are combined by `T::CombineData`
+	#[pallet::storage]
+	#[pallet::getter(fn combine_strategies)]
+	pub type CombineStrategies<T: Config> =
+		StorageMap<_, Twox64Concat, OracleKeyOf<T>, CombineStrategy>;
+
+	/// Exponentially decayed score of the feeders, one for the feeders never scored
+	#[pallet::storage]
+	pub type Reputation<T: Config> =
+		StorageMap<_, Twox64Concat, CreatorId<T::AccountId>, Permill>;
+
+	/// Length in blocks of the rounds of the keys in commit-reveal mode
+	#[pallet::storage]
+	#[pallet::getter(fn commit_reveal_keys)]
+	pub type CommitRevealKeys<T: Config> =
+		StorageMap<_, Twox64Concat, OracleKeyOf<T>, T::BlockNumber>;
+
+	/// The round and the hash of the latest commitment of each feeder, for the keys in
+	/// commit-reveal mode
+	#[pallet::storage]
+	#[pallet::getter(fn commitments)]
+	pub type Commitments<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		OracleKeyOf<T>,
+		Twox64Concat,
+		CreatorId<T::AccountId>,
+		(T::BlockNumber, T::Hash),
+	>;
+
+	/// Number of commitments not revealed in time by each feeder
+	#[pallet::storage]
+	#[pallet::getter(fn missed_reports)]
+	pub type MissedReports<T: Config> =
+		StorageMap<_, Twox64Concat, CreatorId<T::AccountId>, u32, ValueQuery>;
+
+	/// The reporting round config of the keys, the others have no rounds
+	#[pallet::storage]
+	#[pallet::getter(fn round_configs)]
+	pub type RoundConfigs<T: Config> =
+		StorageMap<_, Twox64Concat, OracleKeyOf<T>, RoundConfig<T::BlockNumber>>;
+
+	/// The latest reporting round of each key
+	#[pallet::storage]
+	#[pallet::getter(fn latest_round_data)]
+	pub type LatestRounds<T: Config> =
+		StorageMap<_, Twox64Concat, OracleKeyOf<T>, RoundData<T::BlockNumber>>;
+
+	/// The latest round each feeder reported to, for each key
+	#[pallet::storage]
+	#[pallet::getter(fn last_reported_round)]
+	pub type LastReportedRound<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		OracleKeyOf<T>,
+		Twox64Concat,
+		CreatorId<T::AccountId>,
+		RoundId,
+	>;
+
+	/// The circuit breaker of the keys, the others are never tripped nor stale
+	#[pallet::storage]
+	#[pallet::getter(fn circuit_breakers)]
+	pub type CircuitBreakers<T: Config> =
+		StorageMap<_, Twox64Concat, OracleKeyOf<T>, CircuitBreaker>;
+
+	/// The frozen keys, whose combined value is neither updated nor readable
+	#[pallet::storage]
+	#[pallet::getter(fn frozen)]
+	pub type Frozen<T: Config> = StorageMap<_, Twox64Concat, OracleKeyOf<T>, bool, ValueQuery>;
+
+	/// The values fed in the previous block, scored against the combined values in
+	/// `on_initialize`
+	#[pallet::storage]
+	pub(crate) type FedValues<T: Config> =
+		StorageValue<_, Vec<(OracleKeyOf<T>, CreatorId<T::AccountId>, i64)>, ValueQuery>;
+
 	/// If an oracle operator has fed a value in this block
 	#[pallet::storage]
 	pub(crate) type HasDispatched<T: Config> =
@@ -252,6 +451,24 @@ pub mod pallet {
 		AlreadyFeeded,
         /// XCM Send error
         XcmSendError,
+        /// The history doesn't cover the TWAP window
+        InsufficientHistory,
+        /// The key is in commit-reveal mode, its values must be committed then revealed
+        CommitRevealRequired,
+        /// The key is not in commit-reveal mode
+        NotCommitReveal,
+        /// No commitment of the feeder for the key
+        NoCommitment,
+        /// The commitment can only be revealed in the round following its commit
+        RevealOutOfRound,
+        /// The revealed value and salt don't match the commitment
+        CommitmentMismatch,
+        /// Feeder has already reported in the round of the key
+        AlreadyReportedInRound,
+        /// The key is frozen
+        ValueFrozen,
+        /// The value is older than the max age of the circuit breaker of the key
+        ValueStale,
     }
 
     #[pallet::hooks]
@@ -259,9 +476,18 @@ pub mod pallet {
     where
         T::AccountId: AsRef<[u8]> + ToHex + Decode
     {
-        /// `on_initialize` to return the weight used in `on_finalize`.
-		fn on_initialize(_n: T::BlockNumber) -> Weight {
+        /// Scores the feeders of the previous block, sweeps the expired commitments, and
+        /// returns the weight used by them and by `on_finalize`.
+        ///
+        /// The scoring waits for the next block so that its cost is known when it is charged.
+		fn on_initialize(n: T::BlockNumber) -> Weight {
+			let fed = <FedValues<T>>::take();
+			let count = fed.len() as u32;
+			Self::update_reputations(fed);
+			let swept = Self::sweep_expired_commitments(n);
 			T::WeightInfo::on_finalize()
+				.saturating_add(T::WeightInfo::score_feeders(count))
+				.saturating_add(swept)
 		}
 
 		fn on_finalize(_n: T::BlockNumber) {
@@ -319,6 +545,7 @@ pub mod pallet {
 		) -> DispatchResultWithPostInfo {
 			let feeder = ensure_signed(origin.clone())?;
             let cid = CreatorId::AccountId(feeder);
+            let values = Self::plain_values(values)?;
 
             // ensure feeder is authorized
             //ensure!(T::Members::contains(&feeder), Error::<T>::NoPermission);
@@ -329,21 +556,7 @@ pub mod pallet {
                 Error::<T>::AlreadyFeeded
             );
 
-            let now = T::UnixTime::now().as_millis();
-            for (key, value) in &values {
-                let timestamped = TimestampedValue {
-                    value: value.clone(),
-                    timestamp: now,
-                };
-                RawValues::<T>::insert(&cid, &key, timestamped);
-
-                // Update `Values` storage if `combined` yielded result.
-                if let Some(combined) = Self::combined(key) {
-                    <Values<T>>::insert(key, combined);
-                }
-            }
-
-            Self::deposit_event(Event::NewFeedData { sender: cid, values });
+            Self::do_feed_data(cid, values)?;
 			Ok(Pays::No.into())
 		}
 
@@ -365,6 +578,7 @@ pub mod pallet {
             let para_id =
                 ensure_sibling_para(<T as Config>::RuntimeOrigin::from(origin.clone()))?;
             let cid = CreatorId::ParaId(para_id);
+            let values = Self::plain_values(values)?;
 
             // // ensure feeder is authorized
             // ensure!(T::Members::contains(&feeder), Error::<T>::NoPermission);
@@ -375,24 +589,47 @@ pub mod pallet {
                 Error::<T>::AlreadyFeeded
             );
 
-            let now = T::UnixTime::now().as_millis();
-            for (key, value) in &values {
-                let timestamped = TimestampedValue {
-                    value: value.clone(),
-                    timestamp: now,
-                };
-                RawValues::<T>::insert(&cid, &key, timestamped);
-
-                // Update `Values` storage if `combined` yielded result.
-                if let Some(combined) = Self::combined(key) {
-                    <Values<T>>::insert(key, combined);
-                }
-            }
-
-            Self::deposit_event(Event::NewFeedData { sender: cid, values });
+            Self::do_feed_data(cid, values)?;
 			Ok(Pays::No.into())
 		}
         
+        /// Feed the external value by an unsigned transaction.
+		///
+		/// Call by the offchain worker of a member, the payload is signed by the member. The
+		/// signature and the membership are checked in `validate_unsigned`.
+		///
+		/// # Parameter:
+		/// * `payload` - the values, the block they were fetched at and the member key
+		/// * `signature` - signature of the payload by the member
+		///
+		/// # Emits
+		/// * `NewFeedData`
+        #[pallet::call_index(7)]
+        #[pallet::weight(T::WeightInfo::feed_data(payload.values.len() as u32))]
+		pub fn feed_data_unsigned(
+			origin: OriginFor<T>,
+			payload: FeedPayloadOf<T>,
+			_signature: T::Signature,
+		) -> DispatchResultWithPostInfo {
+			ensure_none(origin)?;
+            let feeder = payload.public.into_account();
+            let cid = CreatorId::AccountId(feeder.clone());
+            let values = Self::plain_values(payload.values)?;
+
+            // ensure account hasn't dispatched an updated yet
+            ensure!(
+                HasDispatched::<T>::mutate(|set| set.insert(cid.clone())),
+                Error::<T>::AlreadyFeeded
+            );
+
+            let next_unsigned_at =
+                <system::Pallet<T>>::block_number().saturating_add(T::UnsignedInterval::get());
+            NextUnsignedAt::<T>::insert(&feeder, next_unsigned_at);
+
+            Self::do_feed_data(cid, values)?;
+			Ok(Pays::No.into())
+		}
+
         /// Query the feed data.
 		///
 		/// Can be only XCM call from feed parachain.
@@ -409,12 +646,32 @@ pub mod pallet {
 			let para_id =
                 ensure_sibling_para(<T as Config>::RuntimeOrigin::from(origin.clone()))?;
 
-            if let Some(val) = Self::get(&key) {
-                Self::send_qret_to_parachain(para_id, key.into(), val.value.into())
-            } else {
-                Err(DispatchError::CannotLookup)
-            }
-            
+            let val = Self::get(&key)?;
+            Self::send_qret_to_parachain(para_id, key.into(), val.value.into())
+		}
+
+        /// Query the time-weighted average value of the feed.
+		///
+		/// Can be only XCM call from feed parachain, the value is sent back like `xcm_query_data`.
+		///
+		/// # Parameter:
+		/// * `key` - key for the feed
+		/// * `window` - milliseconds to average the value over
+		///
+        #[pallet::call_index(9)]
+        #[pallet::weight(T::WeightInfo::query_twap())]
+		pub fn xcm_query_twap(
+			origin: OriginFor<T>,
+			key: OracleKeyOf<T>,
+			window: u128,
+		) -> DispatchResult {
+			let para_id =
+                ensure_sibling_para(<T as Config>::RuntimeOrigin::from(origin.clone()))?;
+
+            // The average is as fresh as the history it's computed from
+            Self::get(&key)?;
+            let twap = Self::get_twap(&key, window).ok_or(Error::<T>::InsufficientHistory)?;
+            Self::send_qret_to_parachain(para_id, key.into(), twap.value)
 		}
 
         /// Submit the URL Endpoint for the feed.
@@ -427,6 +684,7 @@ pub mod pallet {
         /// * `vpath` - value path of the URL result
 		///     example: json = {"x":{"y": ["z", "zz"]}}
         ///     path: "/x/y/1" = "zz" 
+		/// * `interval_blocks` - number of blocks between two fetches of the feed
 		/// 
 		/// # Emits
 		/// * `NewApiFeed`
@@ -437,6 +695,7 @@ pub mod pallet {
             key: OracleKeyOf<T>,
             url: Vec<u8>,
             vpath: Vec<u8>,
+            interval_blocks: T::BlockNumber,
         ) -> DispatchResult {
             let submitter = ensure_signed(origin.clone())?;
             let cid = CreatorId::AccountId(submitter);
@@ -444,7 +703,7 @@ pub mod pallet {
             // ensure submitter is authorized
             //ensure!(T::Members::contains(&submitter), Error::<T>::NoPermission);
             
-            Self::do_submit_api(cid, key, url, vpath)?;
+            Self::do_submit_api(cid, key, url, vpath, interval_blocks)?;
 			Ok(())
         }
 
@@ -483,6 +742,7 @@ pub mod pallet {
 		/// * `vpath` - value path of the URL result
 		///     example: json = {"x":{"y": ["z", "zz"]}}
         ///     path: "/x/y/1" = "zz"
+		/// * `interval_blocks` - number of blocks between two fetches of the feed
         ///  
 		/// # Emits
 		/// * `NewApiFeed`
@@ -493,6 +753,7 @@ pub mod pallet {
             key: OracleKeyOf<T>,
             url: Vec<u8>,
             vpath: Vec<u8>,
+            interval_blocks: T::BlockNumber,
         ) -> DispatchResult {
             let para_id =
                 ensure_sibling_para(<T as Config>::RuntimeOrigin::from(origin.clone()))?;
@@ -501,7 +762,7 @@ pub mod pallet {
             // ensure submitter is authorized
             //ensure!(T::Members::contains(&submitter), Error::<T>::NoPermission);
             
-            Self::do_submit_api(cid, key, url, vpath)?;
+            Self::do_submit_api(cid, key, url, vpath, interval_blocks)?;
 			Ok(())
         }
 
@@ -530,6 +791,221 @@ pub mod pallet {
             Self::do_remove_api(cid, key)?;
             Ok(())
         }
+
+        /// Select the combine strategy of a key.
+		///
+		/// Can be called by `T::UpdateOrigin`.
+		///
+		/// # Parameter:
+		/// * `key` - key for the feed
+		/// * `strategy` - the strategy, or None to combine by `T::CombineData`
+		///
+		/// # Emits
+		/// * `CombineStrategySet`
+        #[pallet::call_index(8)]
+        #[pallet::weight(T::WeightInfo::set_combine_strategy())]
+        pub fn set_combine_strategy(
+            origin: OriginFor<T>,
+            key: OracleKeyOf<T>,
+            strategy: Option<CombineStrategy>,
+        ) -> DispatchResult {
+            T::UpdateOrigin::ensure_origin(origin)?;
+
+            CombineStrategies::<T>::set(&key, strategy);
+            Self::deposit_event(Event::CombineStrategySet { key, strategy });
+            Ok(())
+        }
+
+        /// Enable or disable the commit-reveal mode of a key.
+		///
+		/// Can be called by `T::UpdateOrigin`.
+		///
+		/// # Parameter:
+		/// * `key` - key for the feed
+		/// * `round_length` - length in blocks of the rounds, or None to disable the mode
+		///
+		/// # Emits
+		/// * `CommitRevealSet`
+        #[pallet::call_index(10)]
+        #[pallet::weight(T::WeightInfo::set_commit_reveal())]
+        pub fn set_commit_reveal(
+            origin: OriginFor<T>,
+            key: OracleKeyOf<T>,
+            round_length: Option<T::BlockNumber>,
+        ) -> DispatchResult {
+            T::UpdateOrigin::ensure_origin(origin)?;
+
+            CommitRevealKeys::<T>::set(&key, round_length);
+            if round_length.is_none() {
+                let _ = Commitments::<T>::clear_prefix(&key, u32::MAX, None);
+            }
+            Self::deposit_event(Event::CommitRevealSet { key, round_length });
+            Ok(())
+        }
+
+        /// Commit the hashes of values to reveal in the next round.
+		///
+		/// Can be called by the members. The hash of a value is `T::Hashing` of the SCALE encoded
+		/// `(value, salt)`. A commitment not revealed in the next round counts as a missed
+		/// report, when swept at the end of that round or when overwritten.
+		///
+		/// # Parameter:
+		/// * `commitments` - hash array for the keys in commit-reveal mode
+		///
+		/// # Emits
+		/// * `DataCommitted`
+        #[pallet::call_index(11)]
+        #[pallet::weight(T::WeightInfo::commit_data(commitments.len() as u32))]
+        pub fn commit_data(
+            origin: OriginFor<T>,
+            commitments: Vec<(OracleKeyOf<T>, T::Hash)>,
+        ) -> DispatchResultWithPostInfo {
+            let feeder = ensure_signed(origin)?;
+            ensure!(T::Members::contains(&feeder), Error::<T>::NoPermission);
+            let cid = CreatorId::AccountId(feeder);
+            let block_number = <system::Pallet<T>>::block_number();
+
+            let mut keys = Vec::with_capacity(commitments.len());
+            for (key, hash) in commitments {
+                let round = Self::commit_reveal_round(&key, block_number)
+                    .ok_or(Error::<T>::NotCommitReveal)?;
+                // A commitment of a previous round is overwritten before being revealed
+                if let Some((committed_round, _)) = Self::commitments(&key, &cid) {
+                    if committed_round < round {
+                        Self::record_missed_report(cid.clone(), key.clone());
+                    }
+                }
+                Commitments::<T>::insert(&key, &cid, (round, hash));
+                keys.push(key);
+            }
+
+            Self::deposit_event(Event::DataCommitted { sender: cid, keys });
+			Ok(Pays::No.into())
+        }
+
+        /// Reveal the values committed in the previous round.
+		///
+		/// Can be called by the members. The values matching their commitment are fed like
+		/// `feed_data`.
+		///
+		/// # Parameter:
+		/// * `values` - value and salt array for the committed keys
+		///
+		/// # Emits
+		/// * `NewFeedData`
+        #[pallet::call_index(12)]
+        #[pallet::weight(T::WeightInfo::feed_data(values.len() as u32))]
+        pub fn reveal_data(
+            origin: OriginFor<T>,
+            values: Vec<(OracleKeyOf<T>, i64, [u8; 32])>,
+        ) -> DispatchResultWithPostInfo {
+            let feeder = ensure_signed(origin)?;
+            ensure!(T::Members::contains(&feeder), Error::<T>::NoPermission);
+            let cid = CreatorId::AccountId(feeder);
+            let block_number = <system::Pallet<T>>::block_number();
+
+            let mut revealed = Vec::with_capacity(values.len());
+            for (key, value, salt) in values {
+                let (committed_round, hash) =
+                    Self::commitments(&key, &cid).ok_or(Error::<T>::NoCommitment)?;
+                let round = Self::commit_reveal_round(&key, block_number)
+                    .ok_or(Error::<T>::NotCommitReveal)?;
+                ensure!(
+                    committed_round.saturating_add(1u32.into()) == round,
+                    Error::<T>::RevealOutOfRound
+                );
+                ensure!(
+                    T::Hashing::hash_of(&(value, salt)) == hash,
+                    Error::<T>::CommitmentMismatch
+                );
+                Commitments::<T>::remove(&key, &cid);
+                revealed.push((key, value));
+            }
+
+            Self::do_feed_data(cid, revealed)?;
+			Ok(Pays::No.into())
+        }
+
+        /// Set the reporting round config of a key.
+		///
+		/// Can be called by `T::UpdateOrigin`. The latest round of the key is kept, the new
+		/// duration applies from the next round.
+		///
+		/// # Parameter:
+		/// * `key` - key for the feed
+		/// * `config` - the round config, or None to stop the rounds
+		///
+		/// # Emits
+		/// * `RoundConfigSet`
+        #[pallet::call_index(13)]
+        #[pallet::weight(T::WeightInfo::set_round_config())]
+        pub fn set_round_config(
+            origin: OriginFor<T>,
+            key: OracleKeyOf<T>,
+            config: Option<RoundConfig<T::BlockNumber>>,
+        ) -> DispatchResult {
+            T::UpdateOrigin::ensure_origin(origin)?;
+
+            RoundConfigs::<T>::set(&key, config.clone());
+            Self::deposit_event(Event::RoundConfigSet { key, config });
+            Ok(())
+        }
+
+        /// Set the circuit breaker of a key.
+		///
+		/// Can be called by `T::UpdateOrigin`.
+		///
+		/// # Parameter:
+		/// * `key` - key for the feed
+		/// * `breaker` - the circuit breaker, or None to remove it
+		///
+		/// # Emits
+		/// * `CircuitBreakerSet`
+        #[pallet::call_index(14)]
+        #[pallet::weight(T::WeightInfo::set_circuit_breaker())]
+        pub fn set_circuit_breaker(
+            origin: OriginFor<T>,
+            key: OracleKeyOf<T>,
+            breaker: Option<CircuitBreaker>,
+        ) -> DispatchResult {
+            T::UpdateOrigin::ensure_origin(origin)?;
+
+            CircuitBreakers::<T>::set(&key, breaker);
+            Self::deposit_event(Event::CircuitBreakerSet { key, breaker });
+            Ok(())
+        }
+
+        /// Freeze or unfreeze a key.
+		///
+		/// Can be called by `T::UpdateOrigin`. The combined value of a frozen key is neither
+		/// updated nor readable, a tripped circuit breaker is reset by unfreezing the key: the
+		/// combined value is cleared, and the next one is the new reference of the breaker.
+		///
+		/// # Parameter:
+		/// * `key` - key for the feed
+		/// * `frozen` - true to freeze the key
+		///
+		/// # Emits
+		/// * `FrozenSet`
+        #[pallet::call_index(15)]
+        #[pallet::weight(T::WeightInfo::set_frozen())]
+        pub fn set_frozen(
+            origin: OriginFor<T>,
+            key: OracleKeyOf<T>,
+            frozen: bool,
+        ) -> DispatchResult {
+            T::UpdateOrigin::ensure_origin(origin)?;
+
+            if frozen {
+                Frozen::<T>::insert(&key, true);
+            } else {
+                Frozen::<T>::remove(&key);
+                // The value the breaker tripped on would trip it again
+                Values::<T>::remove(&key);
+            }
+            Self::deposit_event(Event::FrozenSet { key, frozen });
+            Ok(())
+        }
         
     }
 
@@ -571,6 +1047,66 @@ pub mod pallet {
             key: OracleKeyOf<T>,
             feed: ApiFeed<T::BlockNumber>,
 		},
+        /// The reputation of a feeder dropped below `MinReputation`, its values are excluded
+        /// from the combined values.
+		FeederExcluded {
+			feeder: CreatorId<T::AccountId>,
+			reputation: Permill,
+		},
+        /// The circuit breaker of a key is set.
+		CircuitBreakerSet {
+            key: OracleKeyOf<T>,
+            breaker: Option<CircuitBreaker>,
+		},
+        /// A combined value changed more than the max change of the circuit breaker, the key
+        /// is frozen.
+		CircuitBreakerTripped {
+            key: OracleKeyOf<T>,
+            previous: i64,
+            rejected: i64,
+		},
+        /// A key is frozen or unfrozen.
+		FrozenSet {
+            key: OracleKeyOf<T>,
+            frozen: bool,
+		},
+        /// The reporting round config of a key is set.
+		RoundConfigSet {
+            key: OracleKeyOf<T>,
+            config: Option<RoundConfig<T::BlockNumber>>,
+		},
+        /// The first value of a new round of a key is reported.
+		NewRound {
+            key: OracleKeyOf<T>,
+            round_id: RoundId,
+            started_at: T::BlockNumber,
+		},
+        /// A round reached its minimum submissions count.
+		RoundAnswered {
+            key: OracleKeyOf<T>,
+            round_id: RoundId,
+            answer: i64,
+		},
+        /// The commit-reveal mode of a key is set.
+		CommitRevealSet {
+            key: OracleKeyOf<T>,
+            round_length: Option<T::BlockNumber>,
+		},
+        /// Hashes of values are committed.
+		DataCommitted {
+			sender: CreatorId<T::AccountId>,
+			keys: Vec<OracleKeyOf<T>>,
+		},
+        /// A feeder didn't reveal its commitment in time.
+		ReportMissed {
+			feeder: CreatorId<T::AccountId>,
+            key: OracleKeyOf<T>,
+		},
+        /// The combine strategy of a key is set.
+		CombineStrategySet {
+            key: OracleKeyOf<T>,
+            strategy: Option<CombineStrategy>,
+		},
     }
 
     #[pallet::validate_unsigned]
@@ -583,11 +1119,16 @@ pub mod pallet {
 
         /// Validate unsigned call to this module.
         ///
-        /// By default unsigned transactions are disallowed, but implementing the validator
-        /// here we make sure that some particular calls (the ones produced by offchain worker)
-        /// are being whitelisted and marked as valid.
+        /// Only the `feed_data_unsigned` calls whose payload is signed by a member are accepted,
+        /// at most once per `UnsignedInterval` blocks for each member.
         fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
-            InvalidTransaction::Call.into()
+            let Call::feed_data_unsigned { payload, signature } = call else {
+                return InvalidTransaction::Call.into();
+            };
+            if !SignedPayload::<T>::verify::<T::AuthorityId>(payload, signature.clone()) {
+                return InvalidTransaction::BadProof.into();
+            }
+            Self::validate_transaction(payload)
         }
     }
 
@@ -597,107 +1138,81 @@ pub mod pallet {
 impl<T: Config> Pallet<T>
 where T::AccountId: AsRef<[u8]>
 {
-    fn choose_transaction_type(block_number: T::BlockNumber) -> TransactionType {
-        /// A friendlier name for the error that is going to be returned in case we are in the grace
-        /// period.
-        const RECENTLY_SENT: () = ();
-
-        // Start off by creating a reference to Local Storage value.
-        // Since the local storage is common for all offchain workers, it's a good practice
-        // to prepend your entry with the module name.
-        let val = StorageValueRef::persistent(b"ParaOracle_oracle::last_send");
-        // The Local Storage is persisted and shared between runs of the offchain workers,
-        // and offchain workers may run concurrently. We can use the `mutate` function, to
-        // write a storage entry in an atomic fashion. Under the hood it uses `compare_and_set`
-        // low-level method of local storage API, which means that only one worker
-        // will be able to "acquire a lock" and send a transaction if multiple workers
-        // happen to be executed concurrently.
-        let res = val.mutate(
-            |last_send: Result<Option<T::BlockNumber>, StorageRetrievalError>| {
-                match last_send {
-                    // If we already have a value in storage and the block number is recent enough
-                    // we avoid sending another transaction at this time.
-                    Ok(Some(block)) if block_number < block => Err(RECENTLY_SENT),
-                    // In every other case we attempt to acquire the lock and send a transaction.
-                    _ => Ok(block_number),
-                }
-            },
-        );
-
-        // The result of `mutate` call will give us a nested `Result` type.
-        // The first one matches the return of the closure passed to `mutate`, i.e.
-        // if we return `Err` from the closure, we get an `Err` here.
-        // In case we return `Ok`, here we will have another (inner) `Result` that indicates
-        // if the value has been set to the storage correctly - i.e. if it wasn't
-        // written to in the meantime.
-        match res {
-            // The value has been set correctly, which means we can safely send a transaction now.
-            Ok(block_number) => {
-                // Depending if the block is even or odd we will send a `Signed` or `Unsigned`
-                // transaction.
-                // Note that this logic doesn't really guarantee that the transactions will be sent
-                // in an alternating fashion (i.e. fairly distributed). Depending on the execution
-                // order and lock acquisition, we may end up for instance sending two `Signed`
-                // transactions in a row. If a strict order is desired, it's better to use
-                // the storage entry for that. (for instance store both block number and a flag
-                // indicating the type of next transaction to send).
-                let transaction_type = block_number % 3u32.into();
-                if transaction_type == Zero::zero() {
-                    TransactionType::Signed
-                } else if transaction_type == T::BlockNumber::from(1u32) {
-                    TransactionType::UnsignedForAny
-                } else if transaction_type == T::BlockNumber::from(2u32) {
-                    TransactionType::UnsignedForAll
-                } else {
-                    TransactionType::Raw
-                }
-            }
-            // We are in the grace period, we should not send a transaction this time.
-            Err(MutateStorageError::ValueFunctionFailed(RECENTLY_SENT)) => TransactionType::None,
-            // We wanted to send a transaction, but failed to write the block number (acquire a
-            // lock). This indicates that another offchain worker that was running concurrently
-            // most likely executed the same logic and succeeded at writing to storage.
-            // Thus we don't really want to send the transaction, knowing that the other run
-            // already did.
-            Err(MutateStorageError::ConcurrentModification(_)) => TransactionType::None,
-        }
-    }
-
-    /// A helper function to fetch the price and send signed transaction.
+    /// A helper function to fetch the due feeds and send an unsigned transaction for each local
+    /// member account.
+    ///
+    /// The feeds are sharded among the members, and the shards rotate with the block number: the
+    /// feed `i` is fetched by the member `(i + block_number) % members` of the sorted members.
+    /// The feeds are fetched concurrently, and the failing ones are skipped.
     fn fetch_api_and_feed_data(block_number: T::BlockNumber) -> Result<(), &'static str> {
-        let signer = Signer::<T, T::AuthorityId>::all_accounts();
-        if !signer.can_sign() {
+        let members = Self::local_members(block_number);
+        if members.is_empty() {
             return Err(
-                "No local accounts available. Consider adding one via `author_insertKey` RPC.",
+                "No local member account available. Consider adding one via `author_insertKey` RPC.",
             )?;
         }
+        let sorted_members = T::Members::sorted_members();
+        let rotation: u64 = block_number.unique_saturated_into();
+
+        // The due feeds of the shards of the local members
+        let mut jobs = Vec::new();
+        for (index, (_, key, feed)) in
+            <ApiFeeds<T> as IterableStorageDoubleMap<_, _, _>>::iter().enumerate()
+        {
+            let (Some(url), Some(vpath)) = (feed.url, feed.vpath) else {
+                continue;
+            };
+            // The values of the keys in commit-reveal mode must be committed then revealed
+            if <CommitRevealKeys<T>>::contains_key(&key) {
+                continue;
+            }
+            let shard = (index as u64).wrapping_add(rotation) % sorted_members.len() as u64;
+            let Some(public) = members.iter().find(|public| {
+                sorted_members.get(shard as usize) == Some(&(*public).clone().into_account())
+            }) else {
+                continue;
+            };
+            if Self::is_feed_due(&key, feed.interval_blocks, block_number) {
+                jobs.push((public.clone(), key, url, vpath));
+            }
+        }
+        if jobs.is_empty() {
+            return Ok(());
+        }
 
-        let mut values = Vec::<(OracleKeyOf<T>, i64)>::new();
-        for (_creator, key, val) in <ApiFeeds<T> as IterableStorageDoubleMap<_, _, _>>::iter() {
-            // let mut response :Vec<u8>;
-            if val.url.is_some() && val.vpath.is_some() {
-                let vpath = val.vpath.unwrap();
-                let response = Self::fetch_http_get_result(val.url.clone().unwrap())
-                    .map_err(|_| "Failed fetch http")?;
-                let res_json :JValue = serde_json::from_slice(&response)
-                    .map_err(|_| "Response JSON was not well-formatted")?;
-                let path = str::from_utf8(&vpath)
-                    .map_err(|_| "vpath contain invalid utf8 string")?;
-                let fval = res_json.pointer(path)
-                    .ok_or("vpath error")?
-                    .as_f64()
-                    .ok_or("vpath value type error")?;
-
-                // We only store int, so every float will be convert to int with 6 decimals pad
-                let ival :i64 = (fval * 1000000.0) as i64;
-                values.push((key.clone(), ival));
+        let urls: Vec<Vec<u8>> = jobs.iter().map(|(_, _, url, _)| url.clone()).collect();
+        let responses = Self::fetch_http_get_results(&urls);
+
+        let mut values_by_member = Vec::<(T::Public, Vec<(OracleKeyOf<T>, i64)>)>::new();
+        for ((public, key, _, vpath), response) in jobs.into_iter().zip(responses) {
+            let value = response
+                .map_err(|_| "Failed fetch http")
+                .and_then(|body| Self::parse_feed_value(&body, &vpath));
+            let value = match value {
+                Ok(value) => value,
+                Err(e) => {
+                    log::warn!("Skipped feed {:?}: {}", key, e);
+                    continue;
+                }
+            };
+            Self::set_last_fetched(&key, block_number);
+            match values_by_member.iter_mut().find(|(member, _)| *member == public) {
+                Some((_, values)) => values.push((key, value)),
+                None => values_by_member.push((public, vec![(key, value)])),
             }
         }
 
-        if values.len() > 0 {
-            let results = signer.send_signed_transaction(|_account| Call::feed_data {
-                values: values.clone(),
-            });
+        for (public, values) in values_by_member {
+            let results = Signer::<T, T::AuthorityId>::all_accounts()
+                .with_filter(vec![public])
+                .send_unsigned_transaction(
+                    |account| FeedPayload {
+                        block_number,
+                        values: values.clone(),
+                        public: account.public.clone(),
+                    },
+                    |payload, signature| Call::feed_data_unsigned { payload, signature },
+                );
             for (acc, res) in &results {
                 match res {
                     Ok(()) => log::info!("[{:?}] Submitted data", acc.id),
@@ -708,56 +1223,94 @@ where T::AccountId: AsRef<[u8]>
 
         Ok(())
     }
-    
-    /// Fetch current price and return the result in cents.
-    fn fetch_http_get_result(url: Vec<u8>) -> Result<Vec<u8>, http::Error> {
-        // We want to keep the offchain worker execution time reasonable, so we set a hard-coded
-        // deadline to 2s to complete the external call.
-        // You can also wait idefinitely for the response, however you may still get a timeout
-        // coming from the host machine.
+
+    /// Returns true if `interval_blocks` passed since the key was last fetched by this node.
+    ///
+    /// The `last_fetched` blocks are kept in the offchain local storage, the failed fetches
+    /// being retried at the next block.
+    pub fn is_feed_due(
+        key: &OracleKeyOf<T>,
+        interval_blocks: T::BlockNumber,
+        block_number: T::BlockNumber,
+    ) -> bool {
+        let last_fetched = StorageValueRef::persistent(&Self::last_fetched_key(key));
+        match last_fetched.get::<T::BlockNumber>() {
+            Ok(Some(last_fetched)) => {
+                block_number >= last_fetched.saturating_add(interval_blocks.max(1u32.into()))
+            }
+            // Never fetched, or the record is corrupted
+            _ => true,
+        }
+    }
+
+    /// Records in the offchain local storage that the key was fetched at the block
+    fn set_last_fetched(key: &OracleKeyOf<T>, block_number: T::BlockNumber) {
+        StorageValueRef::persistent(&Self::last_fetched_key(key)).set(&block_number);
+    }
+
+    fn last_fetched_key(key: &OracleKeyOf<T>) -> Vec<u8> {
+        let mut storage_key = b"para_oracle::last_fetched::".to_vec();
+        storage_key.extend_from_slice(key);
+        storage_key
+    }
+
+    /// Reads the number at `vpath` of the JSON response
+    fn parse_feed_value(response: &[u8], vpath: &[u8]) -> Result<i64, &'static str> {
+        let res_json: JValue = serde_json::from_slice(response)
+            .map_err(|_| "Response JSON was not well-formatted")?;
+        let path = str::from_utf8(vpath).map_err(|_| "vpath contain invalid utf8 string")?;
+        let fval = res_json
+            .pointer(path)
+            .ok_or("vpath error")?
+            .as_f64()
+            .ok_or("vpath value type error")?;
+
+        // We only store int, so every float will be convert to int with 6 decimals pad
+        Ok((fval * 1000000.0) as i64)
+    }
+
+    /// Fetches the urls concurrently and returns the response bodies
+    fn fetch_http_get_results(urls: &[Vec<u8>]) -> Vec<Result<Vec<u8>, http::Error>> {
+        // We want to keep the offchain worker execution time reasonable, so all the requests
+        // share a hard-coded deadline of 10s.
         let deadline = sp_io::offchain::timestamp().add(Duration::from_millis(10_000));
-        // Initiate an external HTTP GET request.
-        // This is using high-level wrappers from `sp_runtime`, for the low-level calls that
-        // you can find in `sp_io`. The API is trying to be similar to `reqwest`, but
-        // since we are running in a custom WASM execution environment we can't simply
-        // import the library here.
-        let request = http::Request::get(str::from_utf8(&url).unwrap());
-
-        // We set the deadline for sending of the request, note that awaiting response can§
-        // have a separate deadline. Next we send the request, before that it's also possible
-        // to alter request headers or stream body content in case of non-GET requests.
-        let pending = request
-            .deadline(deadline)
-            .send()
-            .map_err(|_| http::Error::IoError)?;
-
-        // The request is already being processed by the host, we are free to do anything
-        // else in the worker (we can send multiple concurrent requests too).
-        // At some point however we probably want to check the response though,
-        // so we can block current thread and wait for it to finish.
-        // Note that since the request is being driven by the host, we don't have to wait
-        // for the request to have it complete, we will just not read the response.
-        let response = pending
-            .try_wait(deadline)
-            .map_err(|_| http::Error::DeadlineReached)??;
-
-        // Let's check the status code before we proceed to reading the response.
-        if response.code != 200 {
-            log::info!("Unexpected status code: {}", response.code);
-            return Err(http::Error::Unknown);
-        }
-
-        // Next we want to fully read the response body and collect it to a vector of bytes.
-        // Note that the return object allows you to read the body in chunks as well
-        // with a way to control the deadline.
-        let body = response.body().collect::<Vec<u8>>();
-        // Create a str slice from the body.
-        let body_str = sp_std::str::from_utf8(&body).map_err(|_| {
-            log::info!("No UTF8 body");
-            http::Error::Unknown
-        })?;
-
-        Ok(body_str.clone().as_bytes().to_vec())
+
+        // Send all the requests before waiting for any of them
+        let mut results = Vec::with_capacity(urls.len());
+        let mut pending = Vec::new();
+        let mut pending_indices = Vec::new();
+        for (index, url) in urls.iter().enumerate() {
+            let sent = str::from_utf8(url)
+                .map_err(|_| http::Error::Unknown)
+                .and_then(|url| {
+                    http::Request::get(url)
+                        .deadline(deadline)
+                        .send()
+                        .map_err(|_| http::Error::IoError)
+                });
+            match sent {
+                Ok(request) => {
+                    pending.push(request);
+                    pending_indices.push(index);
+                    results.push(Err(http::Error::DeadlineReached));
+                }
+                Err(e) => results.push(Err(e)),
+            }
+        }
+
+        let responses = http::PendingRequest::try_wait_all(pending, deadline);
+        for (index, response) in pending_indices.into_iter().zip(responses) {
+            results[index] = match response {
+                Ok(Ok(response)) if response.code == 200 => Ok(response.body().collect()),
+                Ok(Ok(response)) => {
+                    log::info!("Unexpected status code: {}", response.code);
+                    Err(http::Error::Unknown)
+                }
+                Ok(Err(e)) => Err(e),
+                Err(_) => Err(http::Error::DeadlineReached),
+            };
+        }
+        results
     }
 
     fn send_qret_to_parachain(para_id: ParaId, key: Vec<u8>, value: i64) -> DispatchResult {
@@ -784,43 +1337,401 @@ where T::AccountId: AsRef<[u8]>
         Ok(())
     }
 
-    fn validate_transaction(block_number: &T::BlockNumber) -> TransactionValidity {
+    /// Returns the public keys of the local accounts which are members allowed to feed at the
+    /// block
+    fn local_members(block_number: T::BlockNumber) -> Vec<T::Public> {
+        <T::AuthorityId as AppCrypto<T::Public, T::Signature>>::RuntimeAppPublic::all()
+            .into_iter()
+            .map(|key| {
+                let generic: <T::AuthorityId as AppCrypto<T::Public, T::Signature>>::GenericPublic =
+                    key.into();
+                generic.into()
+            })
+            .filter(|public: &T::Public| {
+                let account = public.clone().into_account();
+                T::Members::contains(&account) && Self::next_unsigned_at(&account) <= block_number
+            })
+            .collect()
+    }
+
+    fn validate_transaction(payload: &FeedPayloadOf<T>) -> TransactionValidity {
+        let feeder = payload.public.clone().into_account();
+        if !T::Members::contains(&feeder) {
+            return InvalidTransaction::BadSigner.into();
+        }
         // Now let's check if the transaction has any chance to succeed.
-        let next_unsigned_at = <NextUnsignedAt<T>>::get();
-        if &next_unsigned_at > block_number {
+        let next_unsigned_at = <NextUnsignedAt<T>>::get(&feeder);
+        if next_unsigned_at > payload.block_number {
             return InvalidTransaction::Stale.into();
         }
         // Let's make sure to reject transactions from the future.
         let current_block = <system::Pallet<T>>::block_number();
-        if &current_block < block_number {
+        if current_block < payload.block_number {
             return InvalidTransaction::Future.into();
         }
         ValidTransaction::with_tag_prefix("ParaOracleOCW")
-            .priority(T::UnsignedPriority::get())
+            .priority(
+                T::UnsignedPriority::get().saturating_add(Self::staleness(&payload.values)),
+            )
+            .and_provides((feeder, payload.block_number))
             .longevity(5)
             .propagate(true)
             .build()
     }
 
-    pub fn read_raw_values(key: &OracleKeyOf<T>) -> Vec<TimestampedValueT> {
-        <RawValues<T> as IterableStorageDoubleMap<_, _, _>>::iter()
-            .filter_map(|(_, k, val)| if *key == k { Some(val) } else { None })
+    /// Returns the age in seconds of the stalest combined value of the keys, so that the
+    /// updates of the stale feeds are included first.
+    ///
+    /// The age is capped at `MAX_STALENESS_BONUS`, and the keys without an API feed are ignored:
+    /// a member can't outbid the other transactions by feeding made-up keys. Only the first
+    /// `MAX_STALENESS_KEYS` keys are checked, bounding the cost of the validation.
+    fn staleness(values: &[(OracleKeyOf<T>, i64)]) -> TransactionPriority {
+        let now = T::UnixTime::now().as_millis();
+        values
+            .iter()
+            .take(MAX_STALENESS_KEYS)
+            .filter(|(key, _)| <ApiFeedCount<T>>::get(key) > 0)
+            .map(|(key, _)| match Self::values(key) {
+                Some(combined) => (now.saturating_sub(combined.timestamp) / 1000)
+                    .unique_saturated_into(),
+                // Never fed
+                None => MAX_STALENESS_BONUS,
+            })
+            .max()
+            .unwrap_or_default()
+            .min(MAX_STALENESS_BONUS)
+    }
+
+    fn do_feed_data(cid: CreatorId<T::AccountId>, values: Vec<(OracleKeyOf<T>, i64)>) -> DispatchResult {
+        let now = T::UnixTime::now().as_millis();
+        let mut fed = Vec::with_capacity(values.len());
+        for (key, value) in values {
+            let key = &key;
+            let round = match Self::round_to_report(&cid, key) {
+                Ok(round) => round,
+                // The value waits for the next round, the other keys are still fed
+                Err(e) if e == Error::<T>::AlreadyReportedInRound.into() => continue,
+                Err(e) => return Err(e),
+            };
+
+            let timestamped = TimestampedValue {
+                value: value.clone(),
+                timestamp: now,
+            };
+            RawValues::<T>::insert(&key, &cid, timestamped);
+
+            // Update `Values` storage if `combined` yielded result, unless the circuit breaker
+            // prevents it.
+            let combined = Self::combined(key).filter(|combined| Self::accept_update(key, combined));
+            if let Some(combined) = combined {
+                <Values<T>>::insert(key, combined);
+                <History<T>>::mutate(key, |history| history.record(combined));
+            }
+            <FedValues<T>>::append((key.clone(), cid.clone(), value));
+
+            if let Some((round, min_submissions)) = round {
+                Self::record_round_submission(&cid, key, round, min_submissions);
+            }
+            fed.push((key.clone(), value));
+        }
+
+        Self::deposit_event(Event::NewFeedData { sender: cid, values: fed });
+        Ok(())
+    }
+
+    /// Returns the round of the key the feeder reports to with the minimum submissions count,
+    /// starting a new round if the latest one is past its deadline. Fails if the feeder already
+    /// reported in the round.
+    ///
+    /// Returns None if the key has no round config.
+    fn round_to_report(
+        cid: &CreatorId<T::AccountId>,
+        key: &OracleKeyOf<T>,
+    ) -> Result<Option<(RoundData<T::BlockNumber>, u32)>, DispatchError> {
+        let Some(config) = Self::round_configs(key) else {
+            return Ok(None);
+        };
+        let block_number = <system::Pallet<T>>::block_number();
+        let round = match Self::latest_round_data(key) {
+            Some(latest) if block_number <= latest.deadline => {
+                ensure!(
+                    Self::last_reported_round(key, cid) != Some(latest.round_id),
+                    Error::<T>::AlreadyReportedInRound
+                );
+                latest
+            }
+            // The answer of the previous round is carried until the new round is answered
+            latest => RoundData {
+                round_id: latest.as_ref().map_or(1, |r| r.round_id.saturating_add(1)),
+                started_at: block_number,
+                deadline: block_number.saturating_add(config.duration),
+                submissions: 0,
+                answer: latest.as_ref().and_then(|r| r.answer),
+                updated_at: latest.as_ref().and_then(|r| r.updated_at),
+                answered_in_round: latest.as_ref().map_or(0, |r| r.answered_in_round),
+            },
+        };
+        Ok(Some((round, config.min_submissions)))
+    }
+
+    /// Counts the submission of the feeder in the round, the round being answered by the
+    /// median of its submissions once it has the minimum submissions count. Like a Chainlink
+    /// FluxAggregator, the values of the previous rounds are not part of the answer.
+    fn record_round_submission(
+        cid: &CreatorId<T::AccountId>,
+        key: &OracleKeyOf<T>,
+        mut round: RoundData<T::BlockNumber>,
+        min_submissions: u32,
+    ) {
+        if round.submissions == 0 {
+            Self::deposit_event(Event::NewRound {
+                key: key.clone(),
+                round_id: round.round_id,
+                started_at: round.started_at,
+            });
+        }
+        round.submissions = round.submissions.saturating_add(1);
+        <LastReportedRound<T>>::insert(key, cid, round.round_id);
+        let answer = if round.submissions >= min_submissions {
+            combine_data::median(Self::round_submissions(key, round.round_id))
+        } else {
+            None
+        };
+        if let Some(answer) = answer {
+            round.answer = Some(answer.value);
+            round.updated_at = Some(answer.timestamp);
+            if round.answered_in_round != round.round_id {
+                round.answered_in_round = round.round_id;
+                Self::deposit_event(Event::RoundAnswered {
+                    key: key.clone(),
+                    round_id: round.round_id,
+                    answer: answer.value,
+                });
+            }
+        }
+        <LatestRounds<T>>::insert(key, round);
+    }
+
+    /// Returns the values submitted to the round of the key
+    fn round_submissions(key: &OracleKeyOf<T>, round_id: RoundId) -> Vec<TimestampedValueT> {
+        <LastReportedRound<T>>::iter_prefix(key)
+            .filter(|(_, reported)| *reported == round_id)
+            .filter_map(|(cid, _)| Self::raw_values(key, &cid))
             .collect()
+    }
+
+    /// Returns true if the latest round of the key is not answered, or is past its deadline
+    pub fn is_round_stale(key: &OracleKeyOf<T>) -> bool {
+        match Self::latest_round_data(key) {
+            Some(round) => {
+                round.answered_in_round < round.round_id
+                    || <system::Pallet<T>>::block_number() > round.deadline
+            }
+            None => true,
+        }
+    }
+
+    /// Returns the raw values of the key, excluding the feeders whose reputation is below
+    /// `MinReputation`
+    pub fn read_raw_values(key: &OracleKeyOf<T>) -> Vec<TimestampedValueT> {
+        Self::read_feeder_values(key).into_iter().map(|(_, val)| val).collect()
 	}
 
+    /// Returns the raw values of the key with their feeder, excluding the feeders whose
+    /// reputation is below `MinReputation`
+    pub fn read_feeder_values(
+        key: &OracleKeyOf<T>,
+    ) -> Vec<(CreatorId<T::AccountId>, TimestampedValueT)> {
+        <RawValues<T>>::iter_prefix(key)
+            .filter(|(cid, _)| Self::reputation(cid) >= T::MinReputation::get())
+            .collect()
+    }
+
+    /// Returns the reputation of a feeder
+    pub fn reputation(cid: &CreatorId<T::AccountId>) -> Permill {
+        <Reputation<T>>::get(cid).unwrap_or_else(Permill::one)
+    }
+
+    /// Scores the deviation of the fed values from the combined values of their keys
+    fn update_reputations(fed: Vec<(OracleKeyOf<T>, CreatorId<T::AccountId>, i64)>) {
+        for (key, cid, value) in fed {
+            let Some(combined) = Self::values(&key) else {
+                continue;
+            };
+            let observation =
+                reputation::deviation_score(value, combined.value, T::MaxDeviation::get());
+            Self::score_feeder(cid, observation);
+        }
+    }
+
+    /// Decays the reputation of a feeder by a new observation
+    fn score_feeder(cid: CreatorId<T::AccountId>, observation: Permill) {
+        let previous = Self::reputation(&cid);
+        let updated = reputation::decay(previous, observation, T::ReputationDecay::get());
+        <Reputation<T>>::insert(&cid, updated);
+
+        let threshold = T::MinReputation::get();
+        if previous >= threshold && updated < threshold {
+            Self::deposit_event(Event::FeederExcluded { feeder: cid, reputation: updated });
+        }
+    }
+
+    /// Returns the commit-reveal round of the block for a key, if the key is in commit-reveal
+    /// mode
+    pub fn commit_reveal_round(key: &OracleKeyOf<T>, block_number: T::BlockNumber) -> Option<T::BlockNumber> {
+        let round_length = Self::commit_reveal_keys(key)?;
+        Some(block_number / round_length.max(1u32.into()))
+    }
+
+    /// Skips the values of the keys in commit-reveal mode, those values must be revealed. Fails
+    /// only if all the values are skipped.
+    fn plain_values(
+        values: Vec<(OracleKeyOf<T>, i64)>,
+    ) -> Result<Vec<(OracleKeyOf<T>, i64)>, DispatchError> {
+        let fed = values.len();
+        let plain: Vec<_> = values
+            .into_iter()
+            .filter(|(key, _)| !<CommitRevealKeys<T>>::contains_key(key))
+            .collect();
+        ensure!(fed == 0 || !plain.is_empty(), Error::<T>::CommitRevealRequired);
+        Ok(plain)
+    }
+
+    /// Removes the commitments not revealed in the round following their commit, when a round
+    /// of their key starts, and records them as missed reports.
+    ///
+    /// Returns the weight used.
+    fn sweep_expired_commitments(block_number: T::BlockNumber) -> Weight {
+        let (mut keys, mut commitments) = (0u32, 0u32);
+        for (key, round_length) in <CommitRevealKeys<T>>::iter() {
+            keys += 1;
+            let round_length = round_length.max(1u32.into());
+            if !(block_number % round_length).is_zero() {
+                continue;
+            }
+            let round = block_number / round_length;
+            let expired: Vec<_> = <Commitments<T>>::iter_prefix(&key)
+                .inspect(|_| commitments += 1)
+                .filter(|(_, (committed_round, _))| {
+                    committed_round.saturating_add(1u32.into()) < round
+                })
+                .map(|(cid, _)| cid)
+                .collect();
+            for cid in expired {
+                <Commitments<T>>::remove(&key, &cid);
+                Self::record_missed_report(cid, key.clone());
+            }
+        }
+        T::WeightInfo::sweep_commitments(keys, commitments)
+    }
+
+    /// Records that a feeder didn't reveal its commitment of a key in time
+    fn record_missed_report(cid: CreatorId<T::AccountId>, key: OracleKeyOf<T>) {
+        <MissedReports<T>>::mutate(&cid, |missed| *missed = missed.saturating_add(1));
+        Self::deposit_event(Event::ReportMissed { feeder: cid.clone(), key });
+        Self::score_feeder(cid, Permill::zero());
+    }
+
+    /// Returns the stake of a feeder, the reserved balance of its account: the free balance can
+    /// be moved at any time, so it doesn't back the values. The parachains have no stake.
+    pub fn feeder_stake(cid: &CreatorId<T::AccountId>) -> u128 {
+        match cid {
+            CreatorId::AccountId(account) => {
+                T::Currency::reserved_balance(account).unique_saturated_into()
+            }
+            CreatorId::ParaId(_) => 0,
+        }
+    }
+
 	/// Fetch current combined value.
-	pub fn get(key: &OracleKeyOf<T>) -> Option<TimestampedValueT> {
-		Self::values(key)
+	///
+	/// Fails if the key is frozen, or if the value is older than the max age of its circuit
+	/// breaker.
+	pub fn get(key: &OracleKeyOf<T>) -> Result<TimestampedValueT, DispatchError> {
+		let value = Self::values(key).ok_or(DispatchError::CannotLookup)?;
+		match Self::value_status(key, &value) {
+			ValueStatus::Valid => Ok(value),
+			ValueStatus::Stale => Err(Error::<T>::ValueStale.into()),
+			ValueStatus::Frozen => Err(Error::<T>::ValueFrozen.into()),
+		}
+	}
+
+	/// Returns the status of the combined value of the key
+	pub fn value_status(key: &OracleKeyOf<T>, value: &TimestampedValueT) -> ValueStatus {
+		if Self::frozen(key) {
+			return ValueStatus::Frozen;
+		}
+		let max_age = Self::circuit_breakers(key).and_then(|breaker| breaker.max_age);
+		match max_age {
+			Some(max_age) if T::UnixTime::now().as_millis().saturating_sub(value.timestamp) > max_age => {
+				ValueStatus::Stale
+			}
+			_ => ValueStatus::Valid,
+		}
+	}
+
+	/// Returns false if the key is frozen, or if the combined value changes more than the max
+	/// change of its circuit breaker, which is tripped: the key is frozen.
+	fn accept_update(key: &OracleKeyOf<T>, combined: &TimestampedValueT) -> bool {
+		if Self::frozen(key) {
+			return false;
+		}
+		let max_change_bps = Self::circuit_breakers(key).and_then(|breaker| breaker.max_change_bps);
+		let (Some(max_change_bps), Some(previous)) = (max_change_bps, Self::values(key)) else {
+			return true;
+		};
+		if previous.value == 0 || previous.value == combined.value {
+			return true;
+		}
+		let change = (combined.value as i128 - previous.value as i128).unsigned_abs();
+		let change_bps = change.saturating_mul(10_000) / (previous.value as i128).unsigned_abs();
+		if change_bps <= max_change_bps as u128 {
+			return true;
+		}
+		<Frozen<T>>::insert(key, true);
+		Self::deposit_event(Event::CircuitBreakerTripped {
+			key: key.clone(),
+			previous: previous.value,
+			rejected: combined.value,
+		});
+		false
+	}
+
+	/// Fetch the time-weighted average of the combined value over the last `window`
+	/// milliseconds, timestamped now.
+	///
+	/// Returns None if the history of the key doesn't cover the window.
+	pub fn get_twap(key: &OracleKeyOf<T>, window: u128) -> Option<TimestampedValueT> {
+		let now = T::UnixTime::now().as_millis();
+		let value = Self::history(key).twap(window, now)?;
+		Some(TimestampedValue { value, timestamp: now })
 	}
 
+	/// Fetch the current combined values, None for the frozen and stale ones.
 	#[allow(clippy::complexity)]
 	pub fn get_all_values() -> Vec<(OracleKeyOf<T>, Option<TimestampedValueT>)> {
-		<Values<T>>::iter().map(|(k, v)| (k, Some(v))).collect()
+		<Values<T>>::iter()
+			.map(|(k, v)| {
+				let valid = Self::value_status(&k, &v) == ValueStatus::Valid;
+				(k, valid.then_some(v))
+			})
+			.collect()
 	}
 
 	fn combined(key: &OracleKeyOf<T>) -> Option<TimestampedValueT> {
-		let values = Self::read_raw_values(key);
-		T::CombineData::combine_data(key, values, Self::values(key))
+		match Self::combine_strategies(key) {
+			Some(strategy) => strategy.combine(
+				Self::read_feeder_values(key),
+				Self::values(key),
+				T::UnixTime::now().as_millis(),
+				Self::feeder_stake,
+				Self::reputation,
+			),
+			None => {
+				let values = Self::read_raw_values(key);
+				T::CombineData::combine_data(key, values, Self::values(key))
+			}
+		}
 	}
 
     pub fn do_submit_api(
@@ -828,13 +1739,18 @@ where T::AccountId: AsRef<[u8]>
         key: OracleKeyOf<T>,
         url: Vec<u8>,
         vpath: Vec<u8>,
+        interval_blocks: T::BlockNumber,
     ) -> DispatchResult {
         let block_number = <system::Pallet<T>>::block_number();
         let feed = ApiFeed {
                 requested_block_number: block_number,
                 url: Some(url),
                 vpath: Some(vpath),
+                interval_blocks,
             };
+        if !ApiFeeds::<T>::contains_key(&cid, &key) {
+            <ApiFeedCount<T>>::mutate(&key, |count| *count = count.saturating_add(1));
+        }
         ApiFeeds::<T>::insert(&cid, &key, feed.clone());
 
         Self::deposit_event(Event::NewApiFeed { sender: cid, key, feed });
@@ -849,6 +1765,7 @@ where T::AccountId: AsRef<[u8]>
         if feed_exists {
             let feed = Self::api_feeds(&cid, &key).unwrap();
             <ApiFeeds<T>>::remove(&cid, &key);
+            <ApiFeedCount<T>>::mutate(&key, |count| *count = count.saturating_sub(1));
             Self::deposit_event(Event::ApiFeedRemoved { sender: cid, key, feed });
             Ok(())
         } else {
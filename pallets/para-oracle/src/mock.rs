@@ -0,0 +1,180 @@
+use crate as para_oracle;
+
+use frame_support::{
+	parameter_types,
+	traits::{ConstU128, ConstU32, ConstU64, Everything, SortedMembers},
+};
+use frame_system as system;
+use sp_core::{sr25519, Pair, H256};
+use sp_runtime::{
+	testing::{Header, TestXt},
+	traits::{BlakeTwo256, Extrinsic as ExtrinsicT, IdentifyAccount, IdentityLookup, Verify},
+	MultiSignature, Permill,
+};
+
+pub(crate) type Balance = u128;
+pub(crate) type Signature = MultiSignature;
+pub(crate) type AccountId = <<Signature as Verify>::Signer as IdentifyAccount>::AccountId;
+pub(crate) type Extrinsic = TestXt<RuntimeCall, ()>;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		Timestamp: pallet_timestamp::{Pallet, Call, Storage, Inherent},
+		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+		CumulusXcm: cumulus_pallet_xcm::{Pallet, Event<T>, Origin},
+		// Pallet to test
+		ParaOracle: para_oracle::{Pallet, Call, Storage, Event<T>, ValidateUnsigned},
+	}
+);
+
+impl system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type RuntimeOrigin = RuntimeOrigin;
+	type RuntimeCall = RuntimeCall;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = AccountId;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type RuntimeEvent = RuntimeEvent;
+	type BlockHashCount = ConstU64<250>;
+	type DbWeight = ();
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<Balance>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+	type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_balances::Config for Test {
+	type Balance = Balance;
+	type DustRemoval = ();
+	type RuntimeEvent = RuntimeEvent;
+	type ExistentialDeposit = ConstU128<1>;
+	type AccountStore = System;
+	type WeightInfo = ();
+	type MaxLocks = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+}
+
+impl pallet_timestamp::Config for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = ConstU64<1>;
+	type WeightInfo = ();
+}
+
+impl cumulus_pallet_xcm::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type XcmExecutor = ();
+}
+
+impl frame_system::offchain::SigningTypes for Test {
+	type Public = <Signature as Verify>::Signer;
+	type Signature = Signature;
+}
+
+impl<LocalCall> frame_system::offchain::SendTransactionTypes<LocalCall> for Test
+where
+	RuntimeCall: From<LocalCall>,
+{
+	type OverarchingCall = RuntimeCall;
+	type Extrinsic = Extrinsic;
+}
+
+impl<LocalCall> frame_system::offchain::CreateSignedTransaction<LocalCall> for Test
+where
+	RuntimeCall: From<LocalCall>,
+{
+	fn create_transaction<C: frame_system::offchain::AppCrypto<Self::Public, Self::Signature>>(
+		call: RuntimeCall,
+		_public: <Signature as Verify>::Signer,
+		_account: AccountId,
+		nonce: u64,
+	) -> Option<(RuntimeCall, <Extrinsic as ExtrinsicT>::SignaturePayload)> {
+		Some((call, (nonce, ())))
+	}
+}
+
+/// The oracle members: the accounts of `member_pair(1)` and `member_pair(2)`
+pub struct Members;
+impl SortedMembers<AccountId> for Members {
+	fn sorted_members() -> Vec<AccountId> {
+		let mut members = vec![member(1), member(2)];
+		members.sort();
+		members
+	}
+}
+
+parameter_types! {
+	pub const UnsignedPriority: u64 = 1 << 20;
+	pub const MinimumCount: u32 = 1;
+	pub const ExpiresIn: u128 = 600_000;
+	pub const ReputationDecay: Permill = Permill::from_percent(50);
+	pub const MaxDeviation: Permill = Permill::from_percent(10);
+	pub const MinReputation: Permill = Permill::from_percent(20);
+}
+
+impl para_oracle::Config for Test {
+	type AuthorityId = para_oracle::crypto::TestAuthId;
+	type RuntimeOrigin = RuntimeOrigin;
+	type RuntimeEvent = RuntimeEvent;
+	type RuntimeCall = RuntimeCall;
+	type XcmSender = ();
+	type UnixTime = Timestamp;
+	type UnsignedPriority = UnsignedPriority;
+	type UnsignedInterval = ConstU64<2>;
+	type WeightInfo = ();
+	type EstimateCallFee = ConstU32<0>;
+	type Currency = Balances;
+	type CombineData = para_oracle::DefaultCombineData<Test, MinimumCount, ExpiresIn>;
+	type UpdateOrigin = frame_system::EnsureRoot<AccountId>;
+	type Members = Members;
+	type StrLimit = ConstU32<32>;
+	type MaxHasDispatchedSize = ConstU32<16>;
+	type HistoryDepth = ConstU32<8>;
+	type ReputationDecay = ReputationDecay;
+	type MaxDeviation = MaxDeviation;
+	type MinReputation = MinReputation;
+}
+
+/// Returns the key pair of the test account `seed`
+pub fn member_pair(seed: u8) -> sr25519::Pair {
+	sr25519::Pair::from_seed(&[seed; 32])
+}
+
+/// Returns the account of the test key pair `seed`
+pub fn member(seed: u8) -> AccountId {
+	member_pair(seed).public().into()
+}
+
+/// Build genesis storage according to the mock runtime, at block 1 and timestamp 1_000_000.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let t = system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		Timestamp::set_timestamp(1_000_000);
+	});
+	ext
+}
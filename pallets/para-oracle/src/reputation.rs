@@ -0,0 +1,18 @@
+use sp_runtime::{traits::Saturating, PerThing, Permill};
+
+/// Scores a raw value against the combined value: one if equal, decreasing linearly with the
+/// relative deviation, down to zero at `max_deviation`.
+pub fn deviation_score(value: i64, combined: i64, max_deviation: Permill) -> Permill {
+	let deviation = (value as i128).saturating_sub(combined as i128).unsigned_abs();
+	let allowed = max_deviation.mul_floor((combined as i128).unsigned_abs());
+	if allowed == 0 {
+		return if deviation == 0 { Permill::one() } else { Permill::zero() };
+	}
+	Permill::from_rational(allowed.saturating_sub(deviation), allowed)
+}
+
+/// Returns the score decayed by a new observation weighted by `alpha`:
+/// `alpha * observation + (1 - alpha) * score`.
+pub fn decay(score: Permill, observation: Permill, alpha: Permill) -> Permill {
+	(alpha * observation).saturating_add(alpha.left_from_one() * score)
+}
@@ -0,0 +1,655 @@
+use crate::{mock::*, *};
+use frame_support::{assert_noop, assert_ok, traits::Get};
+use sp_core::H256;
+use sp_core::Pair;
+use sp_runtime::{
+	traits::{BlakeTwo256, Hash, ValidateUnsigned},
+	transaction_validity::{InvalidTransaction, TransactionSource, TransactionValidityError},
+	MultiSignature, MultiSigner,
+};
+
+fn key(name: &str) -> OracleKeyOf<Test> {
+	name.as_bytes().to_vec().try_into().unwrap()
+}
+
+fn register(name: &str) {
+	assert_ok!(ParaOracle::submit_api(
+		RuntimeOrigin::signed(member(9)),
+		key(name),
+		b"https://api.example.com/price".to_vec(),
+		b"/price".to_vec(),
+		1,
+	));
+}
+
+/// Returns the `feed_data_unsigned` call of the values signed by `signer`, fetched at `block_number`
+/// by the member `seed`
+fn feed_call(seed: u8, signer: u8, block_number: u64, values: Vec<(OracleKeyOf<Test>, i64)>) -> Call<Test> {
+	let payload = FeedPayload {
+		block_number,
+		values,
+		public: MultiSigner::from(member_pair(seed).public()),
+	};
+	let signature = MultiSignature::from(member_pair(signer).sign(&payload.encode()));
+	Call::feed_data_unsigned { payload, signature }
+}
+
+fn validate(call: &Call<Test>) -> TransactionValidity {
+	<ParaOracle as ValidateUnsigned>::validate_unsigned(TransactionSource::External, call)
+}
+
+fn priority(call: &Call<Test>) -> TransactionPriority {
+	validate(call).expect("valid transaction").priority
+}
+
+fn dispatch(call: Call<Test>) {
+	let Call::feed_data_unsigned { payload, signature } = call else {
+		unreachable!();
+	};
+	assert_ok!(ParaOracle::feed_data_unsigned(RuntimeOrigin::none(), payload, signature));
+}
+
+#[test]
+fn validate_unsigned_accepts_the_payloads_of_members() {
+	new_test_ext().execute_with(|| {
+		register("BTC");
+		assert!(validate(&feed_call(1, 1, 1, vec![(key("BTC"), 100)])).is_ok());
+		assert!(validate(&feed_call(2, 2, 1, vec![(key("BTC"), 100)])).is_ok());
+	});
+}
+
+#[test]
+fn validate_unsigned_rejects_bad_signatures() {
+	new_test_ext().execute_with(|| {
+		register("BTC");
+		assert_eq!(
+			validate(&feed_call(1, 2, 1, vec![(key("BTC"), 100)])),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::BadProof))
+		);
+	});
+}
+
+#[test]
+fn validate_unsigned_rejects_non_members() {
+	new_test_ext().execute_with(|| {
+		register("BTC");
+		assert_eq!(
+			validate(&feed_call(3, 3, 1, vec![(key("BTC"), 100)])),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::BadSigner))
+		);
+	});
+}
+
+#[test]
+fn validate_unsigned_rejects_other_calls() {
+	new_test_ext().execute_with(|| {
+		let call = Call::set_frozen { key: key("BTC"), frozen: true };
+		assert_eq!(
+			validate(&call),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Call))
+		);
+	});
+}
+
+#[test]
+fn validate_unsigned_rejects_payloads_from_the_future() {
+	new_test_ext().execute_with(|| {
+		register("BTC");
+		assert_eq!(
+			validate(&feed_call(1, 1, 2, vec![(key("BTC"), 100)])),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Future))
+		);
+	});
+}
+
+#[test]
+fn validate_unsigned_enforces_the_unsigned_interval() {
+	new_test_ext().execute_with(|| {
+		register("BTC");
+		dispatch(feed_call(1, 1, 1, vec![(key("BTC"), 100)]));
+		assert_eq!(ParaOracle::next_unsigned_at(member(1)), 1 + <Test as Config>::UnsignedInterval::get());
+
+		System::set_block_number(2);
+		assert_eq!(
+			validate(&feed_call(1, 1, 2, vec![(key("BTC"), 100)])),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Stale))
+		);
+		// The other members are not throttled
+		assert!(validate(&feed_call(2, 2, 2, vec![(key("BTC"), 100)])).is_ok());
+
+		System::set_block_number(3);
+		assert!(validate(&feed_call(1, 1, 3, vec![(key("BTC"), 100)])).is_ok());
+	});
+}
+
+#[test]
+fn stale_keys_are_prioritized() {
+	new_test_ext().execute_with(|| {
+		let base = UnsignedPriority::get();
+		register("BTC");
+		register("ETH");
+		// Never fed
+		assert_eq!(priority(&feed_call(1, 1, 1, vec![(key("BTC"), 100)])), base + MAX_STALENESS_BONUS);
+
+		dispatch(feed_call(1, 1, 1, vec![(key("BTC"), 100), (key("ETH"), 10)]));
+		assert_eq!(priority(&feed_call(2, 2, 1, vec![(key("BTC"), 100)])), base);
+
+		// The age in seconds of the stalest value
+		Timestamp::set_timestamp(1_060_000);
+		assert_eq!(priority(&feed_call(2, 2, 1, vec![(key("BTC"), 100)])), base + 60);
+	});
+}
+
+#[test]
+fn staleness_bonus_is_capped() {
+	new_test_ext().execute_with(|| {
+		let base = UnsignedPriority::get();
+		register("BTC");
+		dispatch(feed_call(1, 1, 1, vec![(key("BTC"), 100)]));
+
+		Timestamp::set_timestamp(1_000_000 + 10 * 60 * 60 * 1000);
+		assert_eq!(priority(&feed_call(2, 2, 1, vec![(key("BTC"), 100)])), base + MAX_STALENESS_BONUS);
+	});
+}
+
+#[test]
+fn staleness_ignores_unregistered_keys() {
+	new_test_ext().execute_with(|| {
+		let base = UnsignedPriority::get();
+		register("BTC");
+		dispatch(feed_call(1, 1, 1, vec![(key("BTC"), 100)]));
+
+		// Never fed, but there is no API feed for it
+		assert_eq!(
+			priority(&feed_call(2, 2, 1, vec![(key("BTC"), 100), (key("FAKE"), 1)])),
+			base
+		);
+	});
+}
+
+#[test]
+fn staleness_follows_the_api_feeds_of_the_keys() {
+	new_test_ext().execute_with(|| {
+		let base = UnsignedPriority::get();
+		register("BTC");
+		// A second feed of the same key
+		assert_ok!(ParaOracle::submit_api(
+			RuntimeOrigin::signed(member(8)),
+			key("BTC"),
+			b"https://api.example.com/price".to_vec(),
+			b"/price".to_vec(),
+			1,
+		));
+		assert_eq!(ApiFeedCount::<Test>::get(key("BTC")), 2);
+
+		assert_ok!(ParaOracle::remove_api(RuntimeOrigin::signed(member(9)), key("BTC")));
+		assert_eq!(priority(&feed_call(1, 1, 1, vec![(key("BTC"), 100)])), base + MAX_STALENESS_BONUS);
+
+		assert_ok!(ParaOracle::remove_api(RuntimeOrigin::signed(member(8)), key("BTC")));
+		assert_eq!(ApiFeedCount::<Test>::get(key("BTC")), 0);
+		assert_eq!(priority(&feed_call(1, 1, 1, vec![(key("BTC"), 100)])), base);
+	});
+}
+
+#[test]
+fn staleness_only_checks_the_first_keys() {
+	new_test_ext().execute_with(|| {
+		let base = UnsignedPriority::get();
+		register("BTC");
+		// Never fed, but after the checked keys
+		let mut values: Vec<_> = (0..MAX_STALENESS_KEYS).map(|i| (key(&format!("FAKE{i}")), 1)).collect();
+		values.push((key("BTC"), 100));
+		assert_eq!(priority(&feed_call(1, 1, 1, values)), base);
+	});
+}
+
+#[test]
+fn feeds_are_due_after_their_interval_since_last_fetched() {
+	let mut ext = new_test_ext();
+	let (offchain, _) = sp_core::offchain::testing::TestOffchainExt::new();
+	ext.register_extension(sp_core::offchain::OffchainDbExt::new(offchain.clone()));
+	ext.register_extension(sp_core::offchain::OffchainWorkerExt::new(offchain));
+	ext.execute_with(|| {
+		register("BTC");
+		assert!(ParaOracle::is_feed_due(&key("BTC"), 3, 1));
+
+		ParaOracle::set_last_fetched(&key("BTC"), 1);
+		// Local to the node, not fed on chain
+		assert_eq!(ParaOracle::values(key("BTC")), None);
+		assert!(!ParaOracle::is_feed_due(&key("BTC"), 3, 3));
+		assert!(ParaOracle::is_feed_due(&key("BTC"), 3, 4));
+		// A zero interval is due every block
+		assert!(!ParaOracle::is_feed_due(&key("BTC"), 0, 1));
+		assert!(ParaOracle::is_feed_due(&key("BTC"), 0, 2));
+	});
+}
+
+#[test]
+fn twap_holds_the_value_fed_before_the_window() {
+	let value = |feeder: u8, value: i64, timestamp: u128| {
+		(CreatorId::AccountId(feeder), TimestampedValue { value, timestamp })
+	};
+	let strategy = CombineStrategy::Twap { minimum_count: 1, window: 100 };
+	// 10 holds from the start of the window at 900 until 950
+	let combined = strategy.combine(
+		vec![value(1, 10, 0), value(2, 20, 950)],
+		None,
+		1_000,
+		|_| 0,
+		|_| Permill::one(),
+	);
+	assert_eq!(combined, Some(TimestampedValue { value: 15, timestamp: 950 }));
+	// Only the latest value before the window holds
+	let combined = strategy.combine(
+		vec![value(1, 10, 0), value(3, 30, 800), value(2, 20, 950)],
+		None,
+		1_000,
+		|_| 0,
+		|_| Permill::one(),
+	);
+	assert_eq!(combined, Some(TimestampedValue { value: 25, timestamp: 950 }));
+}
+
+#[test]
+fn feeder_stake_is_the_reserved_balance() {
+	use frame_support::traits::{Currency, ReservableCurrency};
+	new_test_ext().execute_with(|| {
+		let _ = Balances::deposit_creating(&member(1), 1_000);
+		assert_eq!(ParaOracle::feeder_stake(&CreatorId::AccountId(member(1))), 0);
+
+		assert_ok!(Balances::reserve(&member(1), 300));
+		assert_eq!(ParaOracle::feeder_stake(&CreatorId::AccountId(member(1))), 300);
+		assert_eq!(ParaOracle::feeder_stake(&CreatorId::ParaId(ParaId::from(2000u32))), 0);
+	});
+}
+
+#[test]
+fn deviation_score_decreases_linearly_to_max_deviation() {
+	use crate::reputation::deviation_score;
+	let max = Permill::from_percent(10);
+	assert_eq!(deviation_score(100, 100, max), Permill::one());
+	assert_eq!(deviation_score(105, 100, max), Permill::from_percent(50));
+	assert_eq!(deviation_score(95, 100, max), Permill::from_percent(50));
+	assert_eq!(deviation_score(110, 100, max), Permill::zero());
+	assert_eq!(deviation_score(200, 100, max), Permill::zero());
+	// Relative to the magnitude of negative values too
+	assert_eq!(deviation_score(-105, -100, max), Permill::from_percent(50));
+	// No deviation is allowed around zero
+	assert_eq!(deviation_score(0, 0, max), Permill::one());
+	assert_eq!(deviation_score(1, 0, max), Permill::zero());
+	// Doesn't overflow
+	assert_eq!(deviation_score(i64::MIN, i64::MAX, max), Permill::zero());
+}
+
+#[test]
+fn decay_weights_the_new_observation_by_alpha() {
+	use crate::reputation::decay;
+	let alpha = Permill::from_percent(10);
+	assert_eq!(decay(Permill::one(), Permill::one(), alpha), Permill::one());
+	assert_eq!(decay(Permill::one(), Permill::zero(), alpha), Permill::from_percent(90));
+	assert_eq!(decay(Permill::zero(), Permill::one(), alpha), Permill::from_percent(10));
+	assert_eq!(
+		decay(Permill::from_percent(50), Permill::from_percent(100), alpha),
+		Permill::from_percent(55)
+	);
+	// Alpha one forgets the previous score, alpha zero ignores the observation
+	let (score, observation) = (Permill::from_percent(30), Permill::from_percent(70));
+	assert_eq!(decay(score, observation, Permill::one()), observation);
+	assert_eq!(decay(score, observation, Permill::zero()), score);
+}
+
+/// The weight of `on_initialize` scoring `fed` values, without commit-reveal keys
+fn on_initialize_weight(fed: u32) -> Weight {
+	<() as WeightInfo>::on_finalize()
+		.saturating_add(<() as WeightInfo>::score_feeders(fed))
+		.saturating_add(<() as WeightInfo>::sweep_commitments(0, 0))
+}
+
+#[test]
+fn feeders_are_scored_in_the_next_block() {
+	use frame_support::traits::Hooks;
+	new_test_ext().execute_with(|| {
+		register("BTC");
+		dispatch(feed_call(1, 1, 1, vec![(key("BTC"), 100)]));
+		// Median of 100 and 108
+		dispatch(feed_call(2, 2, 1, vec![(key("BTC"), 108)]));
+		assert_eq!(ParaOracle::values(key("BTC")).map(|x| x.value), Some(108));
+		ParaOracle::on_finalize(1);
+		assert_eq!(ParaOracle::reputation(&CreatorId::AccountId(member(1))), Permill::one());
+
+		System::set_block_number(2);
+		assert_eq!(ParaOracle::on_initialize(2), on_initialize_weight(2));
+		// 100 deviates by 8 of the 10 allowed: scores 20%, averaged with the previous 100%
+		assert_eq!(
+			ParaOracle::reputation(&CreatorId::AccountId(member(1))),
+			Permill::from_percent(60)
+		);
+		assert_eq!(ParaOracle::reputation(&CreatorId::AccountId(member(2))), Permill::one());
+
+		// Nothing left to score
+		assert_eq!(ParaOracle::on_initialize(3), on_initialize_weight(0));
+	});
+}
+
+fn commitment(value: i64, salt: [u8; 32]) -> H256 {
+	BlakeTwo256::hash_of(&(value, salt))
+}
+
+fn enable_commit_reveal(name: &str, round_length: u64) {
+	assert_ok!(ParaOracle::set_commit_reveal(RuntimeOrigin::root(), key(name), Some(round_length)));
+}
+
+#[test]
+fn commit_reveal_is_restricted_to_members() {
+	new_test_ext().execute_with(|| {
+		enable_commit_reveal("BTC", 10);
+		assert_noop!(
+			ParaOracle::commit_data(
+				RuntimeOrigin::signed(member(3)),
+				vec![(key("BTC"), commitment(100, [0; 32]))]
+			),
+			Error::<Test>::NoPermission
+		);
+		assert_ok!(ParaOracle::commit_data(
+			RuntimeOrigin::signed(member(1)),
+			vec![(key("BTC"), commitment(100, [0; 32]))]
+		));
+
+		System::set_block_number(10);
+		assert_noop!(
+			ParaOracle::reveal_data(RuntimeOrigin::signed(member(3)), vec![(key("BTC"), 100, [0; 32])]),
+			Error::<Test>::NoPermission
+		);
+		assert_ok!(ParaOracle::reveal_data(
+			RuntimeOrigin::signed(member(1)),
+			vec![(key("BTC"), 100, [0; 32])]
+		));
+		assert_eq!(ParaOracle::values(key("BTC")).map(|x| x.value), Some(100));
+	});
+}
+
+#[test]
+fn overwritten_commitments_are_missed() {
+	new_test_ext().execute_with(|| {
+		let cid = CreatorId::AccountId(member(1));
+		enable_commit_reveal("BTC", 10);
+		let commit = |value| {
+			assert_ok!(ParaOracle::commit_data(
+				RuntimeOrigin::signed(member(1)),
+				vec![(key("BTC"), commitment(value, [0; 32]))]
+			));
+		};
+		commit(100);
+		// Committing again in the same round replaces the commitment
+		commit(101);
+		assert_eq!(ParaOracle::missed_reports(&cid), 0);
+
+		// Committing instead of revealing
+		System::set_block_number(10);
+		commit(102);
+		assert_eq!(ParaOracle::missed_reports(&cid), 1);
+		assert_eq!(ParaOracle::reputation(&cid), Permill::from_percent(50));
+	});
+}
+
+#[test]
+fn expired_commitments_are_swept_at_the_end_of_the_reveal_round() {
+	use frame_support::traits::Hooks;
+	new_test_ext().execute_with(|| {
+		let (cid1, cid2) = (CreatorId::AccountId(member(1)), CreatorId::AccountId(member(2)));
+		enable_commit_reveal("BTC", 10);
+		for seed in [1, 2] {
+			assert_ok!(ParaOracle::commit_data(
+				RuntimeOrigin::signed(member(seed)),
+				vec![(key("BTC"), commitment(100, [0; 32]))]
+			));
+		}
+
+		// The reveal round
+		System::set_block_number(10);
+		ParaOracle::on_initialize(10);
+		assert!(ParaOracle::commitments(key("BTC"), &cid1).is_some());
+		assert_ok!(ParaOracle::reveal_data(
+			RuntimeOrigin::signed(member(2)),
+			vec![(key("BTC"), 100, [0; 32])]
+		));
+
+		System::set_block_number(19);
+		ParaOracle::on_initialize(19);
+		assert!(ParaOracle::commitments(key("BTC"), &cid1).is_some());
+
+		// The next round
+		System::set_block_number(20);
+		assert_eq!(
+			ParaOracle::on_initialize(20),
+			<() as WeightInfo>::on_finalize()
+				.saturating_add(<() as WeightInfo>::score_feeders(0))
+				.saturating_add(<() as WeightInfo>::sweep_commitments(1, 1))
+		);
+		assert_eq!(ParaOracle::commitments(key("BTC"), &cid1), None);
+		assert_eq!(ParaOracle::missed_reports(&cid1), 1);
+		assert_eq!(ParaOracle::missed_reports(&cid2), 0);
+		System::assert_has_event(Event::<Test>::ReportMissed { feeder: cid1, key: key("BTC") }.into());
+	});
+}
+
+#[test]
+fn commit_reveal_keys_are_skipped_from_plain_feeds() {
+	new_test_ext().execute_with(|| {
+		register("BTC");
+		register("ETH");
+		enable_commit_reveal("ETH", 10);
+
+		dispatch(feed_call(1, 1, 1, vec![(key("BTC"), 100), (key("ETH"), 10)]));
+		assert_eq!(ParaOracle::values(key("BTC")).map(|x| x.value), Some(100));
+		assert_eq!(ParaOracle::values(key("ETH")), None);
+
+		// Nothing left to feed
+		assert_noop!(
+			ParaOracle::feed_data(RuntimeOrigin::signed(member(2)), vec![(key("ETH"), 10)]),
+			Error::<Test>::CommitRevealRequired
+		);
+	});
+}
+
+fn feed(seed: u8, values: Vec<(OracleKeyOf<Test>, i64)>) {
+	assert_ok!(ParaOracle::feed_data(RuntimeOrigin::signed(member(seed)), values));
+}
+
+fn enable_rounds(name: &str, duration: u64, min_submissions: u32) {
+	assert_ok!(ParaOracle::set_round_config(
+		RuntimeOrigin::root(),
+		key(name),
+		Some(RoundConfig { duration, min_submissions }),
+	));
+}
+
+#[test]
+fn rounds_are_answered_from_their_own_submissions() {
+	new_test_ext().execute_with(|| {
+		enable_rounds("BTC", 5, 2);
+
+		feed(1, vec![(key("BTC"), 100)]);
+		assert_eq!(ParaOracle::latest_round_data(key("BTC")).unwrap().answer, None);
+		feed(2, vec![(key("BTC"), 110)]);
+		feed(3, vec![(key("BTC"), 120)]);
+		let round = ParaOracle::latest_round_data(key("BTC")).unwrap();
+		assert_eq!((round.round_id, round.submissions, round.answer), (1, 3, Some(110)));
+
+		// Past the deadline, the answer of the first round is carried until the second is answered
+		ParaOracle::on_finalize(1);
+		System::set_block_number(7);
+		feed(1, vec![(key("BTC"), 300)]);
+		let round = ParaOracle::latest_round_data(key("BTC")).unwrap();
+		assert_eq!((round.round_id, round.answer, round.answered_in_round), (2, Some(110), 1));
+
+		// The value of member 3 in the first round is not part of the answer
+		feed(2, vec![(key("BTC"), 310)]);
+		let round = ParaOracle::latest_round_data(key("BTC")).unwrap();
+		assert_eq!((round.round_id, round.answer, round.answered_in_round), (2, Some(310), 2));
+		System::assert_has_event(
+			Event::<Test>::RoundAnswered { key: key("BTC"), round_id: 2, answer: 310 }.into(),
+		);
+	});
+}
+
+#[test]
+fn keys_already_reported_in_the_round_are_skipped() {
+	new_test_ext().execute_with(|| {
+		enable_rounds("BTC", 5, 1);
+
+		feed(1, vec![(key("BTC"), 100)]);
+		ParaOracle::on_finalize(1);
+		System::set_block_number(2);
+		feed(1, vec![(key("BTC"), 101), (key("ETH"), 10)]);
+
+		let cid = CreatorId::AccountId(member(1));
+		assert_eq!(ParaOracle::raw_values(key("BTC"), &cid).map(|x| x.value), Some(100));
+		assert_eq!(ParaOracle::raw_values(key("ETH"), &cid).map(|x| x.value), Some(10));
+		assert_eq!(ParaOracle::latest_round_data(key("BTC")).unwrap().submissions, 1);
+		System::assert_last_event(
+			Event::<Test>::NewFeedData { sender: cid, values: vec![(key("ETH"), 10)] }.into(),
+		);
+	});
+}
+
+fn set_breaker(name: &str, max_change_bps: Option<u32>, max_age: Option<u128>) {
+	assert_ok!(ParaOracle::set_circuit_breaker(
+		RuntimeOrigin::root(),
+		key(name),
+		Some(CircuitBreaker { max_change_bps, max_age }),
+	));
+}
+
+/// Feeds the value of member 1 in the next block
+fn feed_next_block(value: i64) {
+	let block_number = System::block_number();
+	ParaOracle::on_finalize(block_number);
+	System::set_block_number(block_number + 1);
+	feed(1, vec![(key("BTC"), value)]);
+}
+
+fn sibling_origin() -> RuntimeOrigin {
+	cumulus_pallet_xcm::Origin::SiblingParachain(ParaId::from(2000u32)).into()
+}
+
+#[test]
+fn circuit_breaker_trips_above_the_max_change() {
+	new_test_ext().execute_with(|| {
+		set_breaker("BTC", Some(1_000), None);
+		feed(1, vec![(key("BTC"), 100)]);
+
+		// 10% is allowed
+		feed_next_block(110);
+		assert_eq!(ParaOracle::values(key("BTC")).map(|x| x.value), Some(110));
+		assert!(!ParaOracle::frozen(key("BTC")));
+
+		feed_next_block(122);
+		assert_eq!(ParaOracle::values(key("BTC")).map(|x| x.value), Some(110));
+		assert!(ParaOracle::frozen(key("BTC")));
+		System::assert_has_event(
+			Event::<Test>::CircuitBreakerTripped { key: key("BTC"), previous: 110, rejected: 122 }
+				.into(),
+		);
+		assert_noop!(ParaOracle::get(&key("BTC")), Error::<Test>::ValueFrozen);
+
+		// Frozen keys are not updated
+		feed_next_block(111);
+		assert_eq!(ParaOracle::values(key("BTC")).map(|x| x.value), Some(110));
+	});
+}
+
+#[test]
+fn circuit_breaker_is_bypassed_from_zero() {
+	new_test_ext().execute_with(|| {
+		set_breaker("BTC", Some(1_000), None);
+		feed(1, vec![(key("BTC"), 0)]);
+
+		feed_next_block(1_000);
+		assert_eq!(ParaOracle::values(key("BTC")).map(|x| x.value), Some(1_000));
+		assert!(!ParaOracle::frozen(key("BTC")));
+	});
+}
+
+#[test]
+fn unfreezing_resets_the_circuit_breaker() {
+	new_test_ext().execute_with(|| {
+		set_breaker("BTC", Some(1_000), None);
+		feed(1, vec![(key("BTC"), 100)]);
+		feed_next_block(200);
+		assert!(ParaOracle::frozen(key("BTC")));
+
+		assert_ok!(ParaOracle::set_frozen(RuntimeOrigin::root(), key("BTC"), false));
+		assert_eq!(ParaOracle::values(key("BTC")), None);
+
+		// The first update after the unfreeze is not compared to the rejected one
+		feed_next_block(200);
+		assert!(!ParaOracle::frozen(key("BTC")));
+		assert_eq!(ParaOracle::get(&key("BTC")).map(|x| x.value), Ok(200));
+
+		// and is the new reference
+		feed_next_block(215);
+		assert!(!ParaOracle::frozen(key("BTC")));
+		assert_eq!(ParaOracle::get(&key("BTC")).map(|x| x.value), Ok(215));
+	});
+}
+
+#[test]
+fn values_older_than_the_max_age_are_stale() {
+	new_test_ext().execute_with(|| {
+		set_breaker("BTC", None, Some(60_000));
+		feed(1, vec![(key("BTC"), 100)]);
+		let value = ParaOracle::values(key("BTC")).unwrap();
+		assert_eq!(ParaOracle::value_status(&key("BTC"), &value), ValueStatus::Valid);
+		assert_eq!(ParaOracle::get_all_values(), vec![(key("BTC"), Some(value))]);
+
+		Timestamp::set_timestamp(1_060_000);
+		assert_eq!(ParaOracle::get(&key("BTC")), Ok(value));
+
+		Timestamp::set_timestamp(1_060_001);
+		assert_eq!(ParaOracle::value_status(&key("BTC"), &value), ValueStatus::Stale);
+		assert_noop!(ParaOracle::get(&key("BTC")), Error::<Test>::ValueStale);
+		assert_eq!(ParaOracle::get_all_values(), vec![(key("BTC"), None)]);
+	});
+}
+
+#[test]
+fn frozen_values_report_their_status() {
+	new_test_ext().execute_with(|| {
+		feed(1, vec![(key("BTC"), 100)]);
+		assert_ok!(ParaOracle::set_frozen(RuntimeOrigin::root(), key("BTC"), true));
+
+		// Still stored, for the runtime API to report it as frozen
+		let value = ParaOracle::values(key("BTC")).unwrap();
+		assert_eq!(ParaOracle::value_status(&key("BTC"), &value), ValueStatus::Frozen);
+		assert_eq!(ParaOracle::get_all_values(), vec![(key("BTC"), None)]);
+	});
+}
+
+#[test]
+fn xcm_queries_fail_on_frozen_and_stale_values() {
+	new_test_ext().execute_with(|| {
+		set_breaker("BTC", None, Some(60_000));
+		feed(1, vec![(key("BTC"), 100)]);
+
+		Timestamp::set_timestamp(1_060_001);
+		assert_noop!(
+			ParaOracle::xcm_query_data(sibling_origin(), key("BTC")),
+			Error::<Test>::ValueStale
+		);
+		assert_noop!(
+			ParaOracle::xcm_query_twap(sibling_origin(), key("BTC"), 60_000),
+			Error::<Test>::ValueStale
+		);
+
+		assert_ok!(ParaOracle::set_frozen(RuntimeOrigin::root(), key("BTC"), true));
+		assert_noop!(
+			ParaOracle::xcm_query_data(sibling_origin(), key("BTC")),
+			Error::<Test>::ValueFrozen
+		);
+		assert_noop!(
+			ParaOracle::xcm_query_twap(sibling_origin(), key("BTC"), 60_000),
+			Error::<Test>::ValueFrozen
+		);
+	});
+}
@@ -16,10 +16,19 @@ use sp_std::marker::PhantomData;
 /// Weight functions needed for para_oracle.
 pub trait WeightInfo {
     fn query_data() -> Weight;
+    fn query_twap() -> Weight;
     fn feed_data(c: u32) -> Weight;
     fn on_finalize() -> Weight;
+    fn score_feeders(c: u32) -> Weight;
+    fn sweep_commitments(k: u32, c: u32) -> Weight;
     fn submit_api() -> Weight;
     fn remove_api() -> Weight;
+    fn set_combine_strategy() -> Weight;
+    fn set_commit_reveal() -> Weight;
+    fn set_round_config() -> Weight;
+    fn set_circuit_breaker() -> Weight;
+    fn set_frozen() -> Weight;
+    fn commit_data(c: u32) -> Weight;
 }
 
 /// Weights for para_oracle using the Substrate node and recommended hardware.
@@ -30,6 +39,11 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
             .saturating_add(T::DbWeight::get().reads(4 as u64))
             .saturating_add(T::DbWeight::get().writes(2 as u64))
     }
+    fn query_twap() -> Weight {
+        Weight::from_ref_time(125_000_000)
+            .saturating_add(T::DbWeight::get().reads(5 as u64))
+            .saturating_add(T::DbWeight::get().writes(2 as u64))
+    }
     fn feed_data(c: u32, ) -> Weight {
         Weight::from_ref_time(16_800_000)
 			// Standard Error: 84_000
@@ -42,6 +56,22 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
         Weight::from_ref_time(3_000_000)
 			.saturating_add(T::DbWeight::get().writes(1 as u64))
 	}
+	fn score_feeders(c: u32, ) -> Weight {
+		Weight::from_ref_time(5_000_000)
+			.saturating_add(Weight::from_ref_time(12_000_000).saturating_mul(c as u64))
+			.saturating_add(T::DbWeight::get().reads(1 as u64))
+			.saturating_add(T::DbWeight::get().reads((2 as u64).saturating_mul(c as u64)))
+			.saturating_add(T::DbWeight::get().writes(1 as u64))
+			.saturating_add(T::DbWeight::get().writes((1 as u64).saturating_mul(c as u64)))
+	}
+	fn sweep_commitments(k: u32, c: u32, ) -> Weight {
+		Weight::from_ref_time(2_000_000)
+			.saturating_add(Weight::from_ref_time(1_500_000).saturating_mul(k as u64))
+			.saturating_add(Weight::from_ref_time(10_000_000).saturating_mul(c as u64))
+			.saturating_add(T::DbWeight::get().reads((k as u64).saturating_add(1)))
+			.saturating_add(T::DbWeight::get().reads((3 as u64).saturating_mul(c as u64)))
+			.saturating_add(T::DbWeight::get().writes((3 as u64).saturating_mul(c as u64)))
+	}
     fn submit_api() -> Weight {
         Weight::from_ref_time(66_168_000)
             .saturating_add(T::DbWeight::get().reads(3 as u64))
@@ -52,6 +82,32 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
             .saturating_add(T::DbWeight::get().reads(3 as u64))
             .saturating_add(T::DbWeight::get().writes(3 as u64))
     }
+    fn set_combine_strategy() -> Weight {
+        Weight::from_ref_time(20_000_000)
+            .saturating_add(T::DbWeight::get().writes(1 as u64))
+    }
+    fn set_commit_reveal() -> Weight {
+        Weight::from_ref_time(40_000_000)
+            .saturating_add(T::DbWeight::get().writes(2 as u64))
+    }
+    fn set_round_config() -> Weight {
+        Weight::from_ref_time(20_000_000)
+            .saturating_add(T::DbWeight::get().writes(1 as u64))
+    }
+    fn set_circuit_breaker() -> Weight {
+        Weight::from_ref_time(20_000_000)
+            .saturating_add(T::DbWeight::get().writes(1 as u64))
+    }
+    fn set_frozen() -> Weight {
+        Weight::from_ref_time(20_000_000)
+            .saturating_add(T::DbWeight::get().writes(2 as u64))
+    }
+    fn commit_data(c: u32, ) -> Weight {
+        Weight::from_ref_time(16_800_000)
+			.saturating_add(Weight::from_ref_time(4_000_000).saturating_mul(c as u64))
+			.saturating_add(T::DbWeight::get().reads((2 as u64).saturating_mul(c as u64)))
+			.saturating_add(T::DbWeight::get().writes((3 as u64).saturating_mul(c as u64)))
+    }
 }
 
 // For backwards compatibility and tests
@@ -61,6 +117,11 @@ impl WeightInfo for () {
             .saturating_add(RocksDbWeight::get().reads(4 as u64))
             .saturating_add(RocksDbWeight::get().writes(2 as u64))
     }
+    fn query_twap() -> Weight {
+        Weight::from_ref_time(125_000_000)
+            .saturating_add(RocksDbWeight::get().reads(5 as u64))
+            .saturating_add(RocksDbWeight::get().writes(2 as u64))
+    }
     fn feed_data(c: u32, ) -> Weight {
 		Weight::from_ref_time(16_800_000)
 			// Standard Error: 84_000
@@ -73,6 +134,22 @@ impl WeightInfo for () {
 		Weight::from_ref_time(3_000_000)
 			.saturating_add(RocksDbWeight::get().writes(1 as u64))
 	}
+	fn score_feeders(c: u32, ) -> Weight {
+		Weight::from_ref_time(5_000_000)
+			.saturating_add(Weight::from_ref_time(12_000_000).saturating_mul(c as u64))
+			.saturating_add(RocksDbWeight::get().reads(1 as u64))
+			.saturating_add(RocksDbWeight::get().reads((2 as u64).saturating_mul(c as u64)))
+			.saturating_add(RocksDbWeight::get().writes(1 as u64))
+			.saturating_add(RocksDbWeight::get().writes((1 as u64).saturating_mul(c as u64)))
+	}
+	fn sweep_commitments(k: u32, c: u32, ) -> Weight {
+		Weight::from_ref_time(2_000_000)
+			.saturating_add(Weight::from_ref_time(1_500_000).saturating_mul(k as u64))
+			.saturating_add(Weight::from_ref_time(10_000_000).saturating_mul(c as u64))
+			.saturating_add(RocksDbWeight::get().reads((k as u64).saturating_add(1)))
+			.saturating_add(RocksDbWeight::get().reads((3 as u64).saturating_mul(c as u64)))
+			.saturating_add(RocksDbWeight::get().writes((3 as u64).saturating_mul(c as u64)))
+	}
     fn submit_api() -> Weight {
         Weight::from_ref_time(66_168_000)
             .saturating_add(RocksDbWeight::get().reads(3 as u64))
@@ -83,4 +160,30 @@ impl WeightInfo for () {
             .saturating_add(RocksDbWeight::get().reads(3 as u64))
             .saturating_add(RocksDbWeight::get().writes(3 as u64))
     }
+    fn set_combine_strategy() -> Weight {
+        Weight::from_ref_time(20_000_000)
+            .saturating_add(RocksDbWeight::get().writes(1 as u64))
+    }
+    fn set_commit_reveal() -> Weight {
+        Weight::from_ref_time(40_000_000)
+            .saturating_add(RocksDbWeight::get().writes(2 as u64))
+    }
+    fn set_round_config() -> Weight {
+        Weight::from_ref_time(20_000_000)
+            .saturating_add(RocksDbWeight::get().writes(1 as u64))
+    }
+    fn set_circuit_breaker() -> Weight {
+        Weight::from_ref_time(20_000_000)
+            .saturating_add(RocksDbWeight::get().writes(1 as u64))
+    }
+    fn set_frozen() -> Weight {
+        Weight::from_ref_time(20_000_000)
+            .saturating_add(RocksDbWeight::get().writes(2 as u64))
+    }
+    fn commit_data(c: u32, ) -> Weight {
+        Weight::from_ref_time(16_800_000)
+			.saturating_add(Weight::from_ref_time(4_000_000).saturating_mul(c as u64))
+			.saturating_add(RocksDbWeight::get().reads((2 as u64).saturating_mul(c as u64)))
+			.saturating_add(RocksDbWeight::get().writes((3 as u64).saturating_mul(c as u64)))
+    }
 }
@@ -16,6 +16,11 @@ use sp_std::{borrow::ToOwned, convert::TryFrom, convert::TryInto, prelude::*, st
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
 
+pub mod migrations;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
 pub mod types;
 
 #[frame_support::pallet]
@@ -24,7 +29,7 @@ pub mod pallet {
     use frame_support::{
         dispatch::DispatchResultWithPostInfo,
         pallet_prelude::{OptionQuery, ValueQuery, *},
-        traits::Bounded,
+        traits::{Bounded, Currency, ReservableCurrency},
     };
     use frame_system::pallet_prelude::*;
     use sp_core::H256;
@@ -41,6 +46,12 @@ pub mod pallet {
         pub url: RegistryFeedUrl<T>,
         /// The data path to read from the API response
         pub path: RegistryFeedPath<T>,
+        /// Id of the secret slot (headers / query params) held by the Phat contract.
+        ///
+        /// Secrets never touch the chain, the feed only references them by id.
+        pub secret_slot: Option<SecretSlotId>,
+        /// Optional request body. When set, the feed is fetched with a POST request.
+        pub body: Option<RegistryFeedBody<T>>,
         /// The overall status of the feed. Defaults to "Registered"
         /// and gets changed to "Active" when rollup is established.
         pub status: ApiFeedStatus,
@@ -59,12 +70,28 @@ pub mod pallet {
         type MaxKeySize: Get<u32>;
         #[pallet::constant]
         type MaxPathSize: Get<u32>;
+        #[pallet::constant]
+        type MaxBodySize: Get<u32>;
         // #[pallet::constant]
         // type MaxNameSize: Get<u32>;
+
+        /// The currency of the feed bonds
+        type Currency: ReservableCurrency<Self::AccountId>;
+        /// Amount reserved from the owner of each registered feed until it is unregistered
+        type FeedBond: Get<BalanceOf<Self>>;
+
+        /// The origin of the emergency operations, e.g. the technical committee
+        type ForceOrigin: EnsureOrigin<Self::RuntimeOrigin>;
     }
 
+    pub type BalanceOf<T> =
+        <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
     #[pallet::pallet]
     #[pallet::generate_store(pub(super) trait Store)]
+    #[pallet::storage_version(STORAGE_VERSION)]
     pub struct Pallet<T>(_);
 
     /// A 2D storage map of all feeds which the registry keeps track of.
@@ -81,6 +108,45 @@ pub mod pallet {
         ApiFeed<T>,
     >;
 
+    /// The bond reserved for each feed, released when the feed is unregistered
+    #[pallet::storage]
+    #[pallet::getter(fn feed_bonds)]
+    pub type FeedBonds<T: Config> = StorageDoubleMap<
+        _,
+        Twox64Concat,
+        T::AccountId,
+        Twox64Concat,
+        RegistryFeedKey<T>,
+        BalanceOf<T>,
+    >;
+
+    /// The reputation of each feed, moving with the feed when it's transferred
+    #[pallet::storage]
+    #[pallet::getter(fn feed_reputations)]
+    pub type FeedReputations<T: Config> = StorageDoubleMap<
+        _,
+        Twox64Concat,
+        T::AccountId,
+        Twox64Concat,
+        RegistryFeedKey<T>,
+        FeedReputation,
+        ValueQuery,
+    >;
+
+    /// The pending ownership transfers of the feeds, accepted by the new owner.
+    ///
+    /// The mapping is [Owner] -> [Feed Key] -> [New Owner].
+    #[pallet::storage]
+    #[pallet::getter(fn pending_transfers)]
+    pub type PendingTransfers<T: Config> = StorageDoubleMap<
+        _,
+        Twox64Concat,
+        T::AccountId,
+        Twox64Concat,
+        RegistryFeedKey<T>,
+        T::AccountId,
+    >;
+
     // Events
     #[pallet::event]
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
@@ -97,6 +163,31 @@ pub mod pallet {
             key: RegistryFeedKey<T>,
             feed: ApiFeed<T>,
         },
+        /// Feed updated, with the old and new values of the changed fields
+        FeedUpdated {
+            caller: T::AccountId,
+            key: RegistryFeedKey<T>,
+            url: Option<(RegistryFeedUrl<T>, RegistryFeedUrl<T>)>,
+            path: Option<(RegistryFeedPath<T>, RegistryFeedPath<T>)>,
+        },
+        /// Feed ownership transfer proposed, pending the acceptance of the new owner
+        FeedTransferProposed {
+            owner: T::AccountId,
+            key: RegistryFeedKey<T>,
+            new_owner: T::AccountId,
+        },
+        /// Feed ownership transferred, with its bond and reputation
+        FeedTransferred {
+            old_owner: T::AccountId,
+            key: RegistryFeedKey<T>,
+            new_owner: T::AccountId,
+        },
+        /// Feed slashed by `Config::ForceOrigin`
+        FeedSlashed {
+            owner: T::AccountId,
+            key: RegistryFeedKey<T>,
+            amount: BalanceOf<T>,
+        },
     }
 
     // Errors
@@ -106,6 +197,10 @@ pub mod pallet {
         NoneValue,
         /// Errors should have helpful documentation associated with them.
         StorageOverflow,
+        /// The transfer isn't proposed to the caller
+        NoPendingTransfer,
+        /// The new owner already has a feed with the same key
+        FeedAlreadyExists,
     }
 
     // Hooks
@@ -122,6 +217,20 @@ pub mod pallet {
 
             false
         }
+
+        /// Records the outcome of the feed in an oracle response, if the feed exists
+        pub fn note_feed_response(owner: &T::AccountId, key: &RegistryFeedKey<T>, succeeded: bool) {
+            if !<ApiFeeds<T>>::contains_key(owner, key) {
+                return;
+            }
+            <FeedReputations<T>>::mutate(owner, key, |reputation| {
+                if succeeded {
+                    reputation.responses = reputation.responses.saturating_add(1);
+                } else {
+                    reputation.failures = reputation.failures.saturating_add(1);
+                }
+            });
+        }
     }
 
     // Extrinsics
@@ -133,22 +242,41 @@ pub mod pallet {
         /// This origin must be root until further mechanisms for adding feeds is introduced such as
         /// Parachains adding feeds and putting up a staked amount as an economic incentive to avoid
         /// providing bad data.
+        ///
+        /// `secret_slot` references a secret (e.g. an API key header) stored in the Phat contract,
+        /// and `body` turns the request into a POST (e.g. for JSON-RPC data sources). The contract
+        /// only injects a secret into the feeds of the owners allowed by its admin, for the URLs of
+        /// the host it's bound to.
+        ///
+        /// The caller reserves `Config::FeedBond` for a new feed, released when the feed is
+        /// unregistered.
         #[pallet::call_index(0)]
-        #[pallet::weight(Weight::from_ref_time(10_000) + T::DbWeight::get().reads_writes(1,1))]
+        #[pallet::weight(Weight::from_ref_time(10_000) + T::DbWeight::get().reads_writes(2,3))]
         pub fn register_feed(
             origin: OriginFor<T>,
             key: RegistryFeedKey<T>,
             url: RegistryFeedUrl<T>,
             path: RegistryFeedPath<T>,
+            secret_slot: Option<SecretSlotId>,
+            body: Option<RegistryFeedBody<T>>,
             // TODO: add topic field
         ) -> DispatchResult {
             let who = ensure_signed(origin)?;
 
+            // A feed registered again keeps its bond
+            if !<FeedBonds<T>>::contains_key(&who, &key) {
+                let bond = T::FeedBond::get();
+                T::Currency::reserve(&who, bond)?;
+                <FeedBonds<T>>::insert(&who, &key, bond);
+            }
+
             let block_number = <frame_system::Pallet<T>>::block_number();
             let feed = ApiFeed {
                 started_at: block_number,
                 url,
                 path,
+                secret_slot,
+                body,
                 status: ApiFeedStatus::Registered,
             };
 
@@ -170,7 +298,7 @@ pub mod pallet {
         /// other scenario which would cause an feed to be removed is having bad data (getting slashed)
         /// or other uptime metrics (e.g. the feed errors out too many times).
         #[pallet::call_index(1)]
-        #[pallet::weight(Weight::from_ref_time(10_000) + T::DbWeight::get().reads_writes(1,1))]
+        #[pallet::weight(Weight::from_ref_time(10_000) + T::DbWeight::get().reads_writes(2,4))]
         pub fn unregister_feed(origin: OriginFor<T>, key: RegistryFeedKey<T>) -> DispatchResult {
             let who = ensure_signed(origin.clone())?;
 
@@ -178,6 +306,11 @@ pub mod pallet {
             // This also implicitly checks that the feed is owned by the origin account.
             if let Some(feed) = <ApiFeeds<T>>::get(&who, &key) {
                 <ApiFeeds<T>>::remove(&who, &key);
+                <PendingTransfers<T>>::remove(&who, &key);
+                <FeedReputations<T>>::remove(&who, &key);
+                if let Some(bond) = <FeedBonds<T>>::take(&who, &key) {
+                    T::Currency::unreserve(&who, bond);
+                }
                 Self::deposit_event(Event::FeedUnregistered {
                     caller: who,
                     key,
@@ -189,5 +322,134 @@ pub mod pallet {
                 Err(DispatchError::CannotLookup)
             }
         }
+
+        /// Slashes a feed providing bad data: its status becomes `ApiFeedStatus::Slashed`, so
+        /// it can't be requested anymore, and its bond is slashed.
+        ///
+        /// Can be called by `Config::ForceOrigin`.
+        #[pallet::call_index(2)]
+        #[pallet::weight(Weight::from_ref_time(10_000) + T::DbWeight::get().reads_writes(2,3))]
+        pub fn force_slash_feed(
+            origin: OriginFor<T>,
+            owner: T::AccountId,
+            key: RegistryFeedKey<T>,
+        ) -> DispatchResult {
+            T::ForceOrigin::ensure_origin(origin)?;
+
+            <ApiFeeds<T>>::try_mutate(&owner, &key, |feed| {
+                let feed = feed.as_mut().ok_or(DispatchError::CannotLookup)?;
+                feed.status = ApiFeedStatus::Slashed;
+                Ok::<_, DispatchError>(())
+            })?;
+            let amount = <FeedBonds<T>>::take(&owner, &key)
+                .map(|bond| bond.saturating_sub(T::Currency::slash_reserved(&owner, bond).1))
+                .unwrap_or_default();
+
+            Self::deposit_event(Event::FeedSlashed { owner, key, amount });
+            Ok(())
+        }
+
+        /// Updates the URL and / or the data path of a feed, keeping its identity (`started_at`,
+        /// status, bond and reputation).
+        ///
+        /// A change cancels the pending transfer of the feed, so that the new owner doesn't accept
+        /// a feed other than the one proposed.
+        ///
+        /// The origin must be the owner of the feed.
+        #[pallet::call_index(3)]
+        #[pallet::weight(Weight::from_ref_time(10_000) + T::DbWeight::get().reads_writes(1,2))]
+        pub fn update_feed(
+            origin: OriginFor<T>,
+            key: RegistryFeedKey<T>,
+            url: Option<RegistryFeedUrl<T>>,
+            path: Option<RegistryFeedPath<T>>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let (url, path) = <ApiFeeds<T>>::try_mutate(&who, &key, |feed| {
+                let feed = feed.as_mut().ok_or(DispatchError::CannotLookup)?;
+                let url = url
+                    .filter(|url| *url != feed.url)
+                    .map(|url| (sp_std::mem::replace(&mut feed.url, url.clone()), url));
+                let path = path
+                    .filter(|path| *path != feed.path)
+                    .map(|path| (sp_std::mem::replace(&mut feed.path, path.clone()), path));
+                Ok::<_, DispatchError>((url, path))
+            })?;
+            if url.is_some() || path.is_some() {
+                <PendingTransfers<T>>::remove(&who, &key);
+            }
+
+            Self::deposit_event(Event::FeedUpdated {
+                caller: who,
+                key,
+                url,
+                path,
+            });
+            Ok(())
+        }
+
+        /// Proposes to transfer a feed to `new_owner`, who must accept it with
+        /// `accept_feed_transfer`. A new proposal replaces the pending one.
+        ///
+        /// The origin must be the owner of the feed.
+        #[pallet::call_index(4)]
+        #[pallet::weight(Weight::from_ref_time(10_000) + T::DbWeight::get().reads_writes(1,1))]
+        pub fn transfer_feed(
+            origin: OriginFor<T>,
+            key: RegistryFeedKey<T>,
+            new_owner: T::AccountId,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(<ApiFeeds<T>>::contains_key(&who, &key), DispatchError::CannotLookup);
+
+            <PendingTransfers<T>>::insert(&who, &key, &new_owner);
+            Self::deposit_event(Event::FeedTransferProposed {
+                owner: who,
+                key,
+                new_owner,
+            });
+            Ok(())
+        }
+
+        /// Accepts the transfer of a feed proposed by `owner`.
+        ///
+        /// The feed moves to the caller with its reputation, and the caller reserves its bond
+        /// while the bond of the previous owner is released. Its secret slot is cleared: the
+        /// secret belongs to the previous owner, and the new owner could otherwise point the URL
+        /// to a server of their own to read it.
+        #[pallet::call_index(5)]
+        #[pallet::weight(Weight::from_ref_time(10_000) + T::DbWeight::get().reads_writes(5,8))]
+        pub fn accept_feed_transfer(
+            origin: OriginFor<T>,
+            owner: T::AccountId,
+            key: RegistryFeedKey<T>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(
+                <PendingTransfers<T>>::get(&owner, &key).as_ref() == Some(&who),
+                Error::<T>::NoPendingTransfer
+            );
+            ensure!(!<ApiFeeds<T>>::contains_key(&who, &key), Error::<T>::FeedAlreadyExists);
+            let mut feed = <ApiFeeds<T>>::take(&owner, &key).ok_or(DispatchError::CannotLookup)?;
+            feed.secret_slot = None;
+
+            if let Some(bond) = <FeedBonds<T>>::take(&owner, &key) {
+                T::Currency::reserve(&who, bond)?;
+                T::Currency::unreserve(&owner, bond);
+                <FeedBonds<T>>::insert(&who, &key, bond);
+            }
+            let reputation = <FeedReputations<T>>::take(&owner, &key);
+            <FeedReputations<T>>::insert(&who, &key, reputation);
+            <PendingTransfers<T>>::remove(&owner, &key);
+            <ApiFeeds<T>>::insert(&who, &key, feed);
+
+            Self::deposit_event(Event::FeedTransferred {
+                old_owner: owner,
+                key,
+                new_owner: who,
+            });
+            Ok(())
+        }
     }
 }
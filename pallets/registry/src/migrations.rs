@@ -0,0 +1,55 @@
+//! Storage migrations of the registry pallet
+
+use crate::{types::*, ApiFeed, ApiFeeds, Config, Pallet};
+use codec::{Decode, Encode};
+use frame_support::{
+    log,
+    traits::{Get, OnRuntimeUpgrade, StorageVersion},
+    weights::Weight,
+};
+use frame_system::pallet_prelude::BlockNumberFor;
+use sp_std::marker::PhantomData;
+
+pub mod v1 {
+    use super::*;
+
+    /// The feed before the secret slots and request bodies were introduced
+    #[derive(Encode, Decode)]
+    struct OldApiFeed<T: Config> {
+        started_at: BlockNumberFor<T>,
+        url: RegistryFeedUrl<T>,
+        path: RegistryFeedPath<T>,
+        status: ApiFeedStatus,
+    }
+
+    /// Adds the secret slot and the request body to the feeds
+    ///
+    /// The existing feeds reference no secret and are fetched with a GET request, as they were
+    /// before.
+    pub struct MigrateToV1<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+        fn on_runtime_upgrade() -> Weight {
+            if StorageVersion::get::<Pallet<T>>() != 0 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            let mut translated = 0u64;
+            ApiFeeds::<T>::translate::<OldApiFeed<T>, _>(|_, _, old| {
+                translated += 1;
+                Some(ApiFeed {
+                    started_at: old.started_at,
+                    url: old.url,
+                    path: old.path,
+                    secret_slot: None,
+                    body: None,
+                    status: old.status,
+                })
+            });
+
+            StorageVersion::new(1).put::<Pallet<T>>();
+            log::info!(target: "runtime::registry", "Migrated {} feeds to v1", translated);
+            T::DbWeight::get().reads_writes(translated + 1, translated + 1)
+        }
+    }
+}
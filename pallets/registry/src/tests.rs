@@ -0,0 +1,244 @@
+use crate::{mock::*, types::*, *};
+use codec::Encode;
+use frame_support::{
+    assert_noop, assert_ok,
+    traits::{Get, OnRuntimeUpgrade, StorageVersion},
+    BoundedVec,
+};
+use sp_runtime::DispatchError;
+
+fn bvec<S: Get<u32>>(raw: &[u8]) -> BoundedVec<u8, S> {
+    BoundedVec::truncate_from(raw.to_vec())
+}
+
+fn register_polkadot_usd(owner: u64, secret_slot: Option<u32>) -> RegistryFeedKey<Test> {
+    let key: RegistryFeedKey<Test> = bvec(b"polkadot_usd");
+    assert_ok!(Registry::register_feed(
+        RuntimeOrigin::signed(owner),
+        key.clone(),
+        bvec(b"https://api.example.com/price"),
+        bvec(b"/price"),
+        secret_slot,
+        None,
+    ));
+    key
+}
+
+#[test]
+fn force_slash_feed_works() {
+    new_test_ext().execute_with(|| {
+        let key = register_polkadot_usd(1, None);
+        assert_eq!(Balances::reserved_balance(1), 10);
+
+        assert_noop!(
+            Registry::force_slash_feed(RuntimeOrigin::signed(2), 1, key.clone()),
+            DispatchError::BadOrigin
+        );
+        assert_ok!(Registry::force_slash_feed(RuntimeOrigin::root(), 1, key.clone()));
+        assert_eq!(
+            Registry::api_feeds(1, &key).map(|feed| feed.status),
+            Some(ApiFeedStatus::Slashed)
+        );
+        assert_eq!(Balances::reserved_balance(1), 0);
+        assert_eq!(Balances::free_balance(1), 990);
+        System::assert_last_event(
+            Event::<Test>::FeedSlashed {
+                owner: 1,
+                key: key.clone(),
+                amount: 10,
+            }
+            .into(),
+        );
+
+        // The bond is slashed once
+        assert_ok!(Registry::force_slash_feed(RuntimeOrigin::root(), 1, key.clone()));
+        System::assert_last_event(
+            Event::<Test>::FeedSlashed {
+                owner: 1,
+                key,
+                amount: 0,
+            }
+            .into(),
+        );
+        assert_noop!(
+            Registry::force_slash_feed(RuntimeOrigin::root(), 1, bvec(b"unknown")),
+            DispatchError::CannotLookup
+        );
+    });
+}
+
+#[test]
+fn update_feed_works() {
+    new_test_ext().execute_with(|| {
+        let key = register_polkadot_usd(1, None);
+        System::set_block_number(2);
+
+        assert_noop!(
+            Registry::update_feed(RuntimeOrigin::signed(2), key.clone(), None, Some(bvec(b"/usd"))),
+            DispatchError::CannotLookup
+        );
+        // Only the changed fields are in the diff
+        assert_ok!(Registry::update_feed(
+            RuntimeOrigin::signed(1),
+            key.clone(),
+            Some(bvec(b"https://api.example.com/price")),
+            Some(bvec(b"/usd")),
+        ));
+        System::assert_last_event(
+            Event::<Test>::FeedUpdated {
+                caller: 1,
+                key: key.clone(),
+                url: None,
+                path: Some((bvec(b"/price"), bvec(b"/usd"))),
+            }
+            .into(),
+        );
+        let feed = Registry::api_feeds(1, &key).unwrap();
+        assert_eq!(&feed.path[..], b"/usd");
+        assert_eq!(feed.started_at, 1);
+    });
+}
+
+#[test]
+fn update_feed_cancels_the_pending_transfer() {
+    new_test_ext().execute_with(|| {
+        let key = register_polkadot_usd(1, None);
+        assert_ok!(Registry::transfer_feed(RuntimeOrigin::signed(1), key.clone(), 2));
+
+        // An update without change keeps the transfer
+        assert_ok!(Registry::update_feed(
+            RuntimeOrigin::signed(1),
+            key.clone(),
+            None,
+            Some(bvec(b"/price")),
+        ));
+        assert_eq!(Registry::pending_transfers(1, &key), Some(2));
+
+        assert_ok!(Registry::update_feed(
+            RuntimeOrigin::signed(1),
+            key.clone(),
+            Some(bvec(b"https://evil.example.com/price")),
+            None,
+        ));
+        assert_eq!(Registry::pending_transfers(1, &key), None);
+        assert_noop!(
+            Registry::accept_feed_transfer(RuntimeOrigin::signed(2), 1, key),
+            Error::<Test>::NoPendingTransfer
+        );
+    });
+}
+
+#[test]
+fn transfer_feed_works() {
+    new_test_ext().execute_with(|| {
+        let key = register_polkadot_usd(1, Some(7));
+
+        assert_noop!(
+            Registry::transfer_feed(RuntimeOrigin::signed(2), key.clone(), 3),
+            DispatchError::CannotLookup
+        );
+        assert_ok!(Registry::transfer_feed(RuntimeOrigin::signed(1), key.clone(), 2));
+        assert_eq!(Registry::pending_transfers(1, &key), Some(2));
+        System::assert_last_event(
+            Event::<Test>::FeedTransferProposed {
+                owner: 1,
+                key: key.clone(),
+                new_owner: 2,
+            }
+            .into(),
+        );
+
+        // A new proposal replaces the pending one
+        assert_ok!(Registry::transfer_feed(RuntimeOrigin::signed(1), key.clone(), 3));
+        assert_noop!(
+            Registry::accept_feed_transfer(RuntimeOrigin::signed(2), 1, key.clone()),
+            Error::<Test>::NoPendingTransfer
+        );
+        assert_ok!(Registry::transfer_feed(RuntimeOrigin::signed(1), key.clone(), 2));
+        assert_eq!(Registry::pending_transfers(1, &key), Some(2));
+    });
+}
+
+#[test]
+fn accept_feed_transfer_works() {
+    new_test_ext().execute_with(|| {
+        let key = register_polkadot_usd(1, Some(7));
+        let _ = register_polkadot_usd(3, None);
+        Registry::note_feed_response(&1, &key, true);
+        Registry::note_feed_response(&1, &key, false);
+        assert_ok!(Registry::transfer_feed(RuntimeOrigin::signed(1), key.clone(), 2));
+
+        // The new owner can't already own a feed with the same key
+        assert_ok!(Registry::transfer_feed(RuntimeOrigin::signed(3), key.clone(), 2));
+        assert_ok!(Registry::accept_feed_transfer(RuntimeOrigin::signed(2), 3, key.clone()));
+        assert_noop!(
+            Registry::accept_feed_transfer(RuntimeOrigin::signed(2), 1, key.clone()),
+            Error::<Test>::FeedAlreadyExists
+        );
+        assert_ok!(Registry::unregister_feed(RuntimeOrigin::signed(2), key.clone()));
+
+        assert_ok!(Registry::accept_feed_transfer(RuntimeOrigin::signed(2), 1, key.clone()));
+        System::assert_last_event(
+            Event::<Test>::FeedTransferred {
+                old_owner: 1,
+                key: key.clone(),
+                new_owner: 2,
+            }
+            .into(),
+        );
+        assert_eq!(Registry::api_feeds(1, &key), None);
+        assert_eq!(Registry::pending_transfers(1, &key), None);
+        let feed = Registry::api_feeds(2, &key).unwrap();
+        assert_eq!(feed.started_at, 1);
+        // The secret of the previous owner isn't handed over
+        assert_eq!(feed.secret_slot, None);
+
+        // The bond moves with the feed
+        assert_eq!(Registry::feed_bonds(1, &key), None);
+        assert_eq!(Registry::feed_bonds(2, &key), Some(10));
+        assert_eq!(Balances::reserved_balance(1), 0);
+        assert_eq!(Balances::reserved_balance(2), 10);
+        // and so does the reputation
+        assert_eq!(Registry::feed_reputations(1, &key), FeedReputation::default());
+        assert_eq!(
+            Registry::feed_reputations(2, &key),
+            FeedReputation {
+                responses: 1,
+                failures: 1,
+            }
+        );
+
+        // The transfer is consumed
+        assert_noop!(
+            Registry::accept_feed_transfer(RuntimeOrigin::signed(2), 1, key.clone()),
+            Error::<Test>::NoPendingTransfer
+        );
+    });
+}
+
+#[test]
+fn migration_to_v1_adds_the_secret_slot_and_body() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(0).put::<Registry>();
+        let key: RegistryFeedKey<Test> = bvec(b"polkadot_usd");
+        let url: RegistryFeedUrl<Test> = bvec(b"https://api.example.com/price");
+        let path: RegistryFeedPath<Test> = bvec(b"/price");
+        let old = (1u64, url.clone(), path.clone(), ApiFeedStatus::Active);
+        sp_io::storage::set(&ApiFeeds::<Test>::hashed_key_for(1, &key), &old.encode());
+
+        migrations::v1::MigrateToV1::<Test>::on_runtime_upgrade();
+
+        assert_eq!(
+            Registry::api_feeds(1, &key),
+            Some(ApiFeed {
+                started_at: 1,
+                url,
+                path,
+                secret_slot: None,
+                body: None,
+                status: ApiFeedStatus::Active,
+            })
+        );
+        assert_eq!(StorageVersion::get::<Registry>(), 1);
+    });
+}
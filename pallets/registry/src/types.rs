@@ -11,6 +11,10 @@ use frame_support::{
 pub type RegistryFeedKey<T> = BoundedVec<u8, <T as Config>::MaxKeySize>;
 pub type RegistryFeedUrl<T> = BoundedVec<u8, <T as Config>::MaxUrlSize>;
 pub type RegistryFeedPath<T> = BoundedVec<u8, <T as Config>::MaxPathSize>;
+pub type RegistryFeedBody<T> = BoundedVec<u8, <T as Config>::MaxBodySize>;
+
+/// Identifier of a secret slot held by the Phat contract fetching the feed
+pub type SecretSlotId = u32;
 // pub type RegistryFeedName<T> = BoundedVec<u8, <T as Config>::MaxNameSize>;
 
 /// Feed status
@@ -25,6 +29,16 @@ pub enum ApiFeedStatus {
     Slashed,
 }
 
+/// Track record of a feed, counting the sources of the oracle responses it was part of
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, TypeInfo, MaxEncodedLen, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct FeedReputation {
+    /// Number of responses the feed succeeded in
+    pub responses: u32,
+    /// Number of responses the feed failed in
+    pub failures: u32,
+}
+
 impl ApiFeedStatus {
     pub fn is_active(&self) -> bool {
         return *self == ApiFeedStatus::Active
@@ -6,8 +6,14 @@ pub use crate::phat_oracle_feed::*;
 
 #[ink::contract(env = pink_extension::PinkEnvironment)]
 mod phat_oracle_feed {
-    use alloc::{format, string::String, vec, vec::Vec};
+    use alloc::{
+        format,
+        string::{String, ToString},
+        vec,
+        vec::Vec,
+    };
     use ink::storage::traits::StorageLayout;
+    use ink::storage::Mapping;
     use pink_extension as pink;
     use scale::{Decode, Encode};
     use serde::Deserialize;
@@ -16,23 +22,118 @@ mod phat_oracle_feed {
     use pink::ResultExt;
 
     use phat_offchain_rollup::{
-        clients::substrate::{claim_name, get_name_owner, SubstrateRollupClient},
+        clients::substrate::{get_name_owner, SubstrateRollupClient},
         Action,
     };
+    use pink_kv_session::traits::QueueSession;
+
+    /// Time budget to fetch all the sources of a multi-source request
+    const BATCH_HTTP_TIMEOUT_MS: u64 = 5_000;
+    /// Salt of the contract-derived key signing the responses
+    const ATTESTATION_KEY_SALT: &[u8] = b"attestation_key";
+    /// The kv-store prefix of the anchor queue 0, must equal the `QueuePrefix` of the runtime
+    const QUEUE_PREFIX: &[u8] = b"_queue/";
+    /// Number of anchor queues, drained by priority (the lowest id first)
+    const QUEUE_COUNT: u8 = 2;
 
     #[ink(storage)]
     pub struct PhatOracleFeed {
         owner: AccountId,
         config: Option<Config>,
+        /// Secrets injected into the feed requests, referenced by the on-chain `secret_slot`
+        ///
+        /// The contract storage is confidential, so the secrets never leave the worker.
+        secrets: Mapping<u32, FeedSecret>,
     }
 
     /// Represent each request as a struct
+    ///
+    /// Must be aligned with the request encoded by `pallet_oracle::request`.
     #[derive(Encode, Decode, Debug)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
     pub struct RequestRecord {
         pub request_id: [u8; 32],
+        /// The owner of the feed, allowed or not to use the secret slot
+        pub owner: AccountId,
         pub url: Vec<u8>, // string - the url to query
         pub path: Vec<u8>, // string - path in JSON response
+        /// The secret slot to inject into the request, if any
+        pub secret_slot: Option<u32>,
+        /// The POST body. The request is a GET if not provided.
+        pub body: Option<Vec<u8>>,
+    }
+
+    /// A request fetching several sources and replying with their aggregate
+    #[derive(Encode, Decode, Debug)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct MultiSourceRequest {
+        pub request_id: [u8; 32],
+        /// The owner of the feeds, allowed or not to use their secret slots
+        pub owner: AccountId,
+        /// The sources to fetch
+        pub sources: Vec<FeedSource>,
+        /// Minimum number of sources which must succeed
+        pub min_sources: u32,
+        /// Sources deviating more than this from the median are dropped as outliers
+        pub max_deviation_bps: u32,
+    }
+
+    /// A source of a multi-source request
+    ///
+    /// Must be aligned with `pallet_oracle::types::FeedSource`.
+    #[derive(Encode, Decode, Debug)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct FeedSource {
+        pub url: Vec<u8>,
+        pub path: Vec<u8>,
+        /// The secret slot to inject into the request, if any
+        pub secret_slot: Option<u32>,
+        /// The POST body. The request is a GET if not provided.
+        pub body: Option<Vec<u8>>,
+    }
+
+    /// A request popped from the anchor queue
+    ///
+    /// Must be aligned with `pallet_oracle::types::PhatRequest`.
+    #[derive(Encode, Decode, Debug)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Request {
+        Single(RequestRecord),
+        MultiSource(MultiSourceRequest),
+    }
+
+    /// The aggregate of a multi-source request
+    ///
+    /// Must be aligned with `pallet_oracle::types::MultiSourceResponse`.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct MultiSourceResponse {
+        /// The median of the sources after dropping the outliers
+        pub value: u128,
+        /// The value of each source, `None` if it failed
+        pub values: Vec<Option<u128>>,
+        /// The number of sources which succeeded
+        pub succeeded: u32,
+    }
+
+    /// The secret part of a feed request, set by the owner
+    #[derive(Encode, Decode, Debug, Default, Clone)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+    pub struct FeedSecret {
+        /// The only host the secret is sent to, e.g. `api.example.com`
+        ///
+        /// The URL of a feed can be updated, so the secret must not follow the feed to another
+        /// host.
+        pub host: String,
+        /// The accounts whose feeds may use the secret
+        ///
+        /// Anyone can register a feed referencing the slot, and the path or body of a feed
+        /// chooses what the API key is spent on, so only the feeds of these owners get it.
+        pub owners: Vec<AccountId>,
+        /// Extra HTTP headers, e.g. `("x-api-key", "...")`
+        pub headers: Vec<(String, String)>,
+        /// Extra URL query params, e.g. `("apikey", "...")`
+        pub query: Vec<(String, String)>,
     }
 
     #[derive(Encode, Decode, Debug)]
@@ -46,7 +147,7 @@ mod phat_oracle_feed {
         submit_key: [u8; 32],
     }
 
-    #[derive(Encode, Decode, Debug)]
+    #[derive(Encode, Decode, Debug, PartialEq, Eq)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
     pub enum Error {
         BadOrigin,
@@ -64,6 +165,14 @@ mod phat_oracle_feed {
         FailedToDecode,
         RollupAlreadyInitialized,
         RollupConfiguredByAnotherAccount,
+        SecretNotFound,
+        SecretHostMismatch,
+        SecretNotAllowed,
+        InvalidUrl,
+        InvalidPath,
+        FailedToFetchData,
+        FailedToParseResponse,
+        NotEnoughSources,
     }
 
     type Result<T> = core::result::Result<T, Error>;
@@ -74,6 +183,7 @@ mod phat_oracle_feed {
             Self {
                 owner: Self::env().caller(),
                 config: None,
+                secrets: Mapping::default(),
             }
         }
 
@@ -108,6 +218,46 @@ mod phat_oracle_feed {
             Ok(())
         }
 
+        /// Sets the secret headers and query params of a secret slot (admin only)
+        ///
+        /// Feeds registered on-chain with `secret_slot: Some(slot)` by one of `owners` get the
+        /// secret injected when they are fetched, as long as their URL points to `host`.
+        #[ink(message)]
+        pub fn set_secret(
+            &mut self,
+            slot: u32,
+            host: String,
+            owners: Vec<AccountId>,
+            headers: Vec<(String, String)>,
+            query: Vec<(String, String)>,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            self.secrets.insert(
+                slot,
+                &FeedSecret {
+                    host: host.to_ascii_lowercase(),
+                    owners,
+                    headers,
+                    query,
+                },
+            );
+            Ok(())
+        }
+
+        /// Removes a secret slot (admin only)
+        #[ink(message)]
+        pub fn remove_secret(&mut self, slot: u32) -> Result<()> {
+            self.ensure_owner()?;
+            self.secrets.remove(slot);
+            Ok(())
+        }
+
+        /// Checks if a secret slot is set, without revealing it
+        #[ink(message)]
+        pub fn has_secret(&self, slot: u32) -> bool {
+            self.secrets.contains(slot)
+        }
+
         /// Initializes the rollup on the target blockchain if it's not done yet
         ///
         /// First, look up if the name (contract id) is already claimed on the target chain. If
@@ -131,79 +281,129 @@ mod phat_oracle_feed {
                     return Err(Error::RollupConfiguredByAnotherAccount);
                 }
             }
-            // Not initialized. Let's claim the name.
-            claim_name(
+            // Not initialized. Let's claim the name along with our attestation key.
+            let attestation_key: [u8; 32] = self
+                .attestation_public_key()
+                .try_into()
+                .or(Err(Error::InvalidKeyLength))?;
+            let tx = subrpc::create_transaction(
+                &config.submit_key,
+                "paraxiom",
                 &config.rpc,
                 config.pallet_id,
-                &contract_id,
-                &config.submit_key,
+                // `claim_name` call index
+                0u8,
+                (contract_id, Some(attestation_key)),
+                subrpc::ExtraParam::default(),
             )
-            .log_err("failed to claim name")
-            .map(Some)
-            .or(Err(Error::FailedToClaimName))
+            .log_err("failed to create claim_name tx")
+            .or(Err(Error::FailedToCreateTransaction))?;
+            subrpc::send_transaction(&config.rpc, &tx)
+                .log_err("failed to claim name")
+                .map(Some)
+                .or(Err(Error::FailedToClaimName))
         }
 
+        /// Returns the sr25519 public key the responses are signed with
+        ///
+        /// It's registered in the anchor pallet when claiming the name.
         #[ink(message)]
-        pub fn process_queue_request(&self) -> Result<()> {
+        pub fn attestation_public_key(&self) -> Vec<u8> {
+            pink::ext().get_public_key(
+                pink::chain_extension::SigType::Sr25519,
+                &Self::attestation_key(),
+            )
+        }
+
+        /// Pops a request from the anchor queues, fetches it and replies in a rollup transaction
+        ///
+        /// The queues are drained by priority: a request is only popped from a queue when all the
+        /// queues with a lower id are empty.
+        #[ink(message)]
+        pub fn process_queue_request(&self) -> Result<Option<Vec<u8>>> {
             let config = self.ensure_configured()?;
             let contract_id = self.env().account_id();
-            let mut client =
-                SubstrateRollupClient::new(&config.rpc, config.pallet_id, &contract_id, b"q/")
-                    .log_err("failed to create rollup client")
-                    .or(Err(Error::FailedToCreateClient))?;
 
-            type Req = (Vec<u8>, Vec<u8>, Vec<u8>);
-            //  get the request from the queue
-            let next_request = client.session().queue.pop()
-                .map_err(|_| Error::<T>::FailedToDecode)?;
-
-            if let Some(raw) = next_request {
-                let next_decoded = Req::decode(raw)
-                    .map_err(|_| Error::<T>::FailedToDecode)?;
-
-                let (url, path, request_id) = next_decoded;
-                
-                pink::debug!("request_id: {:?}", request_id);
-                pink::debug!("url: {:?}", url);
-                pink::debug!("path: {:?}", path);
-
-                // TODO: implement the logic
-                //  2. fetch the data from the URL
-                //  3. submit the data as a rollup action
-            } else {
-                pink::debug!("no request in the queue");
+            // get the request from the first non-empty queue
+            let mut popped = None;
+            for queue in 0..QUEUE_COUNT {
+                let mut client = SubstrateRollupClient::new(
+                    &config.rpc,
+                    config.pallet_id,
+                    &contract_id,
+                    &queue_prefix(queue),
+                )
+                .log_err("failed to create rollup client")
+                .or(Err(Error::FailedToCreateClient))?;
+                if let Some(raw) = client
+                    .session()
+                    .pop()
+                    .log_err("failed to pop the queue")
+                    .or(Err(Error::FailedToGetStorage))?
+                {
+                    pink::debug!("popped a request from queue {}", queue);
+                    popped = Some((client, raw));
+                    break;
+                }
             }
+            let Some((mut client, raw)) = popped else {
+                pink::debug!("no request in the queues");
+                return Ok(None);
+            };
+            let (request_id, data) =
+                match Request::decode(&mut &raw[..]).or(Err(Error::FailedToDecode))? {
+                    Request::Single(request) => {
+                        (request.request_id, self.fetch_request(&request)?.encode())
+                    }
+                    Request::MultiSource(request) => (
+                        request.request_id,
+                        self.fetch_multi_source(&request)?.encode(),
+                    ),
+                };
+            pink::debug!("request_id: {:?}", request_id);
 
-            Ok(())
+            let response = ResponseRecord {
+                owner: self.owner,
+                contract_id,
+                request_id,
+                data,
+                timestamp_ms: self.env().block_timestamp(),
+            };
+            client.action(Action::Reply(Self::attest(&response).encode()));
+
+            Self::submit(config, client)
         }
 
-        /// Feeds a price by a rollup transaction
+        /// Feeds a price by a rollup transaction (admin only)
+        ///
+        /// The response is signed by the attestation key, so only the owner may choose the
+        /// request. The other requests are popped from the anchor queues.
         #[ink(message)]
         pub fn feed_request(&self, request: RequestRecord) -> Result<Option<Vec<u8>>> {
+            self.ensure_owner()?;
             // Initialize a rollup client. The client tracks a "rollup transaction" that allows you
             // to read, write, and execute actions on the target chain with atomicity.
             let config = self.ensure_configured()?;
             let contract_id = self.env().account_id();
             let mut client =
-                SubstrateRollupClient::new(&config.rpc, config.pallet_id, &contract_id, b"q/")
+                SubstrateRollupClient::new(&config.rpc, config.pallet_id, &contract_id, QUEUE_PREFIX)
                     .log_err("failed to create rollup client")
                     .or(Err(Error::FailedToCreateClient))?;
 
             // Business logic starts from here.
 
-            // Get the price and respond as a rollup action.
-            // TODO: fetch data using the URL
-            let price = u128::MAX;
+            // Get the value and respond as a rollup action.
+            let value = self.fetch_request(&request)?;
 
             let response = ResponseRecord {
-                owner: self.owner.clone(),
-                contract_id: contract_id.clone(),
+                owner: self.owner,
+                contract_id,
                 request_id: request.request_id,
-                data: price.to_le_bytes().to_vec(),
+                data: value.encode(),
                 timestamp_ms: self.env().block_timestamp(),
             };
             // Attach an action to the tx by:
-            client.action(Action::Reply(response.encode()));
+            client.action(Action::Reply(Self::attest(&response).encode()));
 
             // An offchain rollup contract will get a dedicated kv store on the target blockchain.
             // The kv store can be accessed by the Phat Contract by:
@@ -215,7 +415,99 @@ mod phat_oracle_feed {
 
             // Business logic ends here.
 
-            // Submit the transaction if it's not empty
+            Self::submit(config, client)
+        }
+
+        /// Fetches a multi-source request and aggregates it (admin only)
+        ///
+        /// The request isn't read from the chain, so its owner can't be trusted with the secrets.
+        #[ink(message)]
+        pub fn fetch_multi_source_request(
+            &self,
+            request: MultiSourceRequest,
+        ) -> Result<MultiSourceResponse> {
+            self.ensure_owner()?;
+            self.fetch_multi_source(&request)
+        }
+
+        /// Fetches all the sources concurrently within `BATCH_HTTP_TIMEOUT_MS`, injecting their
+        /// secrets if any
+        ///
+        /// Failed sources are reported as `None`, and outliers are dropped from the aggregate.
+        fn fetch_multi_source(&self, request: &MultiSourceRequest) -> Result<MultiSourceResponse> {
+            let mut paths = Vec::new();
+            let mut http_requests = Vec::new();
+            for source in &request.sources {
+                let url = String::from_utf8(source.url.clone()).or(Err(Error::InvalidUrl))?;
+                let secret = match source.secret_slot {
+                    Some(slot) => self.secret_for(slot, &request.owner, &url)?,
+                    None => FeedSecret::default(),
+                };
+                paths.push(core::str::from_utf8(&source.path).or(Err(Error::InvalidPath))?);
+                let mut headers = secret.headers;
+                let (method, body) = match &source.body {
+                    Some(body) => {
+                        headers.push(("content-type".into(), "application/json".into()));
+                        ("POST", body.clone())
+                    }
+                    None => ("GET", vec![]),
+                };
+                http_requests.push(pink::chain_extension::HttpRequest {
+                    url: with_query(url, &secret.query),
+                    method: method.into(),
+                    headers,
+                    body,
+                });
+            }
+            let responses = pink::ext()
+                .batch_http_request(http_requests, BATCH_HTTP_TIMEOUT_MS)
+                .log_err("failed to batch the http requests")
+                .or(Err(Error::FailedToFetchData))?;
+
+            let values: Vec<Option<u128>> = responses
+                .into_iter()
+                .zip(paths)
+                .map(|(resp, path)| match resp {
+                    Ok(resp) if resp.status_code == 200 => extract_value(&resp.body, path).ok(),
+                    _ => None,
+                })
+                .collect();
+            let succeeded: Vec<u128> = values.iter().flatten().copied().collect();
+            if (succeeded.len() as u32) < request.min_sources {
+                return Err(Error::NotEnoughSources);
+            }
+            let value =
+                aggregate(&succeeded, request.max_deviation_bps).ok_or(Error::NotEnoughSources)?;
+            Ok(MultiSourceResponse {
+                value,
+                values,
+                succeeded: succeeded.len() as u32,
+            })
+        }
+
+        /// The contract-derived key signing the responses
+        fn attestation_key() -> Vec<u8> {
+            pink::ext().derive_sr25519_key(ATTESTATION_KEY_SALT.into())
+        }
+
+        /// Signs the encoded response with the attestation key
+        fn attest(response: &ResponseRecord) -> SignedResponse {
+            let record = response.encode();
+            let signature = pink::ext().sign(
+                pink::chain_extension::SigType::Sr25519,
+                &Self::attestation_key(),
+                &record,
+            );
+            SignedResponse {
+                record,
+                signature: signature
+                    .try_into()
+                    .expect("sr25519 signatures are 64 bytes; qed."),
+            }
+        }
+
+        /// Submits the transaction if it's not empty
+        fn submit(config: &Config, mut client: SubstrateRollupClient) -> Result<Option<Vec<u8>>> {
             let maybe_submittable = client
                 .commit()
                 .log_err("failed to commit")
@@ -230,6 +522,46 @@ mod phat_oracle_feed {
             Ok(None)
         }
 
+        /// Fetches the value of a request, injecting its secret if any
+        ///
+        /// Returns the value at `path` as a fixed-point number with
+        /// `paraxiom_feed_value::VALUE_DECIMALS` decimals.
+        fn fetch_request(&self, request: &RequestRecord) -> Result<u128> {
+            let url = String::from_utf8(request.url.clone()).or(Err(Error::InvalidUrl))?;
+            let secret = match request.secret_slot {
+                Some(slot) => self.secret_for(slot, &request.owner, &url)?,
+                None => FeedSecret::default(),
+            };
+            let url = with_query(url, &secret.query);
+            let path = core::str::from_utf8(&request.path).or(Err(Error::InvalidPath))?;
+
+            let mut headers = secret.headers;
+            let resp = match &request.body {
+                Some(body) => {
+                    headers.push(("content-type".into(), "application/json".into()));
+                    pink::http_post!(url, body.clone(), headers)
+                }
+                None => pink::http_get!(url, headers),
+            };
+            if resp.status_code != 200 {
+                pink::warn!("unexpected status code: {}", resp.status_code);
+                return Err(Error::FailedToFetchData);
+            }
+            extract_value(&resp.body, path)
+        }
+        /// Returns the secret of the slot if the feeds of `owner` may use it and `url` points to
+        /// the host the secret is bound to
+        fn secret_for(&self, slot: u32, owner: &AccountId, url: &str) -> Result<FeedSecret> {
+            let secret = self.secrets.get(slot).ok_or(Error::SecretNotFound)?;
+            if !secret.owners.contains(owner) {
+                return Err(Error::SecretNotAllowed);
+            }
+            match url_host(url) {
+                Some(host) if host.eq_ignore_ascii_case(&secret.host) => Ok(secret),
+                _ => Err(Error::SecretHostMismatch),
+            }
+        }
+
         /// Returns BadOrigin error if the caller is not the owner
         fn ensure_owner(&self) -> Result<()> {
             if self.env().caller() == self.owner {
@@ -264,16 +596,241 @@ mod phat_oracle_feed {
         timestamp_ms: u64,
     }
 
+    /// A `ResponseRecord` signed by the attestation key of the contract
+    ///
+    /// Must be aligned with `pallet_oracle::types::SignedResponse`.
+    #[derive(Debug, PartialEq, Eq, Encode, Decode, Clone)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct SignedResponse {
+        /// The encoded `ResponseRecord`
+        record: Vec<u8>,
+        /// The sr25519 signature of `record`
+        signature: [u8; 64],
+    }
+
+    /// Returns the kv-store prefix of an anchor queue
+    ///
+    /// Must be aligned with `phat_offchain_rollup::anchor::Pallet::queue_prefix`.
+    fn queue_prefix(queue: u8) -> Vec<u8> {
+        let mut prefix = QUEUE_PREFIX.to_vec();
+        if queue != 0 {
+            prefix.push(queue);
+            prefix.push(b'/');
+        }
+        prefix
+    }
+
+    /// Returns the median of the values, the mean of the two middle values (rounded down) for an
+    /// even count
+    ///
+    /// Must be aligned with `pallet_oracle::aggregation`.
+    fn median(values: &[u128]) -> Option<u128> {
+        if values.is_empty() {
+            return None;
+        }
+        let mut sorted = values.to_vec();
+        sorted.sort_unstable();
+        let mid = sorted.len() / 2;
+        if sorted.len() % 2 == 0 {
+            let (a, b) = (sorted[mid - 1], sorted[mid]);
+            Some(a / 2 + b / 2 + (a % 2 + b % 2) / 2)
+        } else {
+            Some(sorted[mid])
+        }
+    }
+
+    /// Returns the median of the values within `max_deviation_bps` of the median of all the values
+    fn aggregate(values: &[u128], max_deviation_bps: u32) -> Option<u128> {
+        let center = median(values)?;
+        let max_deviation = center.saturating_mul(max_deviation_bps as u128) / 10_000;
+        let kept: Vec<u128> = values
+            .iter()
+            .copied()
+            .filter(|v| v.abs_diff(center) <= max_deviation)
+            .collect();
+        median(&kept)
+    }
+
+    /// Returns the host of an http(s) url, without the user info and the port
+    fn url_host(url: &str) -> Option<&str> {
+        let rest = url
+            .strip_prefix("https://")
+            .or_else(|| url.strip_prefix("http://"))?;
+        let authority = rest.split(['/', '?', '#']).next()?;
+        let host = authority.rsplit('@').next()?;
+        let host = host.split(':').next()?;
+        (!host.is_empty()).then_some(host)
+    }
+
+    /// Appends the url-encoded query params to the url
+    fn with_query(mut url: String, query: &[(String, String)]) -> String {
+        for (i, (k, v)) in query.iter().enumerate() {
+            let sep = if i == 0 && !url.contains('?') { '?' } else { '&' };
+            url.push(sep);
+            url.push_str(&url_encode(k));
+            url.push('=');
+            url.push_str(&url_encode(v));
+        }
+        url
+    }
+
+    fn url_encode(raw: &str) -> String {
+        let mut encoded = String::new();
+        for b in raw.bytes() {
+            match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                    encoded.push(b as char)
+                }
+                _ => encoded.push_str(&format!("%{b:02X}")),
+            }
+        }
+        encoded
+    }
+
+    /// Reads the number at the JSON pointer `path` (e.g. `/polkadot/usd`) of the response
+    fn extract_value(body: &[u8], path: &str) -> Result<u128> {
+        paraxiom_feed_value::extract_value(body, path).map_err(|err| match err {
+            paraxiom_feed_value::Error::InvalidPath => Error::InvalidPath,
+            _ => Error::FailedToParseResponse,
+        })
+    }
+
     #[cfg(test)]
     mod tests {
         use super::*;
+        use pink::PinkEnvironment;
 
         #[ink::test]
-        fn fixed_parse() {
-            let _ = env_logger::try_init();
-            pink_extension_runtime::mock_ext::mock_all_ext();
-            let p = PhatOracleFeed::fetch_coingecko_price("polkadot", "usd").unwrap();
-            pink::warn!("Price: {p:?}");
+        fn queue_prefix_works() {
+            assert_eq!(queue_prefix(0), b"_queue/".to_vec());
+            assert_eq!(queue_prefix(1), b"_queue/\x01/".to_vec());
+        }
+
+        #[ink::test]
+        fn extract_value_works() {
+            let body = br#"{"polkadot":{"usd":5.25}}"#;
+            assert_eq!(extract_value(body, "/polkadot/usd"), Ok(5_250000000000));
+            assert_eq!(extract_value(body, "/polkadot/eur"), Err(Error::InvalidPath));
+        }
+
+        #[ink::test]
+        fn aggregate_drops_outliers() {
+            assert_eq!(median(&[4, 1, 3, 2]), Some(2));
+            assert_eq!(aggregate(&[100, 101, 99, 200], 100), Some(100));
+            assert_eq!(aggregate(&[], 100), None);
+        }
+
+        #[ink::test]
+        fn query_is_appended() {
+            let query = vec![("apikey".to_string(), "a b".to_string())];
+            assert_eq!(
+                with_query("https://x.io/p".to_string(), &query),
+                "https://x.io/p?apikey=a%20b"
+            );
+            assert_eq!(
+                with_query("https://x.io/p?a=1".to_string(), &query),
+                "https://x.io/p?a=1&apikey=a%20b"
+            );
+        }
+
+        #[ink::test]
+        fn url_host_works() {
+            assert_eq!(url_host("https://api.x.io/p?a=1"), Some("api.x.io"));
+            assert_eq!(url_host("http://api.x.io:8080"), Some("api.x.io"));
+            assert_eq!(url_host("https://api.x.io@evil.io/p"), Some("evil.io"));
+            assert_eq!(url_host("ftp://api.x.io"), None);
+            assert_eq!(url_host("https:///p"), None);
+        }
+
+        #[ink::test]
+        fn secrets_are_owner_only() {
+            let accounts = ink::env::test::default_accounts::<PinkEnvironment>();
+            let mut feed = PhatOracleFeed::default();
+            feed.set_secret(
+                1,
+                "api.x.io".to_string(),
+                vec![accounts.alice],
+                vec![("x-api-key".to_string(), "secret".to_string())],
+                vec![],
+            )
+            .unwrap();
+            assert!(feed.has_secret(1));
+
+            ink::env::test::set_caller::<PinkEnvironment>(accounts.bob);
+            assert_eq!(feed.remove_secret(1), Err(Error::BadOrigin));
+            assert!(feed.has_secret(1));
+        }
+
+        #[ink::test]
+        fn secrets_are_bound_to_their_host() {
+            let accounts = ink::env::test::default_accounts::<PinkEnvironment>();
+            let mut feed = PhatOracleFeed::default();
+            feed.set_secret(
+                1,
+                "api.x.io".to_string(),
+                vec![accounts.bob],
+                vec![("x-api-key".to_string(), "secret".to_string())],
+                vec![],
+            )
+            .unwrap();
+
+            // A feed of an allowed owner, pointing the slot to another host
+            let request = |url: &str| RequestRecord {
+                request_id: [0; 32],
+                owner: accounts.bob,
+                url: url.as_bytes().to_vec(),
+                path: b"/price".to_vec(),
+                secret_slot: Some(1),
+                body: None,
+            };
+            for url in ["https://evil.io/p", "https://api.x.io@evil.io/p", "https://api.x.io.evil.io"] {
+                assert_eq!(feed.fetch_request(&request(url)), Err(Error::SecretHostMismatch));
+            }
+            assert!(feed.secret_for(1, &accounts.bob, "https://API.x.io/p").is_ok());
+            assert_eq!(
+                feed.secret_for(2, &accounts.bob, "https://api.x.io/p").err(),
+                Some(Error::SecretNotFound)
+            );
+        }
+
+        #[ink::test]
+        fn secrets_are_bound_to_their_owners() {
+            let accounts = ink::env::test::default_accounts::<PinkEnvironment>();
+            let mut feed = PhatOracleFeed::default();
+            feed.set_secret(
+                1,
+                "api.x.io".to_string(),
+                vec![accounts.bob],
+                vec![("x-api-key".to_string(), "secret".to_string())],
+                vec![],
+            )
+            .unwrap();
+
+            // A feed registered by anyone else, referencing the slot on the bound host
+            let request = RequestRecord {
+                request_id: [0; 32],
+                owner: accounts.charlie,
+                url: b"https://api.x.io/account".to_vec(),
+                path: b"/balance".to_vec(),
+                secret_slot: Some(1),
+                body: None,
+            };
+            assert_eq!(feed.fetch_request(&request), Err(Error::SecretNotAllowed));
+            assert_eq!(
+                feed.secret_for(1, &accounts.charlie, "https://api.x.io/p").err(),
+                Some(Error::SecretNotAllowed)
+            );
+
+            // The requests not read from the chain can't claim an owner
+            ink::env::test::set_caller::<PinkEnvironment>(accounts.charlie);
+            let request = MultiSourceRequest {
+                request_id: [0; 32],
+                owner: accounts.bob,
+                sources: vec![],
+                min_sources: 1,
+                max_deviation_bps: 100,
+            };
+            assert_eq!(feed.fetch_multi_source_request(request), Err(Error::BadOrigin));
         }
 
         #[ink::test]
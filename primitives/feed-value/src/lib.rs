@@ -0,0 +1,112 @@
+//! Parsing of the oracle feed values
+//!
+//! The values of the feeds are fixed-point numbers with [`VALUE_DECIMALS`] decimals, read from
+//! the JSON responses of the feed URLs. Both the oracle Phat contract and the off-chain worker
+//! fallback of the oracle pallet read the values with this crate, so that they agree on every
+//! response.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::string::{String, ToString};
+
+/// Number of decimals of the fixed-point values
+pub const VALUE_DECIMALS: u32 = 12;
+
+/// The errors of reading a feed value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The response is not a JSON document
+    InvalidJson,
+    /// The JSON pointer doesn't point to any value of the response
+    InvalidPath,
+    /// The value is not a non-negative decimal number
+    NotANumber,
+    /// The value doesn't fit in a `u128` fixed-point number
+    Overflow,
+}
+
+impl Error {
+    /// Returns a static description of the error
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::InvalidJson => "Response JSON was not well-formatted",
+            Self::InvalidPath => "Invalid path",
+            Self::NotANumber => "Not a number",
+            Self::Overflow => "Value overflow",
+        }
+    }
+}
+
+/// Reads the number at the JSON pointer `path` (e.g. `/polkadot/usd`) of the response
+///
+/// The number may be a JSON number or a string.
+pub fn extract_value(body: &[u8], path: &str) -> Result<u128, Error> {
+    let json: serde_json::Value = serde_json::from_slice(body).or(Err(Error::InvalidJson))?;
+    let raw: String = match json.pointer(path).ok_or(Error::InvalidPath)? {
+        // Numbers are kept as strings (`arbitrary_precision`) to avoid floating point
+        serde_json::Value::Number(n) => n.to_string(),
+        serde_json::Value::String(s) => s.clone(),
+        _ => return Err(Error::NotANumber),
+    };
+    parse_fixed(&raw)
+}
+
+/// Parses a decimal string as a fixed-point number with `VALUE_DECIMALS` decimals
+///
+/// The extra decimals are truncated.
+pub fn parse_fixed(raw: &str) -> Result<u128, Error> {
+    let (int, frac) = raw.split_once('.').unwrap_or((raw, ""));
+    let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if int.is_empty() || !is_digits(int) || !is_digits(frac) {
+        return Err(Error::NotANumber);
+    }
+    let unit = 10u128.pow(VALUE_DECIMALS);
+    let mut value = int
+        .parse::<u128>()
+        .or(Err(Error::Overflow))?
+        .checked_mul(unit)
+        .ok_or(Error::Overflow)?;
+    let frac = &frac[..frac.len().min(VALUE_DECIMALS as usize)];
+    if !frac.is_empty() {
+        let scale = 10u128.pow(VALUE_DECIMALS - frac.len() as u32);
+        let frac = frac.parse::<u128>().or(Err(Error::NotANumber))?;
+        value = value.checked_add(frac * scale).ok_or(Error::Overflow)?;
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_fixed_works() {
+        assert_eq!(parse_fixed("1"), Ok(1_000_000_000_000));
+        assert_eq!(parse_fixed("1.5"), Ok(1_500_000_000_000));
+        assert_eq!(parse_fixed("5.25"), Ok(5_250_000_000_000));
+        assert_eq!(parse_fixed("0.0000000000019"), Ok(1));
+        assert_eq!(parse_fixed("0.1234567890123"), Ok(123_456_789_012));
+        assert_eq!(parse_fixed("-1"), Err(Error::NotANumber));
+        assert_eq!(parse_fixed("abc"), Err(Error::NotANumber));
+        assert_eq!(parse_fixed(".5"), Err(Error::NotANumber));
+        assert_eq!(parse_fixed("1e3"), Err(Error::NotANumber));
+        assert_eq!(parse_fixed(&u128::MAX.to_string()), Err(Error::Overflow));
+    }
+
+    #[test]
+    fn extract_value_works() {
+        let body = br#"{"polkadot":{"usd":6.25},"quote":"0.5","big":123456789.123456789123456}"#;
+        assert_eq!(extract_value(body, "/polkadot/usd"), Ok(6_250_000_000_000));
+        assert_eq!(extract_value(body, "/quote"), Ok(500_000_000_000));
+        // No floating point rounding
+        assert_eq!(
+            extract_value(body, "/big"),
+            Ok(123_456_789_123_456_789_123)
+        );
+        assert_eq!(extract_value(body, "/missing"), Err(Error::InvalidPath));
+        assert_eq!(extract_value(body, "/polkadot"), Err(Error::NotANumber));
+        assert_eq!(extract_value(b"not json", "/quote"), Err(Error::InvalidJson));
+    }
+}
@@ -0,0 +1,173 @@
+//! Chain extension exposing the oracle to the ink! contracts
+//!
+//! The functions are called with the extension id [`PARAXIOM_EXTENSION_ID`] in the upper 16 bits
+//! of the ink! extension id, the function id in the lower ones. The calls with any other extension
+//! id are forwarded to the assets chain extension.
+//!
+//! The feeds requested by a contract must be registered by the contract itself, which is done with
+//! [`FUNC_REGISTER_FEED`]. The calls are dispatched with the contract as the signed origin, so the
+//! feed bond and the request fees are paid from the contract's balance.
+//!
+//! See the `paraxiom-extension` ink! crate for the contract side.
+
+use codec::Encode;
+use frame_support::{
+    dispatch::GetDispatchInfo,
+    traits::{Get, UnfilteredDispatchable},
+};
+use frame_system::RawOrigin;
+use pallet_assets_chain_extension::substrate::AssetsExtension;
+use pallet_contracts::chain_extension::{
+    ChainExtension, Environment, Ext, InitState, RetVal, SysConfig, UncheckedFrom,
+};
+use pallet_registry::types::{RegistryFeedBody, RegistryFeedKey, SecretSlotId};
+use phat_offchain_rollup::types::QueueId;
+use sp_core::H256;
+use sp_runtime::DispatchError;
+use sp_std::vec::Vec;
+
+use crate::Runtime;
+
+/// The extension id of the Paraxiom functions
+pub const PARAXIOM_EXTENSION_ID: u16 = 0x5058;
+
+/// Returns the latest value of a topic, `fn(topic: Vec<u8>) -> Option<LatestValue>`
+pub const FUNC_LATEST_VALUE: u16 = 0x0001;
+/// Returns the responses to a request, `fn(request_id: H256) -> Vec<ResultInfo>`
+pub const FUNC_GET_RESULT: u16 = 0x0002;
+/// Requests the feed `topic` of the contract,
+/// `fn(topic: Vec<u8>, queue: QueueId, nonce: u128) -> H256`
+pub const FUNC_REQUEST: u16 = 0x0003;
+/// Registers the feed `key` owned by the contract,
+/// `fn(key: Vec<u8>, url: Vec<u8>, path: Vec<u8>, secret_slot: Option<u32>, body: Option<Vec<u8>>)`
+pub const FUNC_REGISTER_FEED: u16 = 0x0004;
+
+/// The status codes returned to the contracts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum StatusCode {
+    Success = 0,
+    /// The topic doesn't fit in a registry feed key
+    InvalidTopic = 1,
+    /// The oracle rejected the request
+    RequestFailed = 2,
+    /// A field of the feed exceeds the registry bounds
+    InvalidFeed = 3,
+    /// The registry rejected the feed
+    RegisterFailed = 4,
+}
+
+/// The Paraxiom chain extension
+///
+/// Requests are made on behalf of the calling contract: the requested feed must be registered by
+/// the contract, and any cost of the request is paid from the contract's balance. The calls are
+/// charged their dispatch weight.
+#[derive(Default)]
+pub struct ParaxiomChainExtension {
+    assets: AssetsExtension,
+}
+
+impl ChainExtension<Runtime> for ParaxiomChainExtension {
+    fn call<E>(&mut self, env: Environment<E, InitState>) -> Result<RetVal, DispatchError>
+    where
+        E: Ext<T = Runtime>,
+        <E::T as SysConfig>::AccountId: UncheckedFrom<<E::T as SysConfig>::Hash> + AsRef<[u8]>,
+    {
+        if env.ext_id() != PARAXIOM_EXTENSION_ID {
+            return self.assets.call(env);
+        }
+
+        let db_weight = <Runtime as frame_system::Config>::DbWeight::get();
+        let func_id = env.func_id();
+        let mut env = env.buf_in_buf_out();
+        match func_id {
+            FUNC_LATEST_VALUE => {
+                env.charge_weight(db_weight.reads(1))?;
+                let topic: Vec<u8> = env.read_as_unbounded(env.in_len())?;
+                let latest = pallet_oracle::Pallet::<Runtime>::latest_value(topic);
+                env.write(&latest.encode(), false, None)?;
+            }
+            FUNC_GET_RESULT => {
+                env.charge_weight(db_weight.reads(1))?;
+                let request_id: H256 = env.read_as()?;
+                let results = pallet_oracle::Pallet::<Runtime>::get_result(request_id);
+                // One more read per response
+                env.charge_weight(db_weight.reads(results.len() as u64))?;
+                env.write(&results.encode(), false, None)?;
+            }
+            FUNC_REQUEST => {
+                let (topic, queue, nonce): (Vec<u8>, QueueId, u128) =
+                    env.read_as_unbounded(env.in_len())?;
+                let Ok(registry_feed_key) = RegistryFeedKey::<Runtime>::try_from(topic) else {
+                    return Ok(RetVal::Converging(StatusCode::InvalidTopic as u32));
+                };
+                let call = pallet_oracle::Call::<Runtime>::request {
+                    registry_feed_key,
+                    queue,
+                    nonce,
+                };
+                env.charge_weight(call.get_dispatch_info().weight)?;
+                let contract = env.ext().address().clone();
+                let request_id = pallet_oracle::Pallet::<Runtime>::next_request_id(nonce);
+                if let Err(err) = call.dispatch_bypass_filter(RawOrigin::Signed(contract).into()) {
+                    log::debug!(
+                        target: "runtime::contracts",
+                        "Oracle request from contract failed: {:?}",
+                        err
+                    );
+                    return Ok(RetVal::Converging(StatusCode::RequestFailed as u32));
+                }
+                env.write(&request_id.encode(), false, None)?;
+            }
+            FUNC_REGISTER_FEED => {
+                let (key, url, path, secret_slot, body): (
+                    Vec<u8>,
+                    Vec<u8>,
+                    Vec<u8>,
+                    Option<SecretSlotId>,
+                    Option<Vec<u8>>,
+                ) = env.read_as_unbounded(env.in_len())?;
+                let call = match (
+                    key.try_into(),
+                    url.try_into(),
+                    path.try_into(),
+                    body.map(RegistryFeedBody::<Runtime>::try_from).transpose(),
+                ) {
+                    (Ok(key), Ok(url), Ok(path), Ok(body)) => {
+                        pallet_registry::Call::<Runtime>::register_feed {
+                            key,
+                            url,
+                            path,
+                            secret_slot,
+                            body,
+                        }
+                    }
+                    _ => return Ok(RetVal::Converging(StatusCode::InvalidFeed as u32)),
+                };
+                env.charge_weight(call.get_dispatch_info().weight)?;
+                let contract = env.ext().address().clone();
+                if let Err(err) = call.dispatch_bypass_filter(RawOrigin::Signed(contract).into()) {
+                    log::debug!(
+                        target: "runtime::contracts",
+                        "Feed registration from contract failed: {:?}",
+                        err
+                    );
+                    return Ok(RetVal::Converging(StatusCode::RegisterFailed as u32));
+                }
+            }
+            _ => {
+                log::error!(
+                    target: "runtime::contracts",
+                    "Called an unregistered Paraxiom func_id: {:?}",
+                    func_id
+                );
+                return Err(DispatchError::Other("Unimplemented func_id"));
+            }
+        }
+        Ok(RetVal::Converging(StatusCode::Success as u32))
+    }
+
+    fn enabled() -> bool {
+        true
+    }
+}
@@ -6,6 +6,7 @@
 #[cfg(feature = "std")]
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
+pub mod chain_extension;
 mod weights;
 pub mod xcm_config;
 use frame_system::EnsureSigned;
@@ -24,11 +25,11 @@ const fn deposit(items: u32, bytes: u32) -> Balance {
 use cumulus_pallet_parachain_system::RelayNumberStrictlyIncreases;
 use smallvec::smallvec;
 use sp_api::impl_runtime_apis;
-use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
+use sp_core::{crypto::KeyTypeId, OpaqueMetadata, H256};
 use sp_runtime::{
     create_runtime_str, generic, impl_opaque_keys,
     traits::{AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, Verify},
-    transaction_validity::{TransactionSource, TransactionValidity},
+    transaction_validity::{TransactionPriority, TransactionSource, TransactionValidity},
     ApplyExtrinsicResult, MultiSignature,
 };
 
@@ -41,7 +42,7 @@ use frame_support::{
     construct_runtime,
     dispatch::DispatchClass,
     parameter_types,
-    traits::{ConstU32, ConstU64, ConstU8, EqualPrivilegeOnly, Everything},
+    traits::{ConstU32, ConstU64, ConstU8, Contains, EqualPrivilegeOnly, Everything},
     weights::{
         ConstantMultiplier, Weight, WeightToFeeCoefficient, WeightToFeeCoefficients,
         WeightToFeePolynomial,
@@ -121,6 +122,12 @@ pub type UncheckedExtrinsic =
 /// Extrinsic type that has already been checked.
 pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, RuntimeCall, SignedExtra>;
 
+/// The storage migrations applied by the next runtime upgrade
+pub type Migrations = (
+    phat_offchain_rollup::migrations::v1::MigrateToV1<Runtime>,
+    pallet_registry::migrations::v1::MigrateToV1<Runtime>,
+);
+
 /// Executive: handles dispatch to the various modules.
 pub type Executive = frame_executive::Executive<
     Runtime,
@@ -128,6 +135,7 @@ pub type Executive = frame_executive::Executive<
     frame_system::ChainContext<Runtime>,
     Runtime,
     AllPalletsWithSystem,
+    Migrations,
 >;
 
 use pallet_oracle;
@@ -308,7 +316,7 @@ impl pallet_contracts::Config for Runtime {
     type CallStack = [pallet_contracts::Frame<Self>; 31];
     type WeightPrice = pallet_transaction_payment::Pallet<Self>;
     type WeightInfo = pallet_contracts::weights::SubstrateWeight<Self>;
-    type ChainExtension = pallet_assets_chain_extension::substrate::AssetsExtension;
+    type ChainExtension = chain_extension::ParaxiomChainExtension;
     type DeletionQueueDepth = DeletionQueueDepth;
     type DeletionWeightLimit = DeletionWeightLimit;
     type Schedule = Schedule;
@@ -557,7 +565,11 @@ impl pallet_registry::Config for Runtime {
     type MaxUrlSize = ConstU32<512>;
     type MaxKeySize = ConstU32<128>;
     type MaxPathSize = ConstU32<256>;
+    type MaxBodySize = ConstU32<1024>;
     // type MaxNameSize = ConstU32<64>;
+    type Currency = Balances;
+    type FeedBond = para_democracy::GovernedFeedBond<Runtime>;
+    type ForceOrigin = EnsureRootOrTwoThirdsTechnicalCommittee;
 }
 
 parameter_types! {
@@ -592,7 +604,57 @@ impl pallet_scheduler::Config for Runtime {
     type OriginPrivilegeCmp = EqualPrivilegeOnly;
 }
 
-impl para_democracy::Config for Runtime {}
+parameter_types! {
+    pub const OracleMaxQueues: u32 = 8;
+    pub const DefaultFeedBond: Balance = 10 * UNIT;
+    pub const DefaultQuorum: u32 = 1;
+    pub const DefaultMaxDeviationBps: u32 = 10_000;
+    /// The urgent queue (e.g. liquidation price checks) and the routine queue. The queue 0
+    /// keeps the capacity of the single queue it replaces.
+    pub DefaultQueueCapacities: Vec<u32> = vec![128, 128];
+    pub const DefaultRequestFee: Balance = 0;
+}
+
+/// Configure the governable oracle parameters (see: /pallets/para-democracy)
+impl para_democracy::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type Currency = Balances;
+    // Root, e.g. a democracy referendum
+    type GovernanceOrigin = EnsureRoot<AccountId>;
+    type MaxQueues = OracleMaxQueues;
+    type DefaultFeedBond = DefaultFeedBond;
+    type DefaultQuorum = DefaultQuorum;
+    type DefaultMaxDeviationBps = DefaultMaxDeviationBps;
+    type DefaultQueueCapacities = DefaultQueueCapacities;
+    type DefaultRequestFee = DefaultRequestFee;
+    type WeightInfo = para_democracy::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+    pub const TechnicalMotionDuration: BlockNumber = 3 * DAYS;
+    pub const TechnicalMaxProposals: u32 = 100;
+    pub const TechnicalMaxMembers: u32 = 100;
+}
+
+type TechnicalCollective = pallet_collective::Instance1;
+
+/// Configure the technical committee, in charge of the emergency oracle operations
+impl pallet_collective::Config<TechnicalCollective> for Runtime {
+    type RuntimeOrigin = RuntimeOrigin;
+    type Proposal = RuntimeCall;
+    type RuntimeEvent = RuntimeEvent;
+    type MotionDuration = TechnicalMotionDuration;
+    type MaxProposals = TechnicalMaxProposals;
+    type MaxMembers = TechnicalMaxMembers;
+    type DefaultVote = pallet_collective::PrimeDefaultVote;
+    type WeightInfo = pallet_collective::weights::SubstrateWeight<Runtime>;
+}
+
+/// Root or two thirds of the technical committee
+pub type EnsureRootOrTwoThirdsTechnicalCommittee = EitherOfDiverse<
+    EnsureRoot<AccountId>,
+    pallet_collective::EnsureProportionAtLeast<AccountId, TechnicalCollective, 2, 3>,
+>;
 
 parameter_types! {
     pub const LaunchPeriod: BlockNumber = 28 * DAYS;
@@ -629,10 +691,10 @@ impl pallet_democracy::Config for Runtime {
     type ExternalOrigin = EitherOfDiverse<EnsureRoot<AccountId>, EnsureSigned<AccountId>>;
     type ExternalMajorityOrigin = EitherOfDiverse<EnsureRoot<AccountId>, EnsureSigned<AccountId>>;
     type ExternalDefaultOrigin = EitherOfDiverse<EnsureRoot<AccountId>, EnsureSigned<AccountId>>;
-    type FastTrackOrigin = EitherOfDiverse<EnsureRoot<AccountId>, EnsureSigned<AccountId>>;
+    type FastTrackOrigin = EnsureRootOrTwoThirdsTechnicalCommittee;
     type InstantOrigin = EitherOfDiverse<EnsureRoot<AccountId>, EnsureSigned<AccountId>>;
-    type CancellationOrigin = EnsureRoot<AccountId>;
-    type CancelProposalOrigin = EnsureRoot<AccountId>;
+    type CancellationOrigin = EnsureRootOrTwoThirdsTechnicalCommittee;
+    type CancelProposalOrigin = EnsureRootOrTwoThirdsTechnicalCommittee;
     type VetoOrigin = EnsureSigned<AccountId>;
     type PalletsOrigin = OriginCaller;
     type Preimages = Preimage;
@@ -642,7 +704,11 @@ impl pallet_democracy::Config for Runtime {
 
 parameter_types! {
     pub const QueuePrefix: &'static [u8] = b"_queue/";
-    pub const QueueCapacity: u32 = 128;
+    pub const AnchorNameDeposit: Balance = deposit(1, 64);
+    pub const AnchorDepositPerItem: Balance = deposit(1, 0);
+    pub const AnchorDepositPerByte: Balance = deposit(0, 1);
+    pub const AnchorMaxStorageItems: u32 = 1024;
+    pub const AnchorMaxStorageBytes: u32 = 256 * 1024;
     pub const QuotesCount: u32 = 6;
     pub const DataCount: u32 = 6;
 }
@@ -652,7 +718,35 @@ impl pallet_anchor::Config for Runtime {
     type RuntimeEvent = RuntimeEvent;
     type OnResponse = PhatOracle;
     type QueuePrefix = QueuePrefix;
-    type QueueCapacity = QueueCapacity;
+    type QueueCapacities = para_democracy::GovernedQueueCapacities<Runtime>;
+    type Currency = Balances;
+    type NameDeposit = AnchorNameDeposit;
+    type GovernanceOrigin = EnsureRoot<AccountId>;
+    type ForceOrigin = EnsureRootOrTwoThirdsTechnicalCommittee;
+    type DepositPerItem = AnchorDepositPerItem;
+    type DepositPerByte = AnchorDepositPerByte;
+    type MaxStorageItems = AnchorMaxStorageItems;
+    type MaxStorageBytes = AnchorMaxStorageBytes;
+    type WeightInfo = phat_offchain_rollup::weights::SubstrateWeight<Runtime>;
+    type RuntimeCall = RuntimeCall;
+    type CallFilter = AnchorCallFilter;
+}
+
+/// The runtime calls the Phat contracts are allowed to dispatch through the anchor
+///
+/// This is an allowlist: new oracle calls must be added here explicitly.
+pub struct AnchorCallFilter;
+impl Contains<RuntimeCall> for AnchorCallFilter {
+    fn contains(call: &RuntimeCall) -> bool {
+        matches!(
+            call,
+            RuntimeCall::PhatOracle(
+                pallet_oracle::Call::request { .. }
+                    | pallet_oracle::Call::request_multi_source { .. }
+                    | pallet_oracle::Call::submit_response { .. }
+            )
+        )
+    }
 }
 
 /// Configure the oracle pallet (see: /pallets/offchain-rollup/oracle)
@@ -661,6 +755,132 @@ impl pallet_oracle::Config for Runtime {
     type QuotesCount = QuotesCount;
     type DataCount = DataCount;
     type OracleRandomness = RandomnessCollectiveFlip;
+    type AuthorityId = pallet_oracle::crypto::FallbackAuthId;
+    type Quorum = para_democracy::GovernedQuorum<Runtime>;
+    type MaxDeviationBps = para_democracy::GovernedMaxDeviationBps<Runtime>;
+    type RequestFee = para_democracy::GovernedRequestFee<Runtime>;
+    // The fees are burnt
+    type OnRequestFee = ();
+    type ForceOrigin = EnsureRootOrTwoThirdsTechnicalCommittee;
+    type WeightInfo = pallet_oracle::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+    pub const OracleMaxMembers: u32 = 100;
+}
+
+type OracleMembershipInstance = pallet_membership::Instance1;
+
+/// Configure the membership of the `para_oracle` feeders, managed by the technical committee
+impl pallet_membership::Config<OracleMembershipInstance> for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type AddOrigin = EnsureRootOrTwoThirdsTechnicalCommittee;
+    type RemoveOrigin = EnsureRootOrTwoThirdsTechnicalCommittee;
+    type SwapOrigin = EnsureRootOrTwoThirdsTechnicalCommittee;
+    type ResetOrigin = EnsureRootOrTwoThirdsTechnicalCommittee;
+    type PrimeOrigin = EnsureRootOrTwoThirdsTechnicalCommittee;
+    type MembershipInitialized = ();
+    type MembershipChanged = ();
+    type MaxMembers = OracleMaxMembers;
+    type WeightInfo = pallet_membership::weights::SubstrateWeight<Runtime>;
+}
+
+parameter_types! {
+    pub const ParaOracleUnsignedPriority: TransactionPriority = TransactionPriority::max_value() / 2;
+    pub const ParaOracleUnsignedInterval: BlockNumber = 2;
+    pub const ParaOracleMinimumCount: u32 = 1;
+    pub const ParaOracleExpiresIn: u128 = 60 * 60 * 1000; // 1 hour
+    pub const ParaOracleStrLimit: u32 = 64;
+    pub const ParaOracleMaxHasDispatchedSize: u32 = 100;
+    pub const ParaOracleHistoryDepth: u32 = 64;
+    pub const ParaOracleReputationDecay: Permill = Permill::from_percent(10);
+    pub const ParaOracleMaxDeviation: Permill = Permill::from_percent(10);
+    pub const ParaOracleMinReputation: Permill = Permill::from_percent(20);
+}
+
+/// Configure the para-oracle pallet (see: /pallets/para-oracle)
+impl para_oracle::Config for Runtime {
+    type AuthorityId = para_oracle::crypto::TestAuthId;
+    type RuntimeOrigin = RuntimeOrigin;
+    type RuntimeEvent = RuntimeEvent;
+    type RuntimeCall = RuntimeCall;
+    type XcmSender = xcm_config::XcmRouter;
+    type UnixTime = Timestamp;
+    type UnsignedPriority = ParaOracleUnsignedPriority;
+    type UnsignedInterval = ParaOracleUnsignedInterval;
+    type WeightInfo = para_oracle::weights::SubstrateWeight<Runtime>;
+    type EstimateCallFee = TransactionPayment;
+    type Currency = Balances;
+    type CombineData =
+        para_oracle::DefaultCombineData<Runtime, ParaOracleMinimumCount, ParaOracleExpiresIn>;
+    type UpdateOrigin = EnsureRootOrTwoThirdsTechnicalCommittee;
+    type Members = OracleProvider;
+    type StrLimit = ParaOracleStrLimit;
+    type MaxHasDispatchedSize = ParaOracleMaxHasDispatchedSize;
+    type HistoryDepth = ParaOracleHistoryDepth;
+    type ReputationDecay = ParaOracleReputationDecay;
+    type MaxDeviation = ParaOracleMaxDeviation;
+    type MinReputation = ParaOracleMinReputation;
+}
+
+impl<LocalCall> frame_system::offchain::CreateSignedTransaction<LocalCall> for Runtime
+where
+    RuntimeCall: From<LocalCall>,
+{
+    fn create_transaction<C: frame_system::offchain::AppCrypto<Self::Public, Self::Signature>>(
+        call: RuntimeCall,
+        public: <Signature as Verify>::Signer,
+        account: AccountId,
+        nonce: Index,
+    ) -> Option<(
+        RuntimeCall,
+        <UncheckedExtrinsic as sp_runtime::traits::Extrinsic>::SignaturePayload,
+    )> {
+        use codec::Encode;
+        use sp_runtime::{traits::StaticLookup, SaturatedConversion};
+
+        let period = BlockHashCount::get()
+            .checked_next_power_of_two()
+            .map(|c| c / 2)
+            .unwrap_or(2) as u64;
+        let current_block = System::block_number()
+            .saturated_into::<u64>()
+            // The `System::block_number` is initialized with `n+1`,
+            // so the actual block number is `n`.
+            .saturating_sub(1);
+        let extra: SignedExtra = (
+            frame_system::CheckNonZeroSender::<Runtime>::new(),
+            frame_system::CheckSpecVersion::<Runtime>::new(),
+            frame_system::CheckTxVersion::<Runtime>::new(),
+            frame_system::CheckGenesis::<Runtime>::new(),
+            frame_system::CheckEra::<Runtime>::from(generic::Era::mortal(period, current_block)),
+            frame_system::CheckNonce::<Runtime>::from(nonce),
+            frame_system::CheckWeight::<Runtime>::new(),
+            pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(0),
+        );
+        let raw_payload = generic::SignedPayload::new(call, extra)
+            .map_err(|e| {
+                log::warn!("Unable to create signed payload: {:?}", e);
+            })
+            .ok()?;
+        let signature = raw_payload.using_encoded(|payload| C::sign(payload, public))?;
+        let address = <Runtime as frame_system::Config>::Lookup::unlookup(account);
+        let (call, extra, _) = raw_payload.deconstruct();
+        Some((call, (address, signature, extra)))
+    }
+}
+
+impl frame_system::offchain::SigningTypes for Runtime {
+    type Public = <Signature as Verify>::Signer;
+    type Signature = Signature;
+}
+
+impl<C> frame_system::offchain::SendTransactionTypes<C> for Runtime
+where
+    RuntimeCall: From<C>,
+{
+    type Extrinsic = UncheckedExtrinsic;
+    type OverarchingCall = RuntimeCall;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -706,10 +926,12 @@ construct_runtime!(
         Democracy: pallet_democracy = 60,
         Scheduler: pallet_scheduler = 61,
         Preimage: pallet_preimage = 62,
+        ParaDemocracy: para_democracy = 63,
+        TechnicalCommittee: pallet_collective::<Instance1> = 64,
 
         // Oracle Pallets
-        // OracleProvider: pallet_membership::<Instance1>::{Pallet, Call, Storage, Event<T>} = 41,
-        // ParaOraclePallet: para_oracle = 42,
+        OracleProvider: pallet_membership::<Instance1>::{Pallet, Call, Storage, Event<T>, Config<T>} = 41,
+        ParaOracle: para_oracle = 42,
         // Rollup and Oracles
         PhatRollupAnchor: pallet_anchor = 100,
         PhatOracle: pallet_oracle = 101,
@@ -730,9 +952,25 @@ mod benches {
         [pallet_timestamp, Timestamp]
         [pallet_collator_selection, CollatorSelection]
         [cumulus_pallet_xcmp_queue, XcmpQueue]
+        [phat_offchain_rollup, PhatRollupAnchor]
+        [pallet_oracle, PhatOracle]
     );
 }
 
+/// Converts a value combined by `para_oracle` for the oracle runtime API
+fn combined_value_of(
+    value: i64,
+    timestamp: u128,
+    status: para_oracle::ValueStatus,
+) -> pallet_oracle_runtime_api::CombinedValue {
+    let status = match status {
+        para_oracle::ValueStatus::Valid => pallet_oracle_runtime_api::ValueStatus::Valid,
+        para_oracle::ValueStatus::Stale => pallet_oracle_runtime_api::ValueStatus::Stale,
+        para_oracle::ValueStatus::Frozen => pallet_oracle_runtime_api::ValueStatus::Frozen,
+    };
+    pallet_oracle_runtime_api::CombinedValue { value, timestamp, status }
+}
+
 impl_runtime_apis! {
     impl pallet_contracts::ContractsApi<Block, AccountId, Balance, BlockNumber, Hash>
         for Runtime
@@ -883,6 +1121,92 @@ impl_runtime_apis! {
         }
     }
 
+    impl phat_offchain_rollup_runtime_api::RollupAnchorApi<Block, AccountId> for Runtime {
+        fn queue_head(name: H256, queue: u8) -> u32 {
+            PhatRollupAnchor::queue_head(&name, queue)
+        }
+
+        fn queue_tail(name: H256, queue: u8) -> u32 {
+            PhatRollupAnchor::queue_tail(&name, queue)
+        }
+
+        fn queue_items(name: H256, queue: u8, from: u32, limit: u32) -> Vec<(u32, Vec<u8>)> {
+            PhatRollupAnchor::queue_items(&name, queue, from, limit)
+        }
+
+        fn get_state(name: H256, key: Vec<u8>) -> Option<Vec<u8>> {
+            PhatRollupAnchor::get_state(&name, &key)
+        }
+
+        fn names() -> Vec<H256> {
+            pallet_anchor::SubmitterByNames::<Runtime>::iter_keys().collect()
+        }
+
+        fn submitter(name: H256) -> Option<AccountId> {
+            PhatRollupAnchor::submitter_by_names(name)
+        }
+    }
+
+    impl pallet_oracle_runtime_api::OracleApi<Block, AccountId, BlockNumber> for Runtime {
+        fn get_feed(
+            owner: AccountId,
+            key: Vec<u8>,
+        ) -> Option<pallet_oracle_runtime_api::FeedInfo<AccountId, BlockNumber>> {
+            PhatOracle::get_feed(owner, key)
+        }
+
+        fn list_feeds(
+            owner: Option<AccountId>,
+            topic: Option<Vec<u8>>,
+        ) -> Vec<pallet_oracle_runtime_api::FeedInfo<AccountId, BlockNumber>> {
+            PhatOracle::list_feeds(owner, topic)
+        }
+
+        fn get_request(request_id: H256) -> Option<pallet_oracle_runtime_api::RequestInfo<AccountId>> {
+            PhatOracle::get_request(request_id)
+        }
+
+        fn get_result(request_id: H256) -> Vec<pallet_oracle_runtime_api::ResultInfo> {
+            PhatOracle::get_result(request_id)
+        }
+
+        fn latest_value(topic: Vec<u8>) -> Option<pallet_oracle_runtime_api::LatestValue<BlockNumber>> {
+            PhatOracle::latest_value(topic)
+        }
+
+        fn combined_value(key: Vec<u8>) -> Option<pallet_oracle_runtime_api::CombinedValue> {
+            let key: para_oracle::OracleKeyOf<Runtime> = key.try_into().ok()?;
+            let value = ParaOracle::values(&key)?;
+            let status = ParaOracle::value_status(&key, &value);
+            Some(combined_value_of(value.value, value.timestamp, status))
+        }
+
+        fn twap(key: Vec<u8>, window: u128) -> Option<pallet_oracle_runtime_api::CombinedValue> {
+            let key: para_oracle::OracleKeyOf<Runtime> = key.try_into().ok()?;
+            let twap = ParaOracle::get_twap(&key, window)?;
+            // The average is as fresh as the history it's computed from
+            let status = match ParaOracle::values(&key) {
+                Some(latest) => ParaOracle::value_status(&key, &latest),
+                None => para_oracle::ValueStatus::Stale,
+            };
+            Some(combined_value_of(twap.value, twap.timestamp, status))
+        }
+
+        fn latest_round_data(key: Vec<u8>) -> Option<pallet_oracle_runtime_api::RoundInfo<BlockNumber>> {
+            let key: para_oracle::OracleKeyOf<Runtime> = key.try_into().ok()?;
+            let round = ParaOracle::latest_round_data(&key)?;
+            Some(pallet_oracle_runtime_api::RoundInfo {
+                round_id: round.round_id,
+                started_at: round.started_at,
+                deadline: round.deadline,
+                submissions: round.submissions,
+                answer: round.answer,
+                updated_at: round.updated_at,
+                answered_in_round: round.answered_in_round,
+            })
+        }
+    }
+
     impl pallet_transaction_payment_rpc_runtime_api::TransactionPaymentApi<Block, Balance> for Runtime {
         fn query_info(
             uxt: <Block as BlockT>::Extrinsic,